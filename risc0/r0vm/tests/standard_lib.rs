@@ -54,6 +54,6 @@ fn stdio_outputs_in_receipt() {
 
     let receipt = load_receipt(&receipt_file);
     assert_eq!(receipt.segments.len(), 1);
-    assert!(receipt.segments[0].get_seal_bytes().len() > 0);
+    assert!(receipt.segments[0].get_seal_bytes().unwrap().len() > 0);
     receipt.verify(STANDARD_LIB_ID).unwrap();
 }
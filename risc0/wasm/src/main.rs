@@ -26,10 +26,7 @@ use risc0_zkvm::{
 #[no_mangle]
 fn _start() {
     // TODO: use a real receipt and image_id
-    let receipt = SessionReceipt {
-        segments: Vec::new(),
-        journal: Vec::new(),
-    };
+    let receipt = SessionReceipt::new(Vec::new(), Vec::new());
     let image_id = Digest::from([0; DIGEST_WORDS]);
     receipt.verify(image_id).unwrap();
 }
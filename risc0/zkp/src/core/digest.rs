@@ -15,13 +15,13 @@
 //! A generic (cross hash) digest, which is always 256 bits and composed of 8
 //! words
 
-use alloc::{format, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
 use core::fmt::{Debug, Display, Formatter};
 
 use bytemuck::{Pod, PodCastError, Zeroable};
 use hex::{FromHex, FromHexError};
 pub use risc0_zkvm_platform::WORD_SIZE;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// The number of words in the representation of a [Digest].
 pub const DIGEST_WORDS: usize = 8;
@@ -83,6 +83,14 @@ impl From<[u32; DIGEST_WORDS]> for Digest {
 }
 
 /// Create a new [Digest] from an array of bytes.
+///
+/// The bytes are the digest in memory order, i.e. the inverse of
+/// [Digest::as_bytes], and the same order as its hex encoding. For a SHA-256
+/// digest, these are the bytes of the standard hash output, e.g. an image ID
+/// as a `bytes32` passed from a smart contract. Each word is read from four
+/// bytes in the host's byte order, which is little-endian on every supported
+/// platform including the zkVM; i.e. `bytes[0..4]` are word 0 as given by
+/// [u32::from_le_bytes], rather than as a big-endian number.
 impl From<[u8; DIGEST_BYTES]> for Digest {
     fn from(data: [u8; DIGEST_BYTES]) -> Self {
         match bytemuck::try_cast(data) {
@@ -226,11 +234,42 @@ impl Debug for Digest {
     }
 }
 
+/// Serializes a [Digest] as a hex string.
+///
+/// The default serde representation of a [Digest] is an array of words; this
+/// is intended for use with `#[serde(serialize_with = ...)]` on fields that
+/// should be rendered as hex instead, e.g. in JSON.
+pub fn serialize_digest_hex<S: Serializer>(
+    digest: &Digest,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(digest))
+}
+
+/// Deserializes a [Digest] from a hex string, with or without a `0x` prefix.
+pub fn deserialize_digest_hex<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Digest, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    let hex = hex.strip_prefix("0x").unwrap_or(&hex);
+    Digest::from_hex(hex).map_err(serde::de::Error::custom)
+}
+
+/// Serde helpers for representing a [Digest] as a hex string.
+///
+/// Use as `#[serde(with = "risc0_zkp::core::digest::digest_hex")]`.
+pub mod digest_hex {
+    pub use super::{deserialize_digest_hex as deserialize, serialize_digest_hex as serialize};
+}
+
 #[cfg(test)]
 mod tests {
+    use alloc::format;
+
     use hex::FromHex;
+    use serde::de::value::{Error, StrDeserializer};
 
-    use super::Digest;
+    use super::{deserialize_digest_hex, Digest, DIGEST_BYTES};
 
     #[test]
     fn test_from_hex() {
@@ -250,9 +289,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_bytes() {
+        let bytes: [u8; DIGEST_BYTES] = core::array::from_fn(|i| i as u8);
+        let digest = Digest::from(bytes);
+        assert_eq!(digest.as_bytes(), bytes);
+        assert_eq!(digest.as_words()[0], u32::from_le_bytes([0, 1, 2, 3]));
+        assert_eq!(hex::encode(digest), hex::encode(bytes));
+    }
+
     #[test]
     fn test_roundtrip() {
         const HEX: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
         assert_eq!(hex::encode(Digest::from_hex(HEX).unwrap()), HEX);
     }
+
+    #[test]
+    fn test_deserialize_hex() {
+        const HEX: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let expected = Digest::from_hex(HEX).unwrap();
+        for hex in [HEX.into(), format!("0x{HEX}")] {
+            let deserializer = StrDeserializer::<Error>::new(&hex);
+            assert_eq!(deserialize_digest_hex(deserializer).unwrap(), expected);
+        }
+        let deserializer = StrDeserializer::<Error>::new("0xzz");
+        assert!(deserialize_digest_hex(deserializer).is_err());
+    }
 }
@@ -13,7 +13,7 @@
 // limitations under the License.
 
 //! A Blake2b HashSuite.
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use core::marker::PhantomData;
 
 use blake2::{
@@ -84,8 +84,8 @@ impl<T: Blake2b + 'static> Blake2bHashSuite<T> {
     pub fn new() -> HashSuite<BabyBear> {
         HashSuite {
             name: "blake2b".into(),
-            hashfn: Box::new(Blake2bHashFn::<T>::new()),
-            rng: Box::new(Blake2bRngFactory::<T>::new()),
+            hashfn: Arc::new(Blake2bHashFn::<T>::new()),
+            rng: Arc::new(Blake2bRngFactory::<T>::new()),
         }
     }
 }
@@ -126,6 +126,10 @@ impl<T: Blake2b> HashFn<BabyBear> for Blake2bHashFn<T> {
         }
         Box::new(Digest::from(T::blake2b(data)))
     }
+
+    fn collision_bits(&self) -> u32 {
+        128
+    }
 }
 
 /// Blake2b-based random number generator.
@@ -20,7 +20,7 @@ pub mod poseidon;
 pub mod poseidon_254;
 pub mod sha;
 
-use alloc::{boxed::Box, string::String};
+use alloc::{boxed::Box, string::String, sync::Arc};
 
 use risc0_core::field::Field;
 
@@ -38,6 +38,15 @@ pub trait HashFn<F: Field>: Send + Sync {
     /// Generate a hash from a slice of extension field element.  This may be
     /// unpadded so this is only safe to used when the size is known.
     fn hash_ext_elem_slice(&self, slice: &[F::ExtElem]) -> Box<Digest>;
+
+    /// The collision resistance of this hash function, in bits.
+    ///
+    /// This bounds the security of any proof that commits to its data with
+    /// this hash function. Hash functions that don't override this claim no
+    /// security.
+    fn collision_bits(&self) -> u32 {
+        0
+    }
 }
 
 /// A trait that sets the PRNG used by Fiat-Shamir.  We allow specialization at
@@ -59,19 +68,32 @@ pub trait Rng<F: Field> {
 }
 
 /// Responsible for constructing new Rngs.
-pub trait RngFactory<F: Field> {
+pub trait RngFactory<F: Field>: Send + Sync {
     /// Construct a new Rng
     fn new_rng(&self) -> Box<dyn Rng<F>>;
 }
 
 /// Make it easy compute both hash related traits from a single source
+///
+/// A HashSuite is `Send + Sync`, and cloning one is cheap since the hash
+/// function and RNG factory are shared rather than copied.
 pub struct HashSuite<F: Field> {
     /// The name of this HashSuite.
     pub name: String,
 
     /// Define the hash used by the HashSuite
-    pub hashfn: Box<dyn HashFn<F>>,
+    pub hashfn: Arc<dyn HashFn<F>>,
 
     /// Define an RNG factory
-    pub rng: Box<dyn RngFactory<F>>,
+    pub rng: Arc<dyn RngFactory<F>>,
+}
+
+impl<F: Field> Clone for HashSuite<F> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            hashfn: self.hashfn.clone(),
+            rng: self.rng.clone(),
+        }
+    }
 }
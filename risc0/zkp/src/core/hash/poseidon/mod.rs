@@ -18,7 +18,7 @@
 pub(crate) mod consts;
 mod rng;
 
-use alloc::{boxed::Box, vec::Vec};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 
 use risc0_core::field::{
     baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem, Elem},
@@ -63,6 +63,11 @@ impl HashFn<BabyBear> for PoseidonHashFn {
             slice.iter().map(|ee| ee.subelems().iter()).flatten(),
         ))
     }
+
+    // A digest is 8 BabyBear elements, of just under 31 bits each.
+    fn collision_bits(&self) -> u32 {
+        124
+    }
 }
 
 struct PoseidonRngFactory;
@@ -78,11 +83,14 @@ pub struct PoseidonHashSuite;
 
 impl PoseidonHashSuite {
     /// Construct a new PoseidonHashSuite
+    ///
+    /// The round constants and MDS matrix are compile-time constants, so
+    /// there is nothing to precompute or cache here.
     pub fn new() -> HashSuite<BabyBear> {
         HashSuite {
             name: "poseidon".into(),
-            hashfn: Box::new(PoseidonHashFn {}),
-            rng: Box::new(PoseidonRngFactory {}),
+            hashfn: Arc::new(PoseidonHashFn {}),
+            rng: Arc::new(PoseidonRngFactory {}),
         }
     }
 }
@@ -16,6 +16,8 @@
 //! security of 128 bits.
 pub(crate) mod consts;
 
+use alloc::sync::Arc;
+
 use risc0_core::field::{
     baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem},
     Elem, ExtElem,
@@ -152,6 +154,11 @@ impl HashFn<BabyBear> for Poseidon254HashFn {
             slice.iter().map(|ee| ee.subelems().iter()).flatten(),
         ))
     }
+
+    // A digest is a single element of the 254-bit BN254 scalar field.
+    fn collision_bits(&self) -> u32 {
+        127
+    }
 }
 
 /// An rng implemention for Poseidon in a Snark friendly field
@@ -226,8 +233,8 @@ impl Poseidon254HashSuite {
     pub fn new() -> HashSuite<BabyBear> {
         HashSuite {
             name: "poseidon254".into(),
-            hashfn: Box::new(Poseidon254HashFn {}),
-            rng: Box::new(PoseidonRngFactory {}),
+            hashfn: Arc::new(Poseidon254HashFn {}),
+            rng: Arc::new(PoseidonRngFactory {}),
         }
     }
 }
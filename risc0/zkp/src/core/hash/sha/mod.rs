@@ -21,7 +21,7 @@ mod rng;
 pub mod rust_crypto;
 
 #[cfg(not(target_os = "zkvm"))]
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc};
 use alloc::{format, vec::Vec};
 use core::{
     fmt::{Debug, Display, Formatter},
@@ -318,6 +318,10 @@ impl<F: Field> super::HashFn<F> for Sha256HashFn {
     fn hash_ext_elem_slice(&self, slice: &[F::ExtElem]) -> Box<Digest> {
         cpu::Impl::hash_raw_pod_slice(slice)
     }
+
+    fn collision_bits(&self) -> u32 {
+        128
+    }
 }
 
 #[cfg(not(target_os = "zkvm"))]
@@ -341,8 +345,8 @@ impl<F: Field> Sha256HashSuite<F> {
     pub fn new() -> super::HashSuite<F> {
         super::HashSuite {
             name: "sha-256".into(),
-            hashfn: Box::new(Sha256HashFn {}),
-            rng: Box::new(Sha256RngFactory {}),
+            hashfn: Arc::new(Sha256HashFn {}),
+            rng: Arc::new(Sha256RngFactory {}),
         }
     }
 }
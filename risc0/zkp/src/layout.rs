@@ -69,7 +69,7 @@ macro_rules! layout_buffer {
                }
 
                fn get(&self, reg: &Self::Reg) -> core::option::Option<&$elem> {
-                   if reg.offset > self.0.len() {
+                   if reg.offset >= self.0.len() {
                        None
                    } else {
                        let val = &self.0[reg.offset];
@@ -129,13 +129,42 @@ pub trait Component {
 impl<C: Component, const N: usize> Component for [&C; N] {
     fn walk<V: Visitor>(&self, v: &mut V) -> core::fmt::Result {
         for (n, elem) in self.iter().enumerate() {
-            let name = alloc::format!("[{n}]");
-            v.visit_component(&name, *elem)?;
+            // Formatted on the stack, since this runs for every element of
+            // every array that is decoded.
+            let mut name = IndexName::default();
+            write!(name, "[{n}]")?;
+            v.visit_component(name.as_str(), *elem)?;
         }
         Ok(())
     }
 }
 
+// The name of an array element, e.g. "[12]".
+#[derive(Default)]
+struct IndexName {
+    buf: [u8; 22],
+    len: usize,
+}
+
+impl IndexName {
+    fn as_str(&self) -> &str {
+        // Only whole `str`s are ever written.
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+}
+
+impl Write for IndexName {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(core::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
 /// A visitor that visits components in a component tree.  Users
 /// should not use this directly; it is only "pub" so it can be used
 /// by the layout_buffer macro.
@@ -172,15 +201,24 @@ impl<'a, B: Buffer, C: Component> Tree<'a, B, C> {
             .collect()
     }
 
+    /// Interprets the contents of this tree as an array of exactly `N` bytes.
+    ///
+    /// Unlike [Tree::get_bytes], this doesn't allocate.
+    pub fn get_byte_array<const N: usize>(&self) -> Result<[u8; N]> {
+        let mut fill = TreeFill::new(self.buf);
+        self.component
+            .walk(&mut fill)
+            .map_err(|_| anyhow::Error::msg("expected a byte array of the given length"))?;
+        if fill.len != N {
+            anyhow::bail!("expected {N} bytes, found {}", fill.len);
+        }
+        Ok(fill.bytes)
+    }
+
     /// Returns the contents of this tree as a u32; elements are expected to be
     /// 4 bytes.
     pub fn get_u32(&self) -> Result<u32> {
-        Ok(u32::from_le_bytes(
-            self.get_bytes()?
-                .as_slice()
-                .try_into()
-                .map_err(|err| anyhow::Error::msg(err))?,
-        ))
+        Ok(u32::from_le_bytes(self.get_byte_array()?))
     }
 }
 
@@ -306,6 +344,38 @@ impl<'a, B: Buffer> TreeGather<'a, B> {
     }
 }
 
+// Collects the contents of a tree into a fixed-size array of bytes.
+struct TreeFill<'a, B: Buffer, const N: usize> {
+    buf: &'a B,
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<'a, B: Buffer, const N: usize> TreeFill<'a, B, N> {
+    fn new(buf: &'a B) -> Self {
+        Self {
+            buf,
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+}
+
+impl<'a, B: Buffer, const N: usize> Visitor for TreeFill<'a, B, N> {
+    fn visit_component(&mut self, _name: &str, component: &impl Component) -> core::fmt::Result {
+        component.walk(self)
+    }
+    fn visit_reg(&mut self, buf_name: &'static str, offset: usize) -> core::fmt::Result {
+        if buf_name == self.buf.name() {
+            let val = self.buf.get_u64(&B::Reg::from(offset));
+            let byte = self.bytes.get_mut(self.len).ok_or(core::fmt::Error)?;
+            *byte = u8::try_from(val).map_err(|_| core::fmt::Error)?;
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
 impl<'a, B: Buffer> Visitor for TreeGather<'a, B> {
     fn visit_component(&mut self, _name: &str, component: &impl Component) -> core::fmt::Result {
         component.walk(self)
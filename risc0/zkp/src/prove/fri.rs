@@ -21,7 +21,8 @@ use crate::{
     core::log2_ceil,
     hal::{Buffer, Hal},
     prove::{merkle::MerkleTreeProver, write_iop::WriteIOP},
-    FRI_FOLD, FRI_MIN_DEGREE, INV_RATE, QUERIES,
+    verify::VerifierParams,
+    FRI_FOLD, INV_RATE,
 };
 
 struct ProveRoundInfo<H: Hal> {
@@ -36,7 +37,12 @@ impl<H: Hal> ProveRoundInfo<H> {
     /// produce the evaluations of the polynomial, the merkle tree
     /// committing to the evaluation, and the coefficients of the folded
     /// polynomial.
-    pub fn new(hal: &H, iop: &mut WriteIOP<H::Field>, coeffs: &H::Buffer<H::Elem>) -> Self {
+    pub fn new(
+        hal: &H,
+        iop: &mut WriteIOP<H::Field>,
+        coeffs: &H::Buffer<H::Elem>,
+        queries: usize,
+    ) -> Self {
         debug!("Doing FRI folding");
         let ext_size = H::ExtElem::EXT_SIZE;
         // Get the number of coefficients of the polynomial over the extension field.
@@ -57,7 +63,7 @@ impl<H: Hal> ProveRoundInfo<H> {
             &evaluated,
             domain / FRI_FOLD,
             FRI_FOLD * ext_size,
-            QUERIES,
+            queries,
         );
         // Send the merkle tree (as a commitment) to the virtual IOP verifier
         merkle.commit(iop);
@@ -89,6 +95,7 @@ pub fn fri_prove<H: Hal, F>(
     hal: &H,
     iop: &mut WriteIOP<H::Field>,
     coeffs: &H::Buffer<H::Elem>,
+    params: &VerifierParams,
     inner: F,
 ) where
     F: Fn(&mut WriteIOP<H::Field>, usize),
@@ -97,8 +104,8 @@ pub fn fri_prove<H: Hal, F>(
     let orig_domain = coeffs.size() / ext_size * INV_RATE;
     let mut rounds = Vec::new();
     let mut coeffs = coeffs.clone();
-    while coeffs.size() / ext_size > FRI_MIN_DEGREE {
-        let round = ProveRoundInfo::new(hal, iop, &coeffs);
+    while coeffs.size() / ext_size > params.fri_min_degree {
+        let round = ProveRoundInfo::new(hal, iop, &coeffs, params.queries);
         coeffs = round.coeffs.clone();
         rounds.push(round);
     }
@@ -114,7 +121,7 @@ pub fn fri_prove<H: Hal, F>(
     });
     // Do queries
     debug!("Doing Queries");
-    for _ in 0..QUERIES {
+    for _ in 0..params.queries {
         // Get a 'random' index.
         let mut pos = iop.random_bits(log2_ceil(orig_domain)) as usize;
         // Do the 'inner' proof for this index
@@ -57,13 +57,26 @@ pub struct PolyGroup<H: Hal> {
 }
 
 impl<'a, H: Hal> PolyGroup<H> {
-    #[tracing::instrument(name = "PolyGroup", skip_all, fields(name = _name))]
     pub fn new(
+        hal: &H,
+        coeffs: H::Buffer<H::Elem>,
+        count: usize,
+        size: usize,
+        name: &'static str,
+    ) -> Self {
+        Self::new_with_queries(hal, coeffs, count, size, name, QUERIES)
+    }
+
+    /// Like [PolyGroup::new], for a proof with the given number of FRI
+    /// queries rather than [QUERIES].
+    #[tracing::instrument(name = "PolyGroup", skip_all, fields(name = _name))]
+    pub fn new_with_queries(
         hal: &H,
         coeffs: H::Buffer<H::Elem>,
         count: usize,
         size: usize,
         _name: &'static str,
+        queries: usize,
     ) -> Self {
         assert_eq!(coeffs.size(), count * size);
         let domain = size * INV_RATE;
@@ -71,7 +84,7 @@ impl<'a, H: Hal> PolyGroup<H> {
         hal.batch_expand(&evaluated, &coeffs, count);
         hal.batch_evaluate_ntt(&evaluated, count, log2_ceil(INV_RATE));
         hal.batch_bit_reverse(&coeffs, count);
-        let merkle = MerkleTreeProver::new(hal, &evaluated, domain, count, QUERIES);
+        let merkle = MerkleTreeProver::new(hal, &evaluated, domain, count, queries);
         PolyGroup {
             coeffs,
             count,
@@ -20,7 +20,8 @@ use crate::{
     hal::{Buffer, EvalCheck, Hal},
     prove::{fri::fri_prove, poly_group::PolyGroup, write_iop::WriteIOP},
     taps::TapSet,
-    INV_RATE,
+    verify::{VerificationError, VerifierParams},
+    FRI_FOLD_PO2, INV_RATE,
 };
 
 /// Object to generate a zero-knowledge proof of the execution of some circuit.
@@ -31,6 +32,7 @@ pub struct Prover<'a, H: Hal> {
     groups: Vec<Option<PolyGroup<H>>>,
     cycles: usize,
     po2: usize,
+    params: VerifierParams,
 }
 
 fn make_coeffs<H: Hal>(hal: &H, buf: H::Buffer<H::Elem>, count: usize) -> H::Buffer<H::Elem> {
@@ -45,7 +47,29 @@ fn make_coeffs<H: Hal>(hal: &H, buf: H::Buffer<H::Elem>, count: usize) -> H::Buf
 impl<'a, H: Hal> Prover<'a, H> {
     /// Creates a new prover.
     pub fn new(hal: &'a H, taps: &'a TapSet) -> Self {
-        Self {
+        Self::new_with_params(hal, taps, VerifierParams::default()).unwrap()
+    }
+
+    /// Creates a new prover that proves with the given parameters, so that
+    /// the seal must be verified with the same [VerifierParams].
+    ///
+    /// The folding factor is built into the HAL, so only the number of
+    /// queries and the FRI minimum degree can be changed. Zero-knowledge is
+    /// only preserved for at most [crate::ZK_CYCLES] queries.
+    ///
+    /// Returns [VerificationError::InvalidVerifierParams] if `params` are
+    /// invalid, as for [VerifierParams::validate], or have a folding factor
+    /// other than [crate::FRI_FOLD].
+    pub fn new_with_params(
+        hal: &'a H,
+        taps: &'a TapSet,
+        params: VerifierParams,
+    ) -> Result<Self, VerificationError> {
+        params.validate()?;
+        if params.fri_fold_po2 != FRI_FOLD_PO2 {
+            return Err(VerificationError::InvalidVerifierParams);
+        }
+        Ok(Self {
             hal,
             taps,
             iop: WriteIOP::new(hal.get_hash_suite().rng.as_ref()),
@@ -54,7 +78,8 @@ impl<'a, H: Hal> Prover<'a, H> {
                 .collect(),
             cycles: 0,
             po2: usize::MAX,
-        }
+            params,
+        })
     }
 
     /// Accesses the prover's IOP to commit or read random data.
@@ -85,12 +110,13 @@ impl<'a, H: Hal> Prover<'a, H> {
         );
 
         let coeffs = make_coeffs(self.hal, buf, group_size);
-        let group_ref = self.groups[tap_group_index].insert(PolyGroup::new(
+        let group_ref = self.groups[tap_group_index].insert(PolyGroup::new_with_queries(
             self.hal,
             coeffs,
             group_size,
             self.cycles,
             "data",
+            self.params.queries,
         ));
 
         group_ref.merkle.commit(&mut self.iop);
@@ -162,7 +188,14 @@ impl<'a, H: Hal> Prover<'a, H> {
         // invRate*size to 16 polys of size, without actually doing anything.
 
         // Make the PolyGroup + add it to the IOP;
-        let check_group = PolyGroup::new(self.hal, check_poly, H::CHECK_SIZE, self.cycles, "check");
+        let check_group = PolyGroup::new_with_queries(
+            self.hal,
+            check_poly,
+            H::CHECK_SIZE,
+            self.cycles,
+            "check",
+            self.params.queries,
+        );
         check_group.merkle.commit(&mut self.iop);
         debug!("checkGroup: {}", check_group.merkle.root());
 
@@ -338,13 +371,19 @@ impl<'a, H: Hal> Prover<'a, H> {
         self.hal.batch_bit_reverse(&final_poly_coeffs, ext_size);
         debug!("FRI-proof, size = {}", final_poly_coeffs.size() / ext_size);
 
-        fri_prove(self.hal, &mut self.iop, &final_poly_coeffs, |iop, idx| {
-            for pg in self.groups.iter() {
-                let pg = pg.as_ref().unwrap();
-                pg.merkle.prove(self.hal, iop, idx);
-            }
-            check_group.merkle.prove(self.hal, iop, idx);
-        });
+        fri_prove(
+            self.hal,
+            &mut self.iop,
+            &final_poly_coeffs,
+            &self.params,
+            |iop, idx| {
+                for pg in self.groups.iter() {
+                    let pg = pg.as_ref().unwrap();
+                    pg.merkle.prove(self.hal, iop, idx);
+                }
+                check_group.merkle.prove(self.hal, iop, idx);
+            },
+        );
 
         // Return final proof
         let proof = self.iop.proof;
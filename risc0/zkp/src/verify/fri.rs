@@ -16,7 +16,7 @@ use alloc::vec::Vec;
 
 use risc0_core::field::{Elem, ExtElem, Field, RootsOfUnity};
 
-use super::Verifier;
+use super::{Verifier, VerifierParams};
 use crate::{
     adapter::CircuitCoreDef,
     core::{
@@ -25,7 +25,7 @@ use crate::{
         ntt::{bit_reverse, interpolate_ntt},
     },
     verify::{merkle::MerkleTreeVerifier, read_iop::ReadIOP, VerificationError},
-    FRI_FOLD, FRI_FOLD_PO2, FRI_MIN_DEGREE, INV_RATE, QUERIES,
+    INV_RATE,
 };
 
 /// VerifyRoundInfo contains the data against which the queries for a particular
@@ -38,16 +38,21 @@ struct VerifyRoundInfo<'a, F: Field> {
 }
 
 impl<'a, F: Field> VerifyRoundInfo<'a, F> {
-    pub fn new(iop: &mut ReadIOP<'a, F>, hashfn: &dyn HashFn<F>, in_domain: usize) -> Self {
-        let domain = in_domain / FRI_FOLD;
+    pub fn new(
+        iop: &mut ReadIOP<'a, F>,
+        hashfn: &dyn HashFn<F>,
+        in_domain: usize,
+        params: &VerifierParams,
+    ) -> Self {
+        let domain = in_domain / params.fri_fold();
         VerifyRoundInfo {
             domain,
             merkle: MerkleTreeVerifier::new(
                 iop,
                 hashfn,
                 domain,
-                FRI_FOLD * F::ExtElem::EXT_SIZE,
-                QUERIES,
+                params.fri_fold() * F::ExtElem::EXT_SIZE,
+                params.queries,
             ),
             mix: iop.random_ext_elem(),
         }
@@ -72,11 +77,12 @@ where
         let data = round
             .merkle
             .verify(iop, self.suite.hashfn.as_ref(), group)?;
-        let mut data_ext: Vec<F::ExtElem> = (0..FRI_FOLD)
+        let fri_fold = self.params.fri_fold();
+        let mut data_ext: Vec<F::ExtElem> = (0..fri_fold)
             .map(|i| {
                 let mut inps = Vec::with_capacity(F::ExtElem::EXT_SIZE);
                 for j in 0..F::ExtElem::EXT_SIZE {
-                    inps.push(data[j * FRI_FOLD + i]);
+                    inps.push(data[j * fri_fold + i]);
                 }
                 F::ExtElem::from_subelems(inps)
             })
@@ -86,7 +92,7 @@ where
             return Err(VerificationError::InvalidProof);
         }
         // Compute the new goal + pos
-        let root_po2 = log2_ceil(FRI_FOLD * round.domain);
+        let root_po2 = log2_ceil(fri_fold * round.domain);
         let inv_wk = F::Elem::ROU_REV[root_po2].pow(group);
 
         interpolate_ntt::<F::Elem, F::ExtElem>(&mut data_ext);
@@ -107,26 +113,20 @@ where
         InnerFn: FnMut(&mut ReadIOP<'a, F>, usize) -> Result<F::ExtElem, VerificationError>,
     {
         let hashfn = self.suite.hashfn.as_ref();
+        let params = &self.params;
+        let fri_fold = params.fri_fold();
         let orig_domain = INV_RATE * degree;
         let mut domain = orig_domain;
         // Prep the folding verfiers
-        let rounds_capacity =
-            (log2_ceil((degree + FRI_FOLD - 1) / FRI_FOLD) + FRI_FOLD_PO2 - 1) / FRI_FOLD_PO2;
+        let rounds_capacity = (log2_ceil((degree + fri_fold - 1) / fri_fold) + params.fri_fold_po2
+            - 1)
+            / params.fri_fold_po2;
         let mut rounds = Vec::with_capacity(rounds_capacity);
-        while degree > FRI_MIN_DEGREE {
-            rounds.push(VerifyRoundInfo::new(iop, hashfn, domain));
-            domain /= FRI_FOLD;
-            degree /= FRI_FOLD;
+        while degree > params.fri_min_degree {
+            rounds.push(VerifyRoundInfo::new(iop, hashfn, domain, params));
+            domain /= fri_fold;
+            degree /= fri_fold;
         }
-        // We want to minimize reallocation in verify, so make sure we
-        // didn't have to reallocate.
-        assert!(
-            rounds.len() < rounds_capacity,
-            "Did not allocate enough rounds; needed {} for degree {} but only allocated {}",
-            rounds.len(),
-            degree,
-            rounds_capacity
-        );
         // Grab the final coeffs + commit
         let final_coeffs = iop.read_field_elem_slice(F::ExtElem::EXT_SIZE * degree);
         let final_digest = hashfn.hash_elem_slice(final_coeffs);
@@ -135,7 +135,7 @@ where
         let gen = <F::Elem as RootsOfUnity>::ROU_FWD[log2_ceil(domain)];
         // Do queries
         let mut poly_buf: Vec<F::ExtElem> = Vec::with_capacity(degree);
-        for _ in 0..QUERIES {
+        for _ in 0..params.queries {
             let mut pos = iop.random_bits(log2_ceil(orig_domain)) as usize;
             // Do the 'inner' verification for this index
             let mut goal = inner(iop, pos)?;
@@ -33,37 +33,40 @@ use crate::{
     adapter::{CircuitCoreDef, REGISTER_GROUP_ACCUM, REGISTER_GROUP_CODE, REGISTER_GROUP_DATA},
     core::{digest::Digest, hash::HashSuite, log2_ceil},
     taps::TapSet,
-    INV_RATE, MAX_CYCLES_PO2, QUERIES,
+    FRI_FOLD_PO2, FRI_MIN_DEGREE, INV_RATE, MAX_CYCLES_PO2, QUERIES,
 };
 
-#[derive(Debug, PartialEq)]
+/// An error from verifying a seal with the proof system.
+///
+/// This only covers failures of the proof itself. Checks on what a proof
+/// attests to, e.g. which program was run, are left to the users of this
+/// crate, which wrap this error in their own.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum VerificationError {
     ReceiptFormatError,
-    ControlVerificationError,
-    ImageVerificationError,
+    ControlVerificationError { control_id: Digest },
     MerkleQueryOutOfRange { idx: usize, rows: usize },
     InvalidProof,
-    JournalDigestMismatch,
-    UnexpectedExitCode,
-    InvalidHashSuite,
+    InvalidVerifierParams,
 }
 
 impl fmt::Display for VerificationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             VerificationError::ReceiptFormatError => write!(f, "invalid receipt format"),
-            VerificationError::ControlVerificationError => write!(f, "control_id mismatch"),
-            VerificationError::ImageVerificationError => write!(f, "image_id mismatch"),
+            VerificationError::ControlVerificationError { control_id } => {
+                write!(
+                    f,
+                    "control_id mismatch: unrecognized control_id {control_id}"
+                )
+            }
             VerificationError::MerkleQueryOutOfRange { idx, rows } => write!(
                 f,
                 "Requested Merkle validation on row {idx}, but only {rows} rows exist",
             ),
             VerificationError::InvalidProof => write!(f, "Verification indicates proof is invalid"),
-            VerificationError::JournalDigestMismatch => {
-                write!(f, "Journal digest mismatch detected")
-            }
-            VerificationError::UnexpectedExitCode => write!(f, "Unexpected exit_code"),
-            VerificationError::InvalidHashSuite => write!(f, "Invalid hash suite"),
+            VerificationError::InvalidVerifierParams => write!(f, "invalid verifier parameters"),
         }
     }
 }
@@ -71,6 +74,89 @@ impl fmt::Display for VerificationError {
 #[cfg(feature = "std")]
 impl std::error::Error for VerificationError {}
 
+/// Parameters of the proof system that a seal is verified with.
+///
+/// The defaults are the parameters used by the prover in this crate, and
+/// should only be changed to verify seals from a prover configured
+/// differently, e.g. when experimenting with the parameters. They must match
+/// the parameters the seal was proven with: a seal verified with any other
+/// parameters fails to verify, and since the parameters determine how the
+/// seal is read, a seal that is too short for them may cause a panic, as for
+/// any other malformed seal. Reducing the number of queries reduces the
+/// security of the proof accordingly.
+///
+/// Parameters built with [VerifierParams::new] are checked to be usable;
+/// parameters built any other way are checked when a seal is verified with
+/// them, which fails with [VerificationError::InvalidVerifierParams] if they
+/// aren't.
+///
+/// The Reed-Solomon expansion rate is fixed at [INV_RATE], which the
+/// verifier's check polynomial depends on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifierParams {
+    /// The number of FRI queries. Defaults to [QUERIES].
+    pub queries: usize,
+
+    /// The log2 of the FRI folding factor, which must be nonzero. Defaults to
+    /// 4, i.e. a folding factor of [crate::FRI_FOLD].
+    pub fri_fold_po2: usize,
+
+    /// The degree at which FRI stops folding and reads the remaining
+    /// polynomial in full, which must be at least the folding factor.
+    /// Defaults to 256.
+    pub fri_min_degree: usize,
+}
+
+impl VerifierParams {
+    /// Returns the given parameters, or
+    /// [VerificationError::InvalidVerifierParams] if seals can't be verified
+    /// with them: the number of queries must be nonzero, the folding factor
+    /// must exceed 1, and the minimum degree must be at least the folding
+    /// factor and at most the degree of the largest supported circuit.
+    pub fn new(
+        queries: usize,
+        fri_fold_po2: usize,
+        fri_min_degree: usize,
+    ) -> Result<Self, VerificationError> {
+        let params = Self {
+            queries,
+            fri_fold_po2,
+            fri_min_degree,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks that seals can be verified with these parameters, as for
+    /// [VerifierParams::new].
+    pub fn validate(&self) -> Result<(), VerificationError> {
+        if self.queries == 0
+            || self.fri_fold_po2 == 0
+            || self.fri_fold_po2 > MAX_CYCLES_PO2
+            || self.fri_min_degree < self.fri_fold()
+            || self.fri_min_degree > 1 << MAX_CYCLES_PO2
+        {
+            return Err(VerificationError::InvalidVerifierParams);
+        }
+        Ok(())
+    }
+
+    /// The FRI folding factor, i.e. `2 ^ fri_fold_po2`.
+    pub fn fri_fold(&self) -> usize {
+        1 << self.fri_fold_po2
+    }
+}
+
+impl Default for VerifierParams {
+    fn default() -> Self {
+        Self {
+            queries: QUERIES,
+            fri_fold_po2: FRI_FOLD_PO2,
+            fri_min_degree: FRI_MIN_DEGREE,
+        }
+    }
+}
+
 trait VerifyParams<F: Field> {
     const CHECK_SIZE: usize = INV_RATE * F::ExtElem::EXT_SIZE;
 }
@@ -88,6 +174,7 @@ where
 {
     circuit: &'a C,
     suite: &'a HashSuite<F>,
+    params: VerifierParams,
     po2: u32,
     steps: usize,
     out: Option<&'a [F::Elem]>,
@@ -102,16 +189,22 @@ where
     F: Field,
     C: CircuitCoreDef<F>,
 {
-    fn new(circuit: &'a C, suite: &'a HashSuite<F>) -> Self {
-        Self {
+    fn new(
+        circuit: &'a C,
+        suite: &'a HashSuite<F>,
+        params: VerifierParams,
+    ) -> Result<Self, VerificationError> {
+        params.validate()?;
+        Ok(Self {
             circuit,
             suite,
+            params,
             po2: 0,
             steps: 0,
             out: None,
             mix: Vec::new(),
             tap_cache: RefCell::new(None),
-        }
+        })
     }
 
     // Compute the FRI verify taps sum.
@@ -225,7 +318,8 @@ where
         // The code merkle tree contains the control instructions for the zkVM.
         #[cfg(not(target_os = "zkvm"))]
         log::debug!("code_merkle");
-        let code_merkle = MerkleTreeVerifier::new(&mut iop, hashfn, domain, code_size, QUERIES);
+        let code_merkle =
+            MerkleTreeVerifier::new(&mut iop, hashfn, domain, code_size, self.params.queries);
         // log::debug!("codeRoot = {}", code_merkle.root());
         check_code(self.po2, code_merkle.root())?;
 
@@ -235,7 +329,8 @@ where
         // accesses sorted by location used by PLONK.
         #[cfg(not(target_os = "zkvm"))]
         log::debug!("data_merkle");
-        let data_merkle = MerkleTreeVerifier::new(&mut iop, hashfn, domain, data_size, QUERIES);
+        let data_merkle =
+            MerkleTreeVerifier::new(&mut iop, hashfn, domain, data_size, self.params.queries);
         // log::debug!("dataRoot = {}", data_merkle.root());
 
         // Prep accumulation
@@ -255,7 +350,8 @@ where
         // implement a look-up table.
         #[cfg(not(target_os = "zkvm"))]
         log::debug!("accum_merkle");
-        let accum_merkle = MerkleTreeVerifier::new(&mut iop, hashfn, domain, accum_size, QUERIES);
+        let accum_merkle =
+            MerkleTreeVerifier::new(&mut iop, hashfn, domain, accum_size, self.params.queries);
         // log::debug!("accumRoot = {}", accum_merkle.root());
 
         // Get a pseudorandom value with which to mix the constraint polynomials.
@@ -264,8 +360,13 @@ where
 
         #[cfg(not(target_os = "zkvm"))]
         log::debug!("check_merkle");
-        let check_merkle =
-            MerkleTreeVerifier::new(&mut iop, hashfn, domain, Self::CHECK_SIZE, QUERIES);
+        let check_merkle = MerkleTreeVerifier::new(
+            &mut iop,
+            hashfn,
+            domain,
+            Self::CHECK_SIZE,
+            self.params.queries,
+        );
         // log::debug!("checkRoot = {}", check_merkle.root());
 
         // Get a pseudorandom DEEP query point
@@ -421,6 +522,11 @@ where
 }
 
 /// Verify a seal is valid for the given circuit, and code checking function.
+///
+/// Verification is deterministic: the only challenges are drawn by Fiat-Shamir
+/// from the RNG of the `suite`, which is seeded solely by the commitments read
+/// from the seal. The same seal and suite therefore always produce the same
+/// result, and no source of randomness needs to be supplied.
 #[must_use]
 #[tracing::instrument(skip_all)]
 pub fn verify<F, C, CheckCode>(
@@ -434,5 +540,24 @@ where
     C: CircuitCoreDef<F>,
     CheckCode: Fn(u32, &Digest) -> Result<(), VerificationError>,
 {
-    Verifier::<F, C>::new(circuit, suite).verify(seal, check_code)
+    verify_with_params(circuit, suite, seal, check_code, &VerifierParams::default())
+}
+
+/// Verify a seal is valid for the given circuit, and code checking function,
+/// using proof system parameters other than the defaults; see
+/// [VerifierParams].
+#[tracing::instrument(skip_all)]
+pub fn verify_with_params<F, C, CheckCode>(
+    circuit: &C,
+    suite: &HashSuite<F>,
+    seal: &[u32],
+    check_code: CheckCode,
+    params: &VerifierParams,
+) -> Result<(), VerificationError>
+where
+    F: Field,
+    C: CircuitCoreDef<F>,
+    CheckCode: Fn(u32, &Digest) -> Result<(), VerificationError>,
+{
+    Verifier::<F, C>::new(circuit, suite, *params)?.verify(seal, check_code)
 }
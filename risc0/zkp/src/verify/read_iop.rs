@@ -82,3 +82,37 @@ impl<'a, F: Field> ReadIOP<'a, F> {
         self.rng.random_ext_elem()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use risc0_core::field::baby_bear::BabyBear;
+
+    use super::ReadIOP;
+    use crate::core::{
+        digest::Digest,
+        hash::{blake2b::Blake2bCpuHashSuite, poseidon::PoseidonHashSuite, sha::Sha256HashSuite},
+    };
+
+    // The challenges drawn by the verifier depend only on the proof and the
+    // hash suite, so replaying a transcript must reproduce them exactly.
+    #[test]
+    fn deterministic_transcript() {
+        let proof: [u32; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+        for suite in [
+            Blake2bCpuHashSuite::new(),
+            PoseidonHashSuite::new(),
+            Sha256HashSuite::<BabyBear>::new(),
+        ] {
+            let transcript = || {
+                let mut iop = ReadIOP::new(&proof, suite.rng.as_ref());
+                let digest = iop.read_pod_slice::<Digest>(1)[0];
+                iop.commit(&digest);
+                let bits = iop.random_bits(20);
+                let elem = iop.random_elem();
+                let ext_elem = iop.random_ext_elem();
+                (bits, elem, ext_elem)
+            };
+            assert_eq!(transcript(), transcript(), "{}", suite.name);
+        }
+    }
+}
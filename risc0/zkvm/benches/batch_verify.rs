@@ -0,0 +1,65 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Verification of many receipts, one at a time and with a BatchVerifier.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use risc0_zkvm::{
+    BatchVerifier, Executor, ExecutorEnv, LocalExecutor, SessionReceipt, VerifierContext,
+};
+use risc0_zkvm_methods::{FIB_ELF, FIB_ID};
+
+const RECEIPTS: usize = 100;
+
+pub fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_verify");
+
+    let env = ExecutorEnv::builder().add_input(&[100]).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, FIB_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    let receipts: Vec<SessionReceipt> = vec![receipt; RECEIPTS];
+    let ctx = VerifierContext::default();
+
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(RECEIPTS as u64));
+
+    group.bench_function("per-receipt", |b| {
+        b.iter(|| {
+            for receipt in receipts.iter() {
+                black_box(receipt.verify_with_context(&ctx, FIB_ID).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("batched", |b| {
+        b.iter_batched(
+            || receipts.clone(),
+            |receipts| {
+                let mut batch = BatchVerifier::new(ctx.clone());
+                for receipt in receipts {
+                    batch.add(receipt, FIB_ID).unwrap();
+                }
+                black_box(batch.verify_all())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(name = benches;
+    config = Criterion::default();
+    targets = bench);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `decode_metadata` measures decoding the metadata of every segment of a
+//! large receipt, which is done before any seal is verified.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use risc0_zkvm::{
+    receipt::Receipt, Executor, ExecutorEnv, LocalExecutor, SegmentReceipt, SessionReceipt,
+};
+use risc0_zkvm_methods::FIB_ELF;
+
+const SEGMENTS: usize = 1000;
+
+// Builds a receipt with many copies of a real segment. Only the metadata is
+// decoded, so it doesn't matter that the segments don't chain.
+fn setup() -> SessionReceipt {
+    let env = ExecutorEnv::builder().add_input(&[100]).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, FIB_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    let segment = &receipt.segments[0];
+    let segments = (0..SEGMENTS)
+        .map(|index| {
+            let receipt = SegmentReceipt::new(
+                segment.get_seal().unwrap().to_vec(),
+                index as u32,
+                segment.hashfn(),
+            )
+            .unwrap();
+            Box::new(receipt) as Box<dyn Receipt>
+        })
+        .collect();
+    SessionReceipt::new(segments, receipt.journal)
+}
+
+pub fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_metadata");
+    let receipt = setup();
+    group.throughput(Throughput::Elements(SEGMENTS as u64));
+    group.bench_function(format!("{SEGMENTS}"), |b| {
+        b.iter(|| {
+            for segment in receipt.segments.iter() {
+                black_box(segment.get_metadata().unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(name = benches;
+    config = Criterion::default();
+    targets = bench);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Hashing of large journals, as done when verifying a receipt.
+//
+// Whether SHA-256 uses the assembly implementations is fixed at build time by
+// the `sha-asm` feature, so compare the two by running this benchmark with and
+// without it:
+//
+//   cargo bench --bench journal_hash -- --save-baseline soft
+//   cargo bench --bench journal_hash --features sha-asm -- --baseline soft
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use risc0_zkvm::JournalHash;
+
+const JOURNAL_BYTES: usize = 10 << 20;
+
+pub fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("journal_hash");
+    let journal: Vec<u8> = (0..JOURNAL_BYTES).map(|i| i as u8).collect();
+    group.throughput(Throughput::Bytes(JOURNAL_BYTES as u64));
+    group.sample_size(20);
+
+    group.bench_function("sha-256", |b| {
+        b.iter(|| black_box(JournalHash::Sha256.digest(&journal)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(name = benches;
+    config = Criterion::default();
+    targets = bench);
+criterion_main!(benches);
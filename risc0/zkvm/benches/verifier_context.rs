@@ -0,0 +1,48 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use risc0_zkp::{
+    core::hash::{blake2b::Blake2bCpuHashSuite, poseidon::PoseidonHashSuite, sha::Sha256HashSuite},
+    field::baby_bear::BabyBear,
+};
+use risc0_zkvm::VerifierContext;
+
+pub fn bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verifier_context");
+
+    group.bench_function("default", |b| {
+        b.iter(|| black_box(VerifierContext::default()))
+    });
+
+    let ctx = VerifierContext::default();
+    group.bench_function("clone", |b| b.iter(|| black_box(ctx.clone())));
+
+    group.bench_function("suite/blake2b", |b| {
+        b.iter(|| black_box(Blake2bCpuHashSuite::new()))
+    });
+    group.bench_function("suite/poseidon", |b| {
+        b.iter(|| black_box(PoseidonHashSuite::new()))
+    });
+    group.bench_function("suite/sha-256", |b| {
+        b.iter(|| black_box(Sha256HashSuite::<BabyBear>::new()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(name = benches;
+    config = Criterion::default();
+    targets = bench);
+criterion_main!(benches);
@@ -18,4 +18,9 @@ fn main() {
         std::env::set_var("PROTOC", protobuf_src::protoc());
         prost_build::compile_protos(&["src/exec/profile.proto"], &["src/exec/"]).unwrap();
     }
+    #[cfg(feature = "proto")]
+    {
+        std::env::set_var("PROTOC", protobuf_src::protoc());
+        prost_build::compile_protos(&["src/receipt.proto"], &["src/"]).unwrap();
+    }
 }
@@ -82,7 +82,9 @@ fn top(prover: Rc<dyn Prover>, iterations: u32, skip_prover: bool) -> Metrics {
             .unwrap()
             .segments
             .iter()
-            .fold(0, |acc, segment| acc + segment.get_seal_bytes().len())
+            .fold(0, |acc, segment| {
+                acc + segment.get_seal_bytes().unwrap().len()
+            })
     };
 
     Metrics {
@@ -57,10 +57,9 @@ fn main() {
             .iter()
             .fold(0, |acc, segment| acc + (1 << segment.po2));
 
-        let seal = receipt
-            .segments
-            .iter()
-            .fold(0, |acc, segment| acc + segment.get_seal_bytes().len());
+        let seal = receipt.segments.iter().fold(0, |acc, segment| {
+            acc + segment.get_seal_bytes().unwrap().len()
+        });
 
         let usage = prover.get_peak_memory_usage();
         let throughput = (cycles as f64) / duration.as_secs_f64();
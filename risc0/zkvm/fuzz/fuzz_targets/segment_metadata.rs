@@ -0,0 +1,35 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use risc0_zkvm::{receipt::Receipt, SegmentReceipt};
+
+// Decoding the metadata from an arbitrary seal must never panic. Most inputs
+// are rejected with an error, but a seal whose output buffer happens to hold
+// well-formed metadata decodes successfully, so only panics count as failures
+// here.
+fuzz_target!(|data: &[u8]| {
+    let seal: Vec<u32> = data
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0u8; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_le_bytes(word)
+        })
+        .collect();
+    let receipt = SegmentReceipt::new(seal, 0, "sha-256").unwrap();
+    let _ = receipt.get_metadata();
+});
@@ -0,0 +1,122 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use risc0_zkvm::SessionReceipt;
+
+/// Inspects and verifies receipts, as written by `SessionReceipt::to_writer`.
+#[derive(Parser)]
+#[clap(version)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print a summary of the receipt.
+    Inspect {
+        /// The receipt file.
+        receipt: PathBuf,
+    },
+
+    /// Print the metadata claimed by each segment of the receipt.
+    Metadata {
+        /// The receipt file.
+        receipt: PathBuf,
+    },
+
+    /// Print the segment sizes and cost of verifying the receipt.
+    Stats {
+        /// The receipt file.
+        receipt: PathBuf,
+    },
+
+    /// Verify the receipt, exiting with a failure if it doesn't verify.
+    Verify {
+        /// The receipt file.
+        receipt: PathBuf,
+
+        /// The image ID of the program, in hex, with an optional `0x` prefix.
+        #[clap(long)]
+        image_id: String,
+    },
+}
+
+fn main() -> Result<ExitCode> {
+    let args = Args::parse();
+    match args.command {
+        Command::Inspect { receipt } => {
+            println!("{}", load(&receipt)?.summary_string());
+        }
+        Command::Metadata { receipt } => {
+            for (idx, segment) in load(&receipt)?.segments.iter().enumerate() {
+                println!("segment {idx}: {:#?}", segment.get_metadata()?);
+            }
+        }
+        Command::Stats { receipt } => {
+            let receipt = load(&receipt)?;
+            println!("segments: {}", receipt.segments.len());
+            match receipt.po2_histogram() {
+                Ok(histogram) => {
+                    for (po2, count) in histogram {
+                        println!("  po2 {po2}: {count}");
+                    }
+                }
+                Err(err) => println!("  po2: <unknown> ({err})"),
+            }
+            match receipt.padded_cycles() {
+                Ok(cycles) => println!("padded cycles: {cycles}"),
+                Err(err) => println!("padded cycles: <unknown> ({err})"),
+            }
+            println!("journal bytes: {}", receipt.journal.len());
+            match receipt
+                .segments
+                .iter()
+                .map(|segment| segment.get_seal_bytes().map(<[u8]>::len))
+                .sum::<Result<usize, _>>()
+            {
+                Ok(len) => println!("seal bytes: {len}"),
+                Err(err) => println!("seal bytes: <unknown> ({err})"),
+            }
+            match receipt.security_bits() {
+                Ok(bits) => println!("security bits: {bits:.1}"),
+                Err(err) => println!("security bits: <unknown> ({err})"),
+            }
+            println!("verify cost estimate: {}", receipt.verify_cost_estimate());
+        }
+        Command::Verify { receipt, image_id } => {
+            if let Err(err) = load(&receipt)?.verify_hex(&image_id) {
+                eprintln!("Receipt failed to verify: {err}");
+                return Ok(ExitCode::FAILURE);
+            }
+            println!("Receipt verified");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn load(path: &Path) -> Result<SessionReceipt> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    SessionReceipt::from_reader(BufReader::new(file))
+        .with_context(|| format!("failed to read a receipt from {}", path.display()))
+}
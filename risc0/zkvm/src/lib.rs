@@ -28,6 +28,8 @@ mod exec;
 pub mod guest;
 #[cfg(feature = "prove")]
 mod opcode;
+#[cfg(feature = "proto")]
+pub mod proto;
 #[cfg(feature = "prove")]
 pub mod prove;
 #[cfg(not(target_os = "zkvm"))]
@@ -53,8 +55,16 @@ pub use self::control_id::POSEIDON_CONTROL_ID;
 pub use self::exec::profiler::Profiler;
 #[cfg(not(target_os = "zkvm"))]
 pub use self::receipt::{
-    ExitCode, ReceiptMetadata, SegmentReceipt, SessionReceipt, SystemState, VerifierContext,
+    compute_control_root, compute_journal_commitment, compute_journal_root, journal_digest,
+    journal_inclusion_proof, BatchVerifier, CommitmentVerifier, ControlInclusionProof,
+    DetachedSessionReceipt, ExitCode, FakeReceipt, IncrementalVerifier, JournalHash,
+    LazySessionReceipt, LocalVerifier, MetadataDiff, ReceiptMetadata, SegmentCache, SegmentReceipt,
+    SessionReceipt, SessionReceiptBuilder, SystemState, VerificationCache, VerificationError,
+    VerifiedReceipt, Verifier, VerifierCircuit, VerifierContext, VerifyCursor, VerifyWarning,
+    LAYOUT_VERSION, MAX_SEAL_BYTES,
 };
+#[cfg(all(feature = "std", not(target_os = "zkvm")))]
+pub use self::receipt::{SegmentTiming, VerifyTiming};
 #[cfg(feature = "prove")]
 pub use self::{
     exec::io::{Syscall, SyscallContext},
@@ -0,0 +1,186 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Protobuf representation of receipts.
+//!
+//! The schema is defined in `receipt.proto`, and is intended as a stable wire
+//! format for services that aren't written in Rust. Use
+//! [SessionReceipt::to_proto_bytes] and [SessionReceipt::from_proto_bytes] to
+//! convert to and from the encoded form, or the [From]/[TryFrom] conversions
+//! between the native and generated types.
+
+use prost::Message;
+use risc0_zkp::core::digest::Digest;
+
+use crate::receipt::{self, Receipt, VerificationError};
+
+// Generated proto interface.
+include!(concat!(env!("OUT_DIR"), "/risc0.receipt.rs"));
+
+fn decode_digest(bytes: Vec<u8>) -> Result<Digest, VerificationError> {
+    Digest::try_from(bytes).or(Err(VerificationError::ReceiptFormatError))
+}
+
+impl From<receipt::SystemState> for SystemState {
+    fn from(value: receipt::SystemState) -> Self {
+        Self {
+            pc: value.pc,
+            merkle_root: value.merkle_root.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<SystemState> for receipt::SystemState {
+    type Error = VerificationError;
+
+    fn try_from(value: SystemState) -> Result<Self, Self::Error> {
+        Ok(Self {
+            pc: value.pc,
+            merkle_root: decode_digest(value.merkle_root)?,
+        })
+    }
+}
+
+impl From<receipt::ExitCode> for ExitCode {
+    fn from(value: receipt::ExitCode) -> Self {
+        let kind = match value {
+            receipt::ExitCode::SystemSplit => exit_code::Kind::SystemSplit(exit_code::Empty {}),
+            receipt::ExitCode::SessionLimit => exit_code::Kind::SessionLimit(exit_code::Empty {}),
+            receipt::ExitCode::Paused(code) => exit_code::Kind::Paused(code),
+            receipt::ExitCode::Halted(code) => exit_code::Kind::Halted(code),
+        };
+        Self { kind: Some(kind) }
+    }
+}
+
+impl TryFrom<ExitCode> for receipt::ExitCode {
+    type Error = VerificationError;
+
+    fn try_from(value: ExitCode) -> Result<Self, Self::Error> {
+        match value.kind.ok_or(VerificationError::ReceiptFormatError)? {
+            exit_code::Kind::SystemSplit(_) => Ok(Self::SystemSplit),
+            exit_code::Kind::SessionLimit(_) => Ok(Self::SessionLimit),
+            exit_code::Kind::Paused(code) => Ok(Self::Paused(code)),
+            exit_code::Kind::Halted(code) => Ok(Self::Halted(code)),
+        }
+    }
+}
+
+impl From<receipt::ReceiptMetadata> for ReceiptMetadata {
+    fn from(value: receipt::ReceiptMetadata) -> Self {
+        Self {
+            pre: Some(value.pre.into()),
+            post: Some(value.post.into()),
+            exit_code: Some(value.exit_code.into()),
+            input: value.input.as_bytes().to_vec(),
+            output: value.output.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<ReceiptMetadata> for receipt::ReceiptMetadata {
+    type Error = VerificationError;
+
+    fn try_from(value: ReceiptMetadata) -> Result<Self, Self::Error> {
+        let missing = VerificationError::ReceiptFormatError;
+        Ok(Self {
+            pre: value.pre.ok_or(missing.clone())?.try_into()?,
+            post: value.post.ok_or(missing.clone())?.try_into()?,
+            exit_code: value.exit_code.ok_or(missing)?.try_into()?,
+            input: decode_digest(value.input)?,
+            output: decode_digest(value.output)?,
+        })
+    }
+}
+
+impl From<receipt::SegmentReceipt> for SegmentReceipt {
+    fn from(value: receipt::SegmentReceipt) -> Self {
+        Self {
+            seal: value.seal.into(),
+            index: value.index,
+            hashfn: value.hashfn,
+            layout_version: value.layout_version,
+        }
+    }
+}
+
+impl From<SegmentReceipt> for receipt::SegmentReceipt {
+    fn from(value: SegmentReceipt) -> Self {
+        Self {
+            seal: value.seal.into(),
+            index: value.index,
+            hashfn: value.hashfn,
+            // Messages written before the layout version was recorded use the
+            // first versioned layout.
+            layout_version: match value.layout_version {
+                0 => receipt::LAYOUT_VERSION,
+                version => version,
+            },
+        }
+    }
+}
+
+impl TryFrom<&receipt::SessionReceipt> for SessionReceipt {
+    type Error = VerificationError;
+
+    /// Fails with [VerificationError::ReceiptFormatError] if any segment is
+    /// not a [receipt::SegmentReceipt], since the schema has no representation
+    /// for other kinds of receipt.
+    fn try_from(value: &receipt::SessionReceipt) -> Result<Self, Self::Error> {
+        let segments = value
+            .segments
+            .iter()
+            .map(|segment| {
+                segment
+                    .as_any()
+                    .downcast_ref::<receipt::SegmentReceipt>()
+                    .cloned()
+                    .map(SegmentReceipt::from)
+                    .ok_or(VerificationError::ReceiptFormatError)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            segments,
+            journal: value.journal.clone(),
+        })
+    }
+}
+
+impl TryFrom<SessionReceipt> for receipt::SessionReceipt {
+    type Error = VerificationError;
+
+    fn try_from(value: SessionReceipt) -> Result<Self, Self::Error> {
+        let segments = value
+            .segments
+            .into_iter()
+            .map(|segment| Box::new(receipt::SegmentReceipt::from(segment)) as Box<dyn Receipt>)
+            .collect();
+        Ok(Self::new(segments, value.journal))
+    }
+}
+
+impl receipt::SessionReceipt {
+    /// Encodes this receipt using the protobuf schema in `receipt.proto`.
+    pub fn to_proto_bytes(&self) -> Result<Vec<u8>, VerificationError> {
+        Ok(SessionReceipt::try_from(self)?.encode_to_vec())
+    }
+
+    /// Decodes a receipt encoded with
+    /// [receipt::SessionReceipt::to_proto_bytes].
+    pub fn from_proto_bytes(bytes: &[u8]) -> Result<Self, VerificationError> {
+        SessionReceipt::decode(bytes)
+            .or(Err(VerificationError::ReceiptFormatError))?
+            .try_into()
+    }
+}
@@ -61,7 +61,7 @@ use risc0_zkvm_platform::WORD_SIZE;
 
 use self::{exec::MachineContext, loader::Loader};
 use crate::{
-    receipt::{Receipt, SessionReceipt, VerifierContext},
+    receipt::{Receipt, SessionReceipt, VerifierContext, LAYOUT_VERSION},
     Segment, SegmentReceipt, Session, CIRCUIT,
 };
 
@@ -321,8 +321,11 @@ where
         loader.load(|chunk, fini| executor.step(chunk, fini))?;
         executor.finalize();
 
+        // Prove with the parameters the receipt will be verified with.
+        let params = ctx.params.unwrap_or_default();
+        let mut prover =
+            risc0_zkp::prove::Prover::new_with_params(hal, CIRCUIT.get_taps(), params)?;
         let mut adapter = ProveAdapter::new(&mut executor);
-        let mut prover = risc0_zkp::prove::Prover::new(hal, CIRCUIT.get_taps());
 
         adapter.execute(prover.iop());
 
@@ -351,9 +354,10 @@ where
         let seal = prover.finalize(&[&mix, &out], eval.as_ref());
 
         let receipt = SegmentReceipt {
-            seal,
+            seal: seal.into(),
             index: segment.index,
             hashfn: hashfn.clone(),
+            layout_version: LAYOUT_VERSION,
         };
         receipt.verify_with_context(ctx)?;
 
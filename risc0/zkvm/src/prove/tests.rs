@@ -12,14 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::rc::Rc;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+    sync::Arc,
+};
 
 use anyhow::Result;
 use risc0_circuit_rv32im::cpu::CpuEvalCheck;
+use risc0_core::field::baby_bear::BabyBear;
 use risc0_zkp::{
-    core::{digest::Digest, hash::blake2b::Blake2bCpuHashSuite},
+    core::{
+        digest::Digest,
+        hash::{blake2b::Blake2bCpuHashSuite, HashSuite},
+    },
     hal::cpu::CpuHal,
-    verify::VerificationError,
+    verify::{VerificationError as ProofError, VerifierParams},
 };
 use risc0_zkvm_methods::{multi_test::MultiTestSpec, MULTI_TEST_ELF, MULTI_TEST_ID};
 use risc0_zkvm_platform::{memory, WORD_SIZE};
@@ -28,11 +36,17 @@ use test_log::test;
 
 use super::{get_prover, LocalProver, Prover};
 use crate::{
+    control_id::SHA256_CONTROL_ID,
     exec::Executor,
     prove::HalEval,
-    receipt::{SessionReceipt, VerifierContext},
+    receipt::{
+        compute_image_id, compute_journal_commitment, DetachedSessionReceipt, JournalHash, Receipt,
+        SessionReceipt, VerificationCache, VerificationError, VerifierCircuit, VerifierContext,
+        VerifyCursor,
+    },
     serde::{from_slice, to_vec},
-    testutils, ExecutorEnv, ExitCode, LocalExecutor, SegmentReceipt, CIRCUIT,
+    sha::{self, Sha256},
+    testutils, ExecutorEnv, ExitCode, LocalExecutor, SegmentReceipt, CIRCUIT, LAYOUT_VERSION,
 };
 
 fn prove_nothing(name: &str) -> Result<SessionReceipt> {
@@ -45,6 +59,27 @@ fn prove_nothing(name: &str) -> Result<SessionReceipt> {
     prover.prove_session(&ctx, &session)
 }
 
+// Delegates to the built-in circuit, but reports a control ID of its own, as a
+// modified circuit would.
+struct RenamedCircuit;
+
+impl RenamedCircuit {
+    const CONTROL_ID: Digest = Digest::new([0x5a5a5a5a; 8]);
+}
+
+impl VerifierCircuit for RenamedCircuit {
+    fn verify(
+        &self,
+        suite: &HashSuite<BabyBear>,
+        seal: &[u32],
+        check_code: &dyn Fn(u32, &Digest) -> Result<(), ProofError>,
+        params: &VerifierParams,
+    ) -> Result<(), ProofError> {
+        let check_code = |po2, _: &Digest| check_code(po2, &Self::CONTROL_ID);
+        CIRCUIT.verify(suite, seal, &check_code, params)
+    }
+}
+
 #[test]
 #[cfg_attr(feature = "cuda", serial)]
 fn hashfn_poseidon() {
@@ -76,6 +111,794 @@ fn receipt_serde() {
     decoded.verify(MULTI_TEST_ID).unwrap();
 }
 
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn detached_journal() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    let output = receipt.output_digest().unwrap();
+
+    let (detached, journal) = receipt.detach_journal();
+    assert!(!journal.is_empty());
+    assert_eq!(output, JournalHash::Sha256.digest(&journal));
+    assert_eq!(
+        detached.journal_digest,
+        JournalHash::Sha256.digest(&journal)
+    );
+    assert_eq!(
+        detached
+            .verify_with_context(&VerifierContext::default(), MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::JournalDetached
+    );
+    let ctx = VerifierContext {
+        check_journal: false,
+        ..Default::default()
+    };
+    detached.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    detached
+        .verify_with_journal(MULTI_TEST_ID, &journal)
+        .unwrap();
+
+    // The digest survives serialization in place of the journal.
+    let encoded: Vec<u32> = to_vec(&detached).unwrap();
+    let detached: DetachedSessionReceipt = from_slice(&encoded).unwrap();
+    assert_eq!(
+        detached
+            .clone()
+            .attach_journal(b"wrong".to_vec())
+            .unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+    let receipt = detached.attach_journal(journal).unwrap();
+    receipt.verify(MULTI_TEST_ID).unwrap();
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_with_journal() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let mut receipt = exec.run().unwrap().prove().unwrap();
+
+    let journal = core::mem::take(&mut receipt.journal);
+    assert!(!journal.is_empty());
+    receipt
+        .verify_with_journal(MULTI_TEST_ID, &journal)
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_journal(MULTI_TEST_ID, b"wrong")
+            .unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+    assert_eq!(
+        receipt
+            .verify_with_journal(Digest::default(), &journal)
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+fn verify_against_commitment() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+
+    let commitment = compute_journal_commitment(&MULTI_TEST_ID.into(), &receipt.journal);
+    receipt
+        .verify_against_commitment(MULTI_TEST_ID, commitment)
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_against_commitment(
+                MULTI_TEST_ID,
+                compute_journal_commitment(&MULTI_TEST_ID.into(), b"wrong")
+            )
+            .unwrap_err(),
+        VerificationError::CommitmentMismatch
+    );
+    assert_eq!(
+        receipt
+            .verify_against_commitment(Digest::default(), commitment)
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+fn verify_and_check() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+
+    let expected = *sha::Impl::hash_bytes(b"abc");
+    receipt.verify_and_check(MULTI_TEST_ID, &expected).unwrap();
+    assert_eq!(
+        receipt
+            .verify_and_check(MULTI_TEST_ID, &*sha::Impl::hash_bytes(b"abd"))
+            .unwrap_err(),
+        VerificationError::JournalValueMismatch
+    );
+    // A journal too short to decode as the expected type.
+    assert_eq!(
+        receipt
+            .verify_and_check(MULTI_TEST_ID, &[0u32; 9])
+            .unwrap_err(),
+        VerificationError::JournalValueMismatch
+    );
+    // A journal with data left over after the expected value.
+    let prefix: [u32; 4] = expected.as_words()[..4].try_into().unwrap();
+    assert_eq!(
+        receipt
+            .verify_and_check(MULTI_TEST_ID, &prefix)
+            .unwrap_err(),
+        VerificationError::JournalValueMismatch
+    );
+    assert_eq!(
+        receipt
+            .verify_and_check(Digest::default(), &expected)
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+fn short_seal_metadata() {
+    let receipt = prove_nothing("$default").unwrap();
+    let segment = receipt.segments[0]
+        .as_any()
+        .downcast_ref::<SegmentReceipt>()
+        .unwrap();
+    for len in [0, 1, 137] {
+        let truncated = SegmentReceipt {
+            seal: segment.seal[..len].to_vec().into(),
+            ..segment.clone()
+        };
+        assert_eq!(
+            truncated.get_metadata().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(
+            truncated.decode_exit_code().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(
+            truncated.po2().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_cached() {
+    let receipt = prove_nothing("$default").unwrap();
+    let ctx = VerifierContext::default();
+    let mut cache = VerificationCache::new(4);
+    receipt
+        .verify_cached(&ctx, MULTI_TEST_ID, &mut cache)
+        .unwrap();
+    assert_eq!(cache.len(), 1);
+    receipt
+        .verify_cached(&ctx.clone(), MULTI_TEST_ID, &mut cache)
+        .unwrap();
+    assert_eq!(cache.len(), 1);
+
+    // The context is part of the key: a context with no hash suites can't
+    // verify anything, and must not reuse the result of another context.
+    let empty = VerifierContext {
+        suites: Default::default(),
+        ..Default::default()
+    };
+    assert_eq!(
+        receipt
+            .verify_cached(&empty, MULTI_TEST_ID, &mut cache)
+            .unwrap_err(),
+        VerificationError::InvalidHashSuite
+    );
+
+    // So is the image ID, and failures aren't cached.
+    assert_eq!(
+        receipt
+            .verify_cached(&ctx, Digest::default(), &mut cache)
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+    assert_eq!(cache.len(), 1);
+}
+
+#[cfg(feature = "proto")]
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn proto_roundtrip() {
+    let receipt = prove_nothing("$default").unwrap();
+    let decoded = SessionReceipt::from_proto_bytes(&receipt.to_proto_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, receipt);
+    decoded.verify(MULTI_TEST_ID).unwrap();
+
+    assert_eq!(
+        SessionReceipt::from_proto_bytes(&[0xff]).unwrap_err(),
+        VerificationError::ReceiptFormatError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_metadata() {
+    let receipt = prove_nothing("$default").unwrap();
+    let segment = &receipt.segments[0];
+    let ctx = VerifierContext::default();
+    assert_eq!(
+        segment.verify_metadata(&ctx).unwrap(),
+        segment.get_metadata().unwrap()
+    );
+
+    let empty = VerifierContext {
+        suites: Default::default(),
+        ..Default::default()
+    };
+    assert_eq!(
+        segment.verify_metadata(&empty).unwrap_err(),
+        VerificationError::InvalidHashSuite
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn tampered_receipt() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    receipt.verify(MULTI_TEST_ID).unwrap();
+
+    let segment = receipt
+        .segments
+        .last()
+        .unwrap()
+        .as_any()
+        .downcast_ref::<SegmentReceipt>()
+        .unwrap();
+    let mut tampered = segment.clone();
+    let idx = tampered.seal.len() / 2;
+    tampered.seal[idx] ^= 1;
+    let tampered = SessionReceipt::new(vec![Box::new(tampered)], receipt.journal.clone());
+    assert!(tampered.verify(MULTI_TEST_ID).is_err());
+
+    let mut journal = receipt.journal.clone();
+    journal[0] ^= 1;
+    let tampered = SessionReceipt::new(vec![Box::new(segment.clone())], journal);
+    assert_eq!(
+        tampered.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+
+    assert_eq!(
+        receipt.verify(Digest::default()).unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn skip_journal_check() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let mut receipt = exec.run().unwrap().prove().unwrap();
+    receipt.journal = b"not the committed journal".to_vec();
+    assert_eq!(
+        receipt.verify(MULTI_TEST_ID).unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+
+    let ctx = VerifierContext {
+        check_journal: false,
+        ..Default::default()
+    };
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_context(&ctx, Digest::default())
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_with_deadline() {
+    use std::time::{Duration, Instant};
+
+    let receipt = prove_nothing("$default").unwrap();
+    let ctx = VerifierContext::default();
+    receipt
+        .verify_with_deadline(
+            &ctx,
+            MULTI_TEST_ID,
+            Instant::now() + Duration::from_secs(3600),
+        )
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_deadline(&ctx, MULTI_TEST_ID, Instant::now())
+            .unwrap_err(),
+        VerificationError::DeadlineExceeded
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_async() {
+    let receipt = std::sync::Arc::new(prove_nothing("$default").unwrap());
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime
+        .block_on(
+            receipt
+                .clone()
+                .verify_async(VerifierContext::default(), MULTI_TEST_ID),
+        )
+        .unwrap();
+    assert_eq!(
+        runtime
+            .block_on(receipt.verify_async(VerifierContext::default(), Digest::default()))
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn compressed_segment() {
+    use crate::receipt::CompressedSegmentReceipt;
+
+    let receipt = prove_nothing("$default").unwrap();
+    let segment = receipt.as_segment(0).unwrap();
+    let compressed = CompressedSegmentReceipt::compress(segment, 0).unwrap();
+    let original_len = segment.get_seal_bytes().unwrap().len();
+    let compressed_len = compressed.compressed_seal.len();
+    log::info!("seal: {original_len} bytes, compressed: {compressed_len} bytes");
+    assert!(compressed_len < original_len);
+
+    assert_eq!(compressed.get_metadata(), segment.get_metadata());
+    assert_eq!(compressed.get_seal().unwrap(), segment.get_seal().unwrap());
+    assert_eq!(&compressed.decompress().unwrap(), segment);
+
+    // Only the compressed seal is serialized.
+    let encoded: Vec<u32> = to_vec(&compressed).unwrap();
+    assert!(encoded.len() * 4 < original_len);
+    let decoded: CompressedSegmentReceipt = from_slice(&encoded).unwrap();
+    assert_eq!(decoded, compressed);
+
+    let compressed = SessionReceipt::new(vec![Box::new(decoded)], receipt.journal.clone());
+    compressed.verify(MULTI_TEST_ID).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn from_mmap() {
+    let receipt = prove_nothing("$default").unwrap();
+    let encoded: Vec<u32> = to_vec(&receipt).unwrap();
+    let path = std::env::temp_dir().join(format!("receipt-{}.bin", std::process::id()));
+    std::fs::write(&path, bytemuck::cast_slice::<u32, u8>(&encoded)).unwrap();
+    // SAFETY: nothing else writes to the temporary file.
+    let mapped = unsafe { SessionReceipt::from_mmap(&path) }.unwrap();
+
+    // The seals stay in the mapping, but the receipt is otherwise the same.
+    let segment = mapped.as_segment(0).unwrap();
+    assert!(segment.seal.is_mapped());
+    assert_eq!(segment, receipt.as_segment(0).unwrap());
+    assert_eq!(mapped, receipt);
+    assert_eq!(
+        mapped.fingerprint().unwrap(),
+        receipt.fingerprint().unwrap()
+    );
+    assert_eq!(to_vec(&mapped).unwrap(), encoded);
+    mapped.verify(MULTI_TEST_ID).unwrap();
+
+    // A mapped receipt can be serialized and read back into memory.
+    let decoded: SessionReceipt = from_slice(&to_vec(&mapped).unwrap()).unwrap();
+    assert!(!decoded.as_segment(0).unwrap().seal.is_mapped());
+    assert_eq!(decoded, mapped);
+
+    drop(mapped);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn custom_circuit() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::receipt::{compute_control_root, ControlInclusionProof};
+
+    // Delegates to the built-in circuit, counting the seals it verifies.
+    struct CountingCircuit(AtomicUsize);
+
+    impl VerifierCircuit for CountingCircuit {
+        fn verify(
+            &self,
+            suite: &HashSuite<BabyBear>,
+            seal: &[u32],
+            check_code: &dyn Fn(u32, &Digest) -> Result<(), ProofError>,
+            params: &VerifierParams,
+        ) -> Result<(), ProofError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            CIRCUIT.verify(suite, seal, check_code, params)
+        }
+    }
+
+    static COUNTING: CountingCircuit = CountingCircuit(AtomicUsize::new(0));
+
+    let receipt = prove_nothing("$default").unwrap();
+    let ctx = VerifierContext {
+        circuit: &COUNTING,
+        ..Default::default()
+    };
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    assert_eq!(COUNTING.0.load(Ordering::SeqCst), receipt.segments.len());
+
+    // A circuit's own control IDs aren't accepted unless they are under the
+    // control root.
+    let mut ctx = VerifierContext {
+        circuit: &RenamedCircuit,
+        ..Default::default()
+    };
+    assert_eq!(
+        receipt
+            .verify_with_context(&ctx, MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::Proof(ProofError::ControlVerificationError {
+            control_id: RenamedCircuit::CONTROL_ID
+        })
+    );
+    let control_ids = [RenamedCircuit::CONTROL_ID];
+    ctx.control_root = Some(compute_control_root(&control_ids));
+    ctx.control_proofs.insert(
+        RenamedCircuit::CONTROL_ID,
+        ControlInclusionProof::new(&control_ids, 0).unwrap(),
+    );
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    assert_eq!(
+        receipt.control_ids_with_context(&ctx).unwrap(),
+        BTreeSet::from([RenamedCircuit::CONTROL_ID])
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verifier_params() {
+    let input = to_vec(&MultiTestSpec::DoNothing).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let prover = get_prover("$default");
+
+    // Prove and verify with fewer queries and a lower FRI minimum degree than
+    // the defaults.
+    let defaults = VerifierParams::default();
+    let params = VerifierParams::new(
+        defaults.queries - 10,
+        defaults.fri_fold_po2,
+        defaults.fri_min_degree / 2,
+    )
+    .unwrap();
+    let ctx = VerifierContext {
+        params: Some(params),
+        ..Default::default()
+    };
+    let receipt = prover.prove_session(&ctx, &session).unwrap();
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+
+    // Parameters that no seal can be verified with are rejected up front.
+    for (queries, fri_fold_po2, fri_min_degree) in [
+        (0, defaults.fri_fold_po2, defaults.fri_min_degree),
+        (defaults.queries, 0, defaults.fri_min_degree),
+        (
+            defaults.queries,
+            usize::BITS as usize,
+            defaults.fri_min_degree,
+        ),
+        (defaults.queries, defaults.fri_fold_po2, 1),
+        (defaults.queries, defaults.fri_fold_po2, usize::MAX),
+    ] {
+        assert_eq!(
+            VerifierParams::new(queries, fri_fold_po2, fri_min_degree).unwrap_err(),
+            ProofError::InvalidVerifierParams
+        );
+        let ctx = VerifierContext {
+            params: Some(VerifierParams {
+                queries,
+                fri_fold_po2,
+                fri_min_degree,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            receipt
+                .verify_with_context(&ctx, MULTI_TEST_ID)
+                .unwrap_err(),
+            VerificationError::Proof(ProofError::InvalidVerifierParams)
+        );
+        assert!(prover.prove_session(&ctx, &session).is_err());
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn deterministic_verify() {
+    let receipt = prove_nothing("$default").unwrap();
+    let mut tampered = prove_nothing("$default").unwrap();
+    tampered.journal = b"tampered".to_vec();
+    // Verification with independently constructed contexts gives the same
+    // result every time, for valid and invalid receipts alike.
+    for receipt in [receipt, tampered] {
+        let first = receipt.verify_with_context(&VerifierContext::default(), MULTI_TEST_ID);
+        let second = receipt.verify_with_context(&VerifierContext::default(), MULTI_TEST_ID);
+        assert_eq!(first, second);
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_with_allowed_hashfns() {
+    let receipt = prove_nothing("$default").unwrap();
+    receipt
+        .verify_with_allowed_hashfns(MULTI_TEST_ID, &["sha-256", "poseidon"])
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_allowed_hashfns(MULTI_TEST_ID, &["poseidon"])
+            .unwrap_err(),
+        VerificationError::DisallowedHashFn("sha-256".into())
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_with_timing() {
+    let receipt = prove_nothing("$default").unwrap();
+    let timing = receipt
+        .verify_with_timing(&VerifierContext::default(), MULTI_TEST_ID)
+        .unwrap();
+    assert_eq!(timing.segments.len(), receipt.segments.len());
+    for segment in timing.segments.iter() {
+        assert_eq!(segment.hashfn, "sha-256");
+        assert!(segment.po2.unwrap() >= risc0_zkp::MIN_CYCLES_PO2 as u32);
+        assert!(segment.duration <= timing.total);
+    }
+    assert!(timing.journal <= timing.total);
+
+    assert_eq!(
+        receipt
+            .verify_with_timing(&VerifierContext::default(), Digest::default())
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_hex() {
+    let receipt = prove_nothing("$default").unwrap();
+    let image_id = hex::encode(Digest::from(MULTI_TEST_ID));
+    receipt.verify_hex(&image_id).unwrap();
+    receipt.verify_hex(&format!("0x{image_id}")).unwrap();
+    assert_eq!(
+        receipt.verify_hex("0x1234").unwrap_err(),
+        VerificationError::MalformedImageId
+    );
+    assert_eq!(
+        receipt
+            .verify_hex(&hex::encode(Digest::default()))
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+fn control_ids() {
+    use hex::FromHex;
+    let receipt = prove_nothing("$default").unwrap();
+    let control_ids = receipt.control_ids().unwrap();
+    assert_eq!(control_ids.len(), 1);
+    let control_id = control_ids.first().unwrap();
+    assert!(SHA256_CONTROL_ID
+        .iter()
+        .any(|id| Digest::from_hex(id).unwrap() == *control_id));
+    assert_eq!(
+        receipt.segments[0]
+            .verify_control_id(&VerifierContext::default())
+            .unwrap(),
+        *control_id
+    );
+}
+
+#[test]
+fn verify_image_id_bytes() {
+    let receipt = prove_nothing("$default").unwrap();
+    let image_id = Digest::from(MULTI_TEST_ID);
+    let bytes: [u8; 32] = image_id.as_bytes().try_into().unwrap();
+    receipt.verify(image_id).unwrap();
+    receipt.verify(bytes).unwrap();
+    let mut reversed = bytes;
+    reversed.reverse();
+    assert_eq!(
+        receipt.verify(reversed).unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_with_expected_post_pc() {
+    let receipt = prove_nothing("$default").unwrap();
+    let post = receipt.segments[0].get_metadata().unwrap().post;
+    receipt
+        .verify_with_expected_post_pc(MULTI_TEST_ID, post.pc)
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_expected_post_pc(MULTI_TEST_ID, post.pc + WORD_SIZE as u32)
+            .unwrap_err(),
+        VerificationError::PostStateMismatch
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_matches_metadata() {
+    let receipt = prove_nothing("$default").unwrap();
+    let mut expected = receipt.segments.last().unwrap().get_metadata().unwrap();
+    receipt
+        .verify_matches_metadata(MULTI_TEST_ID, &expected)
+        .unwrap();
+
+    expected.exit_code = ExitCode::Halted(1);
+    assert_eq!(
+        receipt
+            .verify_matches_metadata(MULTI_TEST_ID, &expected)
+            .unwrap_err(),
+        VerificationError::MetadataMismatch {
+            diff: "exit_code: Halted(0) != Halted(1)".into()
+        }
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn layout_version_mismatch() {
+    // Delegates to the built-in circuit, but reports the next layout version.
+    struct NextLayoutCircuit;
+
+    impl VerifierCircuit for NextLayoutCircuit {
+        fn verify(
+            &self,
+            suite: &HashSuite<BabyBear>,
+            seal: &[u32],
+            check_code: &dyn Fn(u32, &Digest) -> Result<(), ProofError>,
+            params: &VerifierParams,
+        ) -> Result<(), ProofError> {
+            CIRCUIT.verify(suite, seal, check_code, params)
+        }
+
+        fn layout_version(&self) -> u32 {
+            LAYOUT_VERSION + 1
+        }
+    }
+
+    let receipt = prove_nothing("$default").unwrap();
+    let segment = SegmentReceipt {
+        layout_version: LAYOUT_VERSION + 1,
+        ..receipt.as_segment(0).unwrap().clone()
+    };
+    assert_eq!(
+        segment
+            .verify_with_context(&VerifierContext::default())
+            .unwrap_err(),
+        VerificationError::LayoutVersionMismatch(LAYOUT_VERSION + 1)
+    );
+
+    // The layout version is taken from the circuit the seal is verified
+    // against, whatever its control ID.
+    let ctx = VerifierContext {
+        circuit: &NextLayoutCircuit,
+        ..Default::default()
+    };
+    assert_eq!(
+        receipt
+            .as_segment(0)
+            .unwrap()
+            .verify_with_context(&ctx)
+            .unwrap_err(),
+        VerificationError::LayoutVersionMismatch(LAYOUT_VERSION)
+    );
+    segment.verify_with_context(&ctx).unwrap();
+    // This crate can't decode the metadata of any other layout.
+    assert_eq!(
+        segment.get_metadata().unwrap_err(),
+        VerificationError::UnknownLayoutVersion(LAYOUT_VERSION + 1)
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_with_min_security() {
+    let receipt = prove_nothing("$default").unwrap();
+    receipt
+        .verify_with_min_security(MULTI_TEST_ID, 100.0)
+        .unwrap();
+    assert_eq!(
+        receipt
+            .verify_with_min_security(MULTI_TEST_ID, 100.5)
+            .unwrap_err(),
+        VerificationError::InsufficientSecurity
+    );
+
+    // Fewer queries provide less security.
+    let ctx = VerifierContext {
+        params: Some(VerifierParams {
+            queries: 40,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert_eq!(
+        receipt
+            .verify_with_min_security_and_context(&ctx, MULTI_TEST_ID, 100.0)
+            .unwrap_err(),
+        VerificationError::InsufficientSecurity
+    );
+
+    // The security of a receipt can't be estimated without its hash function.
+    let ctx = VerifierContext {
+        suites: BTreeMap::from([("blake2b".into(), Blake2bCpuHashSuite::new())]),
+        ..Default::default()
+    };
+    assert_eq!(
+        receipt
+            .verify_with_min_security_and_context(&ctx, MULTI_TEST_ID, 1.0)
+            .unwrap_err(),
+        VerificationError::InvalidHashSuite
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_batch() {
+    let image_id = Digest::from(MULTI_TEST_ID);
+    let valid = prove_nothing("$default").unwrap();
+    let mut tampered = prove_nothing("$default").unwrap();
+    tampered.journal = b"tampered".to_vec();
+    let batch = [
+        (valid, image_id),
+        (tampered, image_id),
+        (prove_nothing("$default").unwrap(), Digest::default()),
+        (prove_nothing("$poseidon").unwrap(), image_id),
+    ];
+    assert_eq!(
+        crate::receipt::verify_batch(&batch, &VerifierContext::default()),
+        [
+            Ok(()),
+            Err(VerificationError::JournalDigestMismatch),
+            Err(VerificationError::ImageVerificationError),
+            Ok(()),
+        ]
+    );
+}
+
 #[test]
 #[cfg_attr(feature = "cuda", serial)]
 fn check_image_id() {
@@ -85,9 +908,42 @@ fn check_image_id() {
         *word = word.wrapping_add(1);
     }
     assert_eq!(
-        receipt.verify(image_id).unwrap_err(),
-        VerificationError::ImageVerificationError
+        receipt.verify(image_id).unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn journal_hash_poseidon() {
+    let input = to_vec(&MultiTestSpec::ShaDigest { data: "abc".into() }).unwrap();
+    let env = ExecutorEnv::builder().add_input(&input).build().unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    assert!(!receipt.journal.is_empty());
+
+    // The guest commits to its journal with SHA-256, so checking the journal
+    // with Poseidon must fail.
+    let ctx = VerifierContext {
+        commitment: Arc::new(JournalHash::Poseidon),
+        ..Default::default()
+    };
+    assert_eq!(
+        receipt
+            .verify_with_context(&ctx, MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::JournalDigestMismatch
+    );
+    assert_ne!(
+        JournalHash::Poseidon.digest(&receipt.journal),
+        JournalHash::Sha256.digest(&receipt.journal)
     );
+
+    let ctx = VerifierContext {
+        commitment: Arc::new(JournalHash::Sha256),
+        ..Default::default()
+    };
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
 }
 
 #[test]
@@ -210,10 +1066,20 @@ fn pause_continue() {
         0
     );
 
+    receipt.verify(MULTI_TEST_ID).unwrap();
+    assert_eq!(receipt.pause_code().unwrap(), Some(0));
+    let resume_image_id = receipt.resume_image_id().unwrap();
+
     // Run until sys_halt
     let session = exec.run().unwrap();
     assert_eq!(session.exit_code, ExitCode::Halted(0));
-    session.prove().unwrap();
+    let receipt = session.prove().unwrap();
+    receipt.verify(resume_image_id).unwrap();
+    assert_eq!(receipt.pause_code().unwrap(), None);
+    assert_eq!(
+        receipt.resume_image_id().unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
 }
 
 #[test]
@@ -253,6 +1119,499 @@ fn continuation() {
     }
 }
 
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn continuation_segments() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let receipt = session.prove().unwrap();
+    for idx in 0..receipt.segments.len() {
+        let segment = receipt.as_segment(idx).unwrap();
+        assert_eq!(segment.index, idx as u32);
+        assert_eq!(
+            segment.decode_exit_code().unwrap(),
+            segment.get_metadata().unwrap().exit_code
+        );
+    }
+    assert!(receipt.as_segment(receipt.segments.len()).is_none());
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_sampled() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    assert_eq!(receipt.segments.len(), 2);
+    let ctx = VerifierContext::default();
+    receipt
+        .verify_sampled(&ctx, MULTI_TEST_ID, &[0, 1])
+        .unwrap();
+
+    // Corrupt the proof in the first seal, leaving its metadata intact.
+    let mut segment = receipt.as_segment(0).unwrap().clone();
+    let idx = segment.seal.len() / 2;
+    segment.seal[idx] ^= 1;
+    let mut segments = receipt.segments;
+    segments[0] = Box::new(segment);
+    let tampered = SessionReceipt::new(segments, receipt.journal);
+
+    // Only a sample that includes the corrupted segment detects it.
+    tampered.verify_sampled(&ctx, MULTI_TEST_ID, &[1]).unwrap();
+    assert!(tampered.verify_sampled(&ctx, MULTI_TEST_ID, &[0]).is_err());
+
+    // The structure of unsampled segments is still checked.
+    assert_eq!(
+        tampered
+            .verify_sampled(&ctx, Digest::default(), &[])
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+    assert_eq!(
+        tampered
+            .verify_sampled(&ctx, MULTI_TEST_ID, &[2])
+            .unwrap_err(),
+        VerificationError::ReceiptFormatError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn po2_histogram() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+
+    let mut expected = BTreeMap::new();
+    for i in 0..receipt.segments.len() {
+        let po2 = receipt.as_segment(i).unwrap().po2().unwrap();
+        assert!(po2 <= segment_limit_po2 as u32);
+        *expected.entry(po2).or_insert(0) += 1;
+    }
+    assert_eq!(receipt.po2_histogram().unwrap(), expected);
+    assert_eq!(
+        receipt.po2_histogram().unwrap().values().sum::<usize>(),
+        receipt.segments.len()
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn padded_cycles() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let segments = session.resolve().unwrap();
+    let receipt = session.prove().unwrap();
+
+    let padded: u64 = segments.iter().map(|segment| 1 << segment.po2).sum();
+    let user: u64 = segments
+        .iter()
+        .map(|segment| segment.insn_cycles as u64)
+        .sum();
+    assert_eq!(receipt.padded_cycles().unwrap(), padded);
+    assert!(user >= cycles as u64);
+    assert!(padded >= user);
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn split_at() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    assert_eq!(receipt.segments.len(), 2);
+    let journal = receipt.journal.clone();
+
+    let (prefix, suffix) = receipt.split_at(1).unwrap();
+    assert_eq!(prefix.segments.len(), 1);
+    assert!(prefix.journal.is_empty());
+    assert_eq!(suffix.segments.len(), 1);
+    assert_eq!(suffix.journal, journal);
+
+    let ctx = VerifierContext::default();
+    let cursor = prefix.verify_prefix(&ctx, MULTI_TEST_ID).unwrap();
+    suffix.verify(cursor.expected_pre_image_id).unwrap();
+
+    // Neither part may be empty.
+    for index in [0, 1] {
+        let receipt = prove_nothing("$default").unwrap();
+        assert_eq!(
+            receipt.split_at(index).unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+
+    // Only a split segment can end the first part.
+    let halted = prove_nothing("$default").unwrap().segments.pop().unwrap();
+    let mut segments = suffix.segments;
+    segments.insert(0, halted);
+    let receipt = SessionReceipt::new(segments, Vec::new());
+    assert_eq!(
+        receipt.split_at(1).unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn append_segment() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    let segments: Vec<SegmentReceipt> = receipt
+        .segments
+        .iter()
+        .map(|x| x.as_any().downcast_ref::<SegmentReceipt>().unwrap().clone())
+        .collect();
+    assert_eq!(segments.len(), 2);
+
+    let mut assembled = SessionReceipt::new(Vec::new(), receipt.journal.clone());
+    assembled
+        .append_segment(Box::new(segments[0].clone()))
+        .unwrap();
+    assert_eq!(
+        assembled
+            .append_segment(Box::new(segments[0].clone()))
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+    assembled
+        .append_segment(Box::new(segments[1].clone()))
+        .unwrap();
+    assert_eq!(
+        assembled
+            .append_segment(Box::new(segments[1].clone()))
+            .unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
+    assert_eq!(assembled.segments.len(), 2);
+    assembled.verify(MULTI_TEST_ID).unwrap();
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_transition() {
+    let receipt = prove_nothing("$default").unwrap();
+    let segment = receipt.segments[0]
+        .as_any()
+        .downcast_ref::<SegmentReceipt>()
+        .unwrap();
+    let metadata = segment.get_metadata().unwrap();
+    let ctx = VerifierContext::default();
+    segment
+        .verify_transition(&ctx, &metadata.pre, &metadata.post)
+        .unwrap();
+
+    let mut wrong = metadata.pre.clone();
+    wrong.pc += WORD_SIZE as u32;
+    assert_eq!(
+        segment
+            .verify_transition(&ctx, &wrong, &metadata.post)
+            .unwrap_err(),
+        VerificationError::PreStateMismatch
+    );
+    // The raw, unadjusted post PC must not be accepted.
+    let mut wrong = metadata.post.clone();
+    wrong.pc += WORD_SIZE as u32;
+    assert_eq!(
+        segment
+            .verify_transition(&ctx, &metadata.pre, &wrong)
+            .unwrap_err(),
+        VerificationError::PostStateMismatch
+    );
+}
+
+// The image IDs that `verify` chains together must be reproducible from the
+// public metadata of each segment, and must agree with the executor.
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn metadata_image_ids() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let segments = session.resolve().unwrap();
+    let receipt = session.prove().unwrap();
+    receipt.verify(MULTI_TEST_ID).unwrap();
+    assert_eq!(segments.len(), receipt.segments.len());
+
+    let chain = receipt.image_id_chain().unwrap();
+    assert_eq!(chain.len(), segments.len() + 1);
+    assert_eq!(chain[0], Digest::from(MULTI_TEST_ID));
+
+    let mut prev_image_id = Digest::from(MULTI_TEST_ID);
+    for (idx, (segment, receipt)) in segments.iter().zip(receipt.segments.iter()).enumerate() {
+        let metadata = receipt.get_metadata().unwrap();
+        let pre_image_id = compute_image_id(&metadata.pre.merkle_root, metadata.pre.pc);
+        let post_image_id = compute_image_id(&metadata.post.merkle_root, metadata.post.pc);
+        assert_eq!(pre_image_id, prev_image_id);
+        assert_eq!(pre_image_id, segment.pre_image.compute_id());
+        if metadata.exit_code == ExitCode::SystemSplit {
+            assert_eq!(post_image_id, segment.post_image_id);
+        }
+        assert_eq!(post_image_id, chain[idx + 1]);
+        prev_image_id = post_image_id;
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_cursor() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    assert_eq!(receipt.segments.len(), 2);
+    let ctx = VerifierContext::default();
+
+    let cursor = receipt.verify_prefix(&ctx, MULTI_TEST_ID).unwrap();
+    assert_eq!(cursor.next_index, 2);
+    cursor.verify_finish(&ctx, &receipt.journal).unwrap();
+
+    let mut cursor = VerifyCursor::new(MULTI_TEST_ID);
+    cursor
+        .verify_continue(&ctx, &receipt.segments[..1])
+        .unwrap();
+    assert_eq!(cursor.next_index, 1);
+    assert_eq!(
+        cursor.verify_finish(&ctx, &receipt.journal).unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
+
+    // The cursor can be persisted and verification resumed from it.
+    let mut cursor: VerifyCursor = from_slice(&to_vec(&cursor).unwrap()).unwrap();
+    assert_eq!(
+        cursor
+            .verify_continue(&ctx, &receipt.segments[..1])
+            .unwrap_err(),
+        VerificationError::ImageVerificationError
+    );
+    assert_eq!(cursor.next_index, 1);
+    cursor
+        .verify_continue(&ctx, &receipt.segments[1..])
+        .unwrap();
+    assert_eq!(cursor.next_index, 2);
+    cursor.verify_finish(&ctx, &receipt.journal).unwrap();
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn session_receipt_builder() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let receipt = exec.run().unwrap().prove().unwrap();
+    assert_eq!(receipt.segments.len(), 2);
+    let first = || Box::new(receipt.as_segment(0).unwrap().clone());
+    let second = || Box::new(receipt.as_segment(1).unwrap().clone());
+
+    let built = SessionReceipt::builder()
+        .add_segment(first())
+        .add_segment(second())
+        .journal(receipt.journal.clone())
+        .build()
+        .unwrap();
+    assert_eq!(built, receipt);
+    built.verify(MULTI_TEST_ID).unwrap();
+
+    assert_eq!(
+        SessionReceipt::builder().build().unwrap_err(),
+        VerificationError::ReceiptFormatError
+    );
+    assert_eq!(
+        SessionReceipt::builder()
+            .add_segment(first())
+            .build()
+            .unwrap_err(),
+        VerificationError::UnexpectedExitCode
+    );
+    assert_eq!(
+        SessionReceipt::builder()
+            .add_segment(second())
+            .build()
+            .unwrap_err(),
+        VerificationError::ReceiptFormatError
+    );
+    assert_eq!(
+        SessionReceipt::builder()
+            .add_segment(first())
+            .add_segment(first())
+            .build()
+            .unwrap_err(),
+        VerificationError::ReceiptFormatError
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn verify_control_root() {
+    use hex::FromHex;
+
+    use crate::{
+        control_id::{BLAKE2B_CONTROL_ID, POSEIDON_CONTROL_ID, SHA256_CONTROL_ID},
+        receipt::{compute_control_root, ControlInclusionProof},
+    };
+
+    let receipt = prove_nothing("$default").unwrap();
+    let control_ids: Vec<Digest> = POSEIDON_CONTROL_ID
+        .into_iter()
+        .chain(SHA256_CONTROL_ID)
+        .chain(BLAKE2B_CONTROL_ID)
+        .map(|x| Digest::from_hex(x).unwrap())
+        .collect();
+    let mut ctx = VerifierContext {
+        control_root: Some(compute_control_root(&control_ids)),
+        ..Default::default()
+    };
+    assert!(matches!(
+        receipt
+            .verify_with_context(&ctx, MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::Proof(ProofError::ControlVerificationError { control_id })
+            if control_ids.contains(&control_id)
+    ));
+
+    for (idx, control_id) in control_ids.iter().enumerate() {
+        let proof = ControlInclusionProof::new(&control_ids, idx).unwrap();
+        ctx.control_proofs.insert(*control_id, proof);
+    }
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+
+    // A root that doesn't commit to the control IDs in use is rejected.
+    ctx.control_root = Some(compute_control_root(&control_ids[1..]));
+    assert!(matches!(
+        receipt
+            .verify_with_context(&ctx, MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::Proof(ProofError::ControlVerificationError { control_id })
+            if control_ids.contains(&control_id)
+    ));
+
+    // A root may also commit to control IDs that aren't built in, which are
+    // accepted for a circuit that reports them.
+    let mut control_ids = control_ids;
+    control_ids.push(RenamedCircuit::CONTROL_ID);
+    let mut ctx = VerifierContext {
+        control_root: Some(compute_control_root(&control_ids)),
+        ..Default::default()
+    };
+    for (idx, control_id) in control_ids.iter().enumerate() {
+        let proof = ControlInclusionProof::new(&control_ids, idx).unwrap();
+        ctx.control_proofs.insert(*control_id, proof);
+    }
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    ctx.circuit = &RenamedCircuit;
+    receipt.verify_with_context(&ctx, MULTI_TEST_ID).unwrap();
+    for segment in receipt.segments.iter() {
+        assert_eq!(
+            segment.verify_control_id(&ctx).unwrap(),
+            RenamedCircuit::CONTROL_ID
+        );
+    }
+}
+
+#[test]
+#[cfg_attr(feature = "cuda", serial)]
+fn mixed_hashfn() {
+    let segment_limit_po2 = 16; // 64k cycles
+    let cycles = 1 << segment_limit_po2;
+    let spec = &to_vec(&MultiTestSpec::BusyLoop { cycles }).unwrap();
+    let env = ExecutorEnv::builder()
+        .add_input(&spec)
+        .segment_limit_po2(segment_limit_po2)
+        .build()
+        .unwrap();
+    let mut exec = LocalExecutor::from_elf(env, MULTI_TEST_ELF).unwrap();
+    let session = exec.run().unwrap();
+    let segments = session.resolve().unwrap();
+    assert_eq!(segments.len(), 2);
+
+    let ctx = VerifierContext::default();
+    let first = get_prover("$default")
+        .prove_segment(&ctx, &segments[0])
+        .unwrap();
+    let second = get_prover("$poseidon")
+        .prove_segment(&ctx, &segments[1])
+        .unwrap();
+    assert_ne!(first.hashfn, second.hashfn);
+    let receipt = SessionReceipt::new(
+        vec![Box::new(first), Box::new(second)],
+        session.journal.clone(),
+    );
+    receipt.verify(MULTI_TEST_ID).unwrap();
+
+    let mut ctx = VerifierContext::default();
+    ctx.suites.remove("poseidon");
+    assert_eq!(
+        receipt
+            .verify_with_context(&ctx, MULTI_TEST_ID)
+            .unwrap_err(),
+        VerificationError::InvalidHashSuite
+    );
+}
+
 // These tests come from:
 // https://github.com/riscv-software-src/riscv-tests
 // They were built using the toolchain from:
@@ -74,17 +74,64 @@
 //! [serde](crate::serde) module, which can be used to read data from the
 //! journal as the same type it was written to the journal. If you prefer, you
 //! can also directly access the [SessionReceipt::journal] as a `Vec<u8>`.
+//!
+//! # Logging
+//!
+//! Verification logs under two targets, so that each can be enabled on its
+//! own, e.g. with `RUST_LOG=risc0_zkvm::verify::mismatch=debug`:
+//!
+//! * `risc0_zkvm::verify::metadata` logs the metadata of each segment as it is
+//!   verified, at the `debug` level.
+//! * `risc0_zkvm::verify::mismatch` logs the expected and actual values when a
+//!   check fails, at the `debug` level. The journal can be large, so it is only
+//!   dumped in full at the `trace` level.
+
+mod batch;
+#[cfg(feature = "zstd")]
+mod compressed;
+mod control;
+mod detached;
+mod error;
+mod fake;
+mod journal_set;
+mod lazy;
+#[cfg(feature = "mmap")]
+mod mapped;
+#[cfg(feature = "rmp-serde")]
+mod msgpack;
+#[cfg(feature = "remote")]
+mod remote;
+mod seal;
+#[cfg(feature = "ed25519")]
+mod signed;
+#[cfg(feature = "std")]
+mod stream;
+mod verified;
 
-use alloc::{boxed::Box, collections::BTreeMap, string::String, vec::Vec};
-use core::fmt::Debug;
+use alloc::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    cell::Cell,
+    fmt::{self, Debug},
+};
 
 use anyhow::Result;
 use dyn_partial_eq::{dyn_partial_eq, DynPartialEq};
-use risc0_circuit_rv32im::layout;
-use risc0_core::field::baby_bear::BabyBear;
+use risc0_circuit_rv32im::{layout, CircuitImpl};
+use risc0_core::field::{
+    baby_bear::{BabyBear, BabyBearElem, BabyBearExtElem},
+    Elem as _, ExtElem as _,
+};
 use risc0_zkp::{
+    adapter::{CircuitCoreDef, CircuitInfo},
     core::{
-        digest::Digest,
+        digest::{Digest, DIGEST_BYTES},
         hash::{
             blake2b::Blake2bCpuHashSuite,
             poseidon::PoseidonHashSuite,
@@ -93,27 +140,50 @@ use risc0_zkp::{
         },
     },
     layout::Buffer,
-    verify::VerificationError,
+    verify::{VerificationError as ProofError, VerifierParams},
+};
+use risc0_zkvm_platform::{memory::MEM_SIZE, WORD_SIZE};
+use serde::{
+    de::{self, DeserializeOwned, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
 };
-use risc0_zkvm_platform::WORD_SIZE;
-use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "zstd")]
+pub use self::compressed::CompressedSegmentReceipt;
+#[cfg(feature = "remote")]
+pub use self::remote::{RemoteVerifier, RemoteVerifyRequest, RemoteVerifyResponse};
+pub use self::{
+    batch::BatchVerifier,
+    control::{compute_control_root, ControlInclusionProof},
+    detached::DetachedSessionReceipt,
+    error::VerificationError,
+    fake::FakeReceipt,
+    journal_set::{compute_journal_root, journal_inclusion_proof},
+    lazy::{segment_fingerprint, LazySessionReceipt, SegmentCache},
+    seal::Seal,
+    verified::VerifiedReceipt,
+};
 use crate::{
     control_id::{BLAKE2B_CONTROL_ID, POSEIDON_CONTROL_ID, SHA256_CONTROL_ID},
-    sha::{
-        self,
-        rust_crypto::{Digest as _, Sha256},
-    },
+    sha::{self, Sha256 as _},
 };
 
+// The log targets described in the module documentation.
+const LOG_METADATA: &str = "risc0_zkvm::verify::metadata";
+const LOG_MISMATCH: &str = "risc0_zkvm::verify::mismatch";
+
 /// Indicates how a Segment or Session's execution has terminated
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ExitCode {
     /// This indicates when a system-initiated split has occured due to the
     /// segment limit being exceeded.
     SystemSplit,
 
     /// This indicates that the session limit has been reached.
+    ///
+    /// This is only ever reported by the executor, which stops with an error
+    /// rather than producing a segment that ends this way. The circuit has no
+    /// encoding for it, so it never appears in the metadata of a receipt.
     SessionLimit,
 
     /// A user may manually pause a session so that it can be resumed at a later
@@ -125,9 +195,27 @@ pub enum ExitCode {
     Halted(u32),
 }
 
+impl ExitCode {
+    /// Returns the exit code returned by the guest, if any.
+    ///
+    /// This is `Some` for [ExitCode::Halted] and [ExitCode::Paused], and `None`
+    /// for exit codes that were not chosen by the guest.
+    pub fn user_code(&self) -> Option<u32> {
+        match self {
+            ExitCode::Halted(user_exit) | ExitCode::Paused(user_exit) => Some(*user_exit),
+            ExitCode::SystemSplit | ExitCode::SessionLimit => None,
+        }
+    }
+}
+
 /// Represents the public state of a segment, needed for continuations and
 /// receipt verification.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+///
+/// The image ID of a state combines its `merkle_root` and `pc`, see
+/// [compute_image_id]. The image ID that a receipt is verified against is the
+/// image ID of the state that its first segment starts from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
 pub struct SystemState {
     /// The program counter.
     pub pc: u32,
@@ -139,7 +227,8 @@ pub struct SystemState {
 
 /// Data associated with a receipt which is used for both input and
 /// output of global state.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(deny_unknown_fields)]
 pub struct ReceiptMetadata {
     /// The [SystemState] of a segment just before execution has begun.
     pub pre: SystemState,
@@ -154,34 +243,96 @@ pub struct ReceiptMetadata {
     pub input: Digest,
 
     /// A [Digest] of the journal, from the viewpoint of the guest.
+    ///
+    /// The circuit only commits to this digest, never to the journal itself:
+    /// its global outputs hold the exit codes, the input and output digests
+    /// and the pre- and post-states, with no room for journal bytes. So even a
+    /// journal of a few bytes must be supplied alongside the seal, in
+    /// [SessionReceipt::journal], to be checked against this digest.
     pub output: Digest,
 }
 
+/// A field that differs between two [ReceiptMetadata] values, as returned by
+/// [ReceiptMetadata::diff].
+///
+/// Each variant holds the value from the metadata `diff` was called on,
+/// followed by the value from the metadata it was compared with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataDiff {
+    /// The program counter of the pre-state differs.
+    PrePc(u32, u32),
+    /// The memory image merkle root of the pre-state differs.
+    PreMerkleRoot(Digest, Digest),
+    /// The program counter of the post-state differs.
+    PostPc(u32, u32),
+    /// The memory image merkle root of the post-state differs.
+    PostMerkleRoot(Digest, Digest),
+    /// The exit code differs.
+    ExitCode(ExitCode, ExitCode),
+    /// The input digest differs.
+    Input(Digest, Digest),
+    /// The output digest differs.
+    Output(Digest, Digest),
+}
+
+impl core::fmt::Display for MetadataDiff {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            MetadataDiff::PrePc(left, right) => {
+                write!(f, "pre.pc: 0x{left:08x} != 0x{right:08x}")
+            }
+            MetadataDiff::PreMerkleRoot(left, right) => {
+                write!(f, "pre.merkle_root: {left} != {right}")
+            }
+            MetadataDiff::PostPc(left, right) => {
+                write!(f, "post.pc: 0x{left:08x} != 0x{right:08x}")
+            }
+            MetadataDiff::PostMerkleRoot(left, right) => {
+                write!(f, "post.merkle_root: {left} != {right}")
+            }
+            MetadataDiff::ExitCode(left, right) => {
+                write!(f, "exit_code: {left:?} != {right:?}")
+            }
+            MetadataDiff::Input(left, right) => write!(f, "input: {left} != {right}"),
+            MetadataDiff::Output(left, right) => write!(f, "output: {left} != {right}"),
+        }
+    }
+}
+
 /// A receipt attesting to the execution of a Session.
 ///
 /// A SessionReceipt attests that the `journal` was produced by executing a
 /// [crate::Session] based on a specified memory image. This image is _not_
 /// included in the receipt and must be provided by the verifier when calling
 /// [SessionReceipt::verify].
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct SessionReceipt {
     /// The constituent [Receipt]s.
     ///
     /// Together these can be used by [SessionReceipt::verify] to
     /// cryptographically prove that this full Session was faithfully executed.
+    /// Each segment is verified with the hash suite it names, so segments
+    /// proven with different hash functions may be mixed in one Session as
+    /// long as the [VerifierContext] has a suite for each of them.
     pub segments: Vec<Box<dyn Receipt>>,
 
     /// The public data written by the guest in this Session.
     ///
     /// This data is cryptographically authenticated in
-    /// [SessionReceipt::verify].
+    /// [SessionReceipt::verify], by checking it against the digest committed
+    /// in [ReceiptMetadata::output]. The seal doesn't contain the journal, so
+    /// it must be kept with the receipt however short it is.
     pub journal: Vec<u8>,
 }
 
 /// Provide common functionality implemented by all receipt types.
+///
+/// Receipts must be `Send + Sync`, so that a [SessionReceipt] can be moved to
+/// or shared with other threads, e.g. to verify receipts in parallel.
 #[dyn_partial_eq]
 #[typetag::serde(tag = "type")]
-pub trait Receipt: Debug {
+pub trait Receipt: Debug + Send + Sync {
     /// Verify the integrity of this receipt.
     #[must_use]
     fn verify(&self) -> Result<(), VerificationError> {
@@ -192,15 +343,72 @@ pub trait Receipt: Debug {
     #[must_use]
     fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError>;
 
+    /// Verify the integrity of this receipt and return its metadata.
+    ///
+    /// This is equivalent to calling [Receipt::verify_with_context] followed
+    /// by [Receipt::get_metadata].
+    fn verify_metadata(&self, ctx: &VerifierContext) -> Result<ReceiptMetadata, VerificationError> {
+        self.verify_with_context(ctx)?;
+        self.get_metadata()
+    }
+
     /// Return the metadata for this receipt.
     fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError>;
 
     /// Return the seal for this receipt.
-    fn get_seal(&self) -> &[u32];
+    ///
+    /// Returns an error if the seal can't be recovered from the receipt, e.g.
+    /// because it is stored in a form that fails to decode.
+    fn get_seal(&self) -> Result<&[u32], VerificationError>;
 
     /// Return the seal for this receipt, as a slice of bytes.
-    fn get_seal_bytes(&self) -> &[u8] {
-        bytemuck::cast_slice(self.get_seal())
+    ///
+    /// The bytes are the words of [Receipt::get_seal] in memory order, so each
+    /// word appears in the host's byte order, i.e. little-endian on all
+    /// platforms that the zkVM targets.
+    fn get_seal_bytes(&self) -> Result<&[u8], VerificationError> {
+        self.get_seal().map(bytemuck::cast_slice)
+    }
+
+    /// Return the name of the hash function used to create this receipt.
+    fn hashfn(&self) -> &str;
+
+    /// Return a copy of this receipt in a new [Box].
+    ///
+    /// This is what makes `Box<dyn Receipt>`, and so [SessionReceipt],
+    /// [Clone]. Implementations return `Box::new(self.clone())`.
+    fn clone_box(&self) -> Box<dyn Receipt>;
+
+    /// Verify the integrity of this receipt, and return the control ID of the
+    /// circuit that it was proven against.
+    ///
+    /// The control ID is the one the verifier recovers from the seal, so it
+    /// is bound to the proof rather than claimed by the receipt. Receipts that
+    /// aren't proven against any circuit, such as [FakeReceipt], return
+    /// [VerificationError::ReceiptFormatError] after being verified.
+    fn verify_control_id(&self, ctx: &VerifierContext) -> Result<Digest, VerificationError> {
+        self.verify_with_context(ctx)?;
+        Err(VerificationError::ReceiptFormatError)
+    }
+
+    /// Return a rough estimate of the cost of verifying this receipt, in
+    /// arbitrary cost points.
+    ///
+    /// This is meant for scheduling, e.g. to prioritize or reject receipts
+    /// before verifying them, and does no verification work. It isn't
+    /// precise, but grows with the actual verification time. By default it is
+    /// the length of the seal in words: the verifier reads the whole seal, and
+    /// the seal grows with the po2 of the segment, as do the number of Merkle
+    /// branches checked. A receipt whose seal can't be recovered is given the
+    /// maximum cost.
+    fn verify_cost_estimate(&self) -> u64 {
+        self.get_seal().map_or(u64::MAX, |seal| seal.len() as u64)
+    }
+}
+
+impl Clone for Box<dyn Receipt> {
+    fn clone(&self) -> Self {
+        self.clone_box()
     }
 }
 
@@ -208,7 +416,11 @@ pub trait Receipt: Debug {
 ///
 /// A SegmentReceipt attests that a [crate::Segment] was executed in a manner
 /// consistent with the [ReceiptMetadata] included in the receipt.
+///
+/// Prefer [SegmentReceipt::new] over constructing this directly, as it rejects
+/// an unknown [SegmentReceipt::hashfn] up front rather than at verification.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct SegmentReceipt {
     /// The cryptographic data attesting to the validity of the code execution.
     ///
@@ -217,220 +429,2453 @@ pub struct SegmentReceipt {
     /// was faithfully executed. It is largely opaque cryptographic data, but
     /// contains a non-opaque metadata component, which can be conveniently
     /// accessed with [SegmentReceipt::get_metadata].
-    pub seal: Vec<u32>,
+    ///
+    /// A seal larger than [MAX_SEAL_BYTES] fails to deserialize, and fails to
+    /// verify with [VerificationError::ReceiptTooLarge]. A seal loaded by
+    /// [SessionReceipt::from_mmap] stays in the mapped file; see [Seal].
+    pub seal: Seal,
 
     /// Segment index within the [SessionReceipt]
     pub index: u32,
 
     /// Name of the hash function used to create this receipt.
     pub hashfn: String,
-}
 
-/// Context available to the verification process.
-pub struct VerifierContext {
-    /// A registry of hash functions to be used by the verification process.
-    pub suites: BTreeMap<String, HashSuite<BabyBear>>,
+    /// The version of the circuit's output layout that the metadata in the
+    /// seal is encoded with.
+    ///
+    /// Receipts produced by this crate use [LAYOUT_VERSION]. This isn't
+    /// covered by the seal, so verification checks it against the circuit
+    /// that the seal was proven with.
+    ///
+    /// Receipts serialized before this was recorded default to
+    /// [LAYOUT_VERSION] when the field names are serialized too, as for the
+    /// segments of a [SessionReceipt] in any format, or for a JSON receipt. A
+    /// bare [SegmentReceipt] in the word-oriented [crate::serde] format has no
+    /// field names, and so can only be decoded with this field present.
+    #[serde(default = "default_layout_version")]
+    pub layout_version: u32,
 }
 
-impl SessionReceipt {
-    /// Construct a new SessionReceipt
-    pub fn new(segments: Vec<Box<dyn Receipt>>, journal: Vec<u8>) -> Self {
-        Self { segments, journal }
-    }
+/// The version of the circuit's output layout used by receipts produced by
+/// this crate.
+///
+/// This changes whenever the layout of the rv32im circuit's outputs changes.
+/// Each [SegmentReceipt] records the version it was proven with, so that its
+/// [ReceiptMetadata] is never decoded with the wrong layout. This is the only
+/// layout this crate can decode; version 1 is the first versioned layout, so
+/// there are no prior layouts to decode yet.
+pub const LAYOUT_VERSION: u32 = 1;
 
-    /// Verifies the integrity of this receipt.
-    ///
-    /// Uses the ZKP system to cryptographically verify that each constituent
-    /// Segment has a valid receipt, and validates that these [SegmentReceipt]s
-    /// stitch together correctly, and that the initial memory image matches the
-    /// given `image_id` parameter.
-    #[must_use]
-    pub fn verify(&self, image_id: impl Into<Digest>) -> Result<(), VerificationError> {
-        self.verify_with_context(&VerifierContext::default(), image_id)
+/// The largest seal, in bytes, that a receipt may carry.
+///
+/// This is well above the size of a seal for a segment of
+/// [risc0_zkp::MAX_CYCLES_PO2] cycles, and bounds the memory used to decode a
+/// seal from an untrusted encoding, such as a compressed one.
+pub const MAX_SEAL_BYTES: usize = 1 << 24;
+
+// Returns [VerificationError::ReceiptTooLarge] if a seal of `words` words is
+// larger than [MAX_SEAL_BYTES]. Every seal size limit is checked here, so
+// that they all agree.
+pub(crate) fn check_seal_len(words: usize) -> Result<(), VerificationError> {
+    if words > MAX_SEAL_BYTES / WORD_SIZE {
+        return Err(VerificationError::ReceiptTooLarge);
     }
+    Ok(())
+}
 
-    /// Verifies the integrity of this receipt.
-    ///
-    /// Uses the ZKP system to cryptographically verify that each constituent
-    /// Segment has a valid receipt, and validates that these [SegmentReceipt]s
-    /// stitch together correctly, and that the initial memory image matches the
-    /// given `image_id` parameter.
-    #[must_use]
-    pub fn verify_with_context(
-        &self,
-        ctx: &VerifierContext,
-        image_id: impl Into<Digest>,
-    ) -> Result<(), VerificationError> {
-        let (final_receipt, receipts) = self
-            .segments
-            .as_slice()
-            .split_last()
-            .ok_or(VerificationError::ReceiptFormatError)?;
-        let mut prev_image_id = image_id.into();
-        for receipt in receipts {
-            receipt.verify_with_context(ctx)?;
-            let metadata = receipt.get_metadata()?;
-            log::debug!("metadata: {metadata:#?}");
-            if prev_image_id != metadata.pre.compute_image_id() {
-                return Err(VerificationError::ImageVerificationError);
+// Deserializes a seal, rejecting one larger than [MAX_SEAL_BYTES] as soon as
+// its declared length is known, before reading or allocating for the rest of
+// it.
+fn deserialize_seal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u32>, D::Error> {
+    struct SealVisitor;
+
+    impl<'de> Visitor<'de> for SealVisitor {
+        type Value = Vec<u32>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "a seal of at most {MAX_SEAL_BYTES} bytes")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u32>, A::Error> {
+            if let Some(len) = seq.size_hint() {
+                check_seal_len(len).map_err(de::Error::custom)?;
             }
-            if metadata.exit_code != ExitCode::SystemSplit {
-                return Err(VerificationError::UnexpectedExitCode);
+            let mut seal = Vec::new();
+            while let Some(word) = seq.next_element()? {
+                seal.push(word);
+                check_seal_len(seal.len()).map_err(de::Error::custom)?;
             }
-            prev_image_id = metadata.post.compute_image_id();
+            Ok(seal)
         }
-        final_receipt.verify_with_context(ctx)?;
-        let metadata = final_receipt.get_metadata()?;
-        log::debug!("final: {metadata:#?}");
-        if prev_image_id != metadata.pre.compute_image_id() {
-            return Err(VerificationError::ImageVerificationError);
+    }
+
+    deserializer.deserialize_seq(SealVisitor)
+}
+
+// Returns a SHA-256 digest of `value` in the zkVM's serde format, by which
+// receipts are fingerprinted.
+pub(crate) fn serialized_digest<T: Serialize + ?Sized>(
+    value: &T,
+) -> Result<Digest, VerificationError> {
+    let words = crate::serde::to_vec(value).map_err(|err| match err {
+        crate::serde::Error::SerializeTooLarge => VerificationError::ReceiptTooLarge,
+        _ => VerificationError::ReceiptFormatError,
+    })?;
+    Ok(*sha::Impl::hash_words(&words))
+}
+
+fn default_layout_version() -> u32 {
+    LAYOUT_VERSION
+}
+
+// Checks that metadata encoded with the given layout version can be decoded,
+// which is only the case for [LAYOUT_VERSION].
+fn check_layout_version(layout_version: u32) -> Result<(), VerificationError> {
+    if layout_version != LAYOUT_VERSION {
+        return Err(VerificationError::UnknownLayoutVersion(layout_version));
+    }
+    Ok(())
+}
+
+/// The hash function used to commit the journal to [ReceiptMetadata::output].
+///
+/// The guest commits to its journal by writing a digest of it to the `output`
+/// field of the [ReceiptMetadata]. The verifier must hash the journal with the
+/// same function in order to check it against that digest, so the
+/// [VerifierContext::commitment] must match the hash used by the guest. The
+/// standard zkVM guest always uses SHA-256; other values are only useful for
+/// guests that have been modified to commit their journal differently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JournalHash {
+    /// SHA-256, as used by the standard zkVM guest.
+    #[default]
+    Sha256,
+
+    /// Poseidon over the BabyBear field, where each byte of the journal is
+    /// absorbed as a single field element.
+    Poseidon,
+}
+
+/// A bounded cache of successful receipt verifications.
+///
+/// Entries are keyed by the [SessionReceipt::fingerprint] of a receipt, the
+/// image ID it was verified against, and the [VerifierContext] it was verified
+/// with, and the least recently used entry is evicted once the cache is full.
+/// Only successes are cached, so a receipt that failed is verified again in
+/// full every time.
+///
+/// Contexts are compared by the value of their settings, except for the
+/// implementations of their hash suites, [VerifierContext::commitment] and
+/// [VerifierContext::circuit], which can't be compared by value; those are
+/// identified by [VerifierContext::cache_id] instead, along with the names of
+/// the suites.
+///
+/// A cached result is trusted as if the receipt had been verified again, so the
+/// cache must be protected by the same trust boundary as the receipts
+/// themselves: anyone able to insert entries can make arbitrary receipts appear
+/// valid.
+pub struct VerificationCache {
+    capacity: usize,
+    // The time each entry was last used, and the entries in order of that
+    // time, so that the least recently used entry is found without a scan.
+    entries: BTreeMap<(Digest, Digest, Digest), u64>,
+    order: BTreeMap<u64, (Digest, Digest, Digest)>,
+    clock: u64,
+}
+
+impl VerificationCache {
+    /// Construct an empty cache holding at most `capacity` results.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: BTreeMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
         }
+    }
 
-        let digest = Sha256::digest(&self.journal);
-        let digest_words: &[u32] = bytemuck::cast_slice(digest.as_slice());
-        let output_words = metadata.output.as_words();
-        let is_journal_valid = || {
-            (self.journal.is_empty() && output_words.iter().all(|x| *x == 0))
-                || digest_words == output_words
-        };
-        if !is_journal_valid() {
-            log::debug!(
-                "journal: \"{}\", digest: 0x{}, output: 0x{}, {:?}",
-                hex::encode(&self.journal),
-                hex::encode(bytemuck::cast_slice(digest_words)),
-                hex::encode(bytemuck::cast_slice(output_words)),
-                self.journal
-            );
-            return Err(VerificationError::JournalDigestMismatch);
+    /// Returns the number of results in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the cache holds no results.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn contains(&mut self, key: &(Digest, Digest, Digest)) -> bool {
+        if !self.entries.contains_key(key) {
+            return false;
         }
+        self.touch(*key);
+        true
+    }
 
-        if metadata.exit_code == ExitCode::SystemSplit {
-            return Err(VerificationError::UnexpectedExitCode);
+    fn insert(&mut self, key: (Digest, Digest, Digest)) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.touch(key);
+        if self.entries.len() > self.capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
         }
+    }
 
-        Ok(())
+    // Marks `key` as the most recently used entry, adding it if it is new.
+    fn touch(&mut self, key: (Digest, Digest, Digest)) {
+        self.clock += 1;
+        if let Some(last_used) = self.entries.insert(key, self.clock) {
+            self.order.remove(&last_used);
+        }
+        self.order.insert(self.clock, key);
     }
 }
 
-#[typetag::serde]
-impl Receipt for SegmentReceipt {
-    fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
-        use hex::FromHex;
-        let check_code = |_, control_id: &Digest| -> Result<(), VerificationError> {
-            POSEIDON_CONTROL_ID
-                .into_iter()
-                .chain(SHA256_CONTROL_ID)
-                .chain(BLAKE2B_CONTROL_ID)
-                .find(|x| Digest::from_hex(x).unwrap() == *control_id)
-                .map(|_| ())
-                .ok_or(VerificationError::ControlVerificationError)
-        };
-        let suite = ctx
-            .suites
-            .get(&self.hashfn)
-            .ok_or(VerificationError::InvalidHashSuite)?;
-        risc0_zkp::verify::verify(&crate::CIRCUIT, suite, &self.seal, check_code)
+/// A breakdown of the time taken by [SessionReceipt::verify_with_timing].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct VerifyTiming {
+    /// The time taken to verify each segment, in order.
+    pub segments: Vec<SegmentTiming>,
+
+    /// The time taken to check the journal against the output of the final
+    /// segment.
+    pub journal: std::time::Duration,
+
+    /// The total time taken to verify the receipt.
+    pub total: std::time::Duration,
+}
+
+/// The time taken to verify one segment of a [SessionReceipt].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct SegmentTiming {
+    /// The name of the hash function the segment was proven with.
+    pub hashfn: String,
+
+    /// The log2 of the number of cycles in the segment's trace, or `None` for
+    /// a receipt without a seal, such as a [FakeReceipt] verified in dev mode.
+    pub po2: Option<u32>,
+
+    /// The time taken to verify the segment's seal and check that it
+    /// continues from the previous segment.
+    pub duration: std::time::Duration,
+}
+
+/// An advisory issue with a receipt that verified successfully, reported by
+/// [SessionReceipt::verify_with_warnings].
+///
+/// Warnings flag receipts that a deployment may want to log or act on as a
+/// matter of policy; none of them casts doubt on the validity of the proof.
+/// More variants may be added in future releases.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VerifyWarning {
+    /// The segment at position `segment` was proven with a hash function
+    /// listed in [VerifierContext::deprecated_hashfns].
+    DeprecatedHashFn {
+        /// The position of the segment in [SessionReceipt::segments].
+        segment: usize,
+
+        /// The name of the hash function.
+        hashfn: String,
+    },
+
+    /// The journal holds this many bytes, which is more than
+    /// [VerifierContext::large_journal_bytes].
+    LargeJournal(usize),
+
+    /// The guest exited with this nonzero user exit code, see
+    /// [ExitCode::user_code].
+    NonzeroExit(u32),
+}
+
+/// A builder pattern used to construct a [SessionReceipt].
+#[derive(Default)]
+pub struct SessionReceiptBuilder {
+    segments: Vec<Box<dyn Receipt>>,
+    journal: Vec<u8>,
+}
+
+impl SessionReceiptBuilder {
+    /// Add a segment receipt after those already added.
+    pub fn add_segment(&mut self, receipt: Box<dyn Receipt>) -> &mut Self {
+        self.segments.push(receipt);
+        self
     }
 
-    fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
-        let elems = bytemuck::cast_slice(&self.seal);
-        ReceiptMetadata::decode_from_io(layout::OutBuffer(elems))
+    /// Set the journal of the receipt.
+    pub fn journal(&mut self, journal: Vec<u8>) -> &mut Self {
+        self.journal = journal;
+        self
     }
 
-    fn get_seal(&self) -> &[u32] {
-        self.seal.as_slice()
+    /// Finalize this builder to construct a [SessionReceipt].
+    ///
+    /// Checks that there is at least one segment, that segment indices count up
+    /// from zero, that each segment continues from the state the previous one
+    /// ended in, and that only the final segment ends with an exit code other
+    /// than [ExitCode::SystemSplit]. These checks are structural only; seals
+    /// are not verified until [SessionReceipt::verify]. The segments and
+    /// journal are moved into the receipt, leaving this builder empty.
+    pub fn build(&mut self) -> Result<SessionReceipt, VerificationError> {
+        let segments = core::mem::take(&mut self.segments);
+        let mut receipt = SessionReceipt::new(Vec::new(), core::mem::take(&mut self.journal));
+        for (idx, segment) in segments.into_iter().enumerate() {
+            if segment_index(segment.as_ref()).map_or(false, |index| index as usize != idx) {
+                return Err(VerificationError::ReceiptFormatError);
+            }
+            receipt.append_segment(segment)?;
+        }
+        let final_receipt = receipt
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        if final_receipt.get_metadata()?.exit_code == ExitCode::SystemSplit {
+            return Err(VerificationError::UnexpectedExitCode);
+        }
+        Ok(receipt)
     }
 }
 
-impl SystemState {
-    fn decode_from_io(
-        io: layout::OutBuffer,
-        sys_state: &layout::SystemState,
-    ) -> Result<Self, VerificationError> {
-        let bytes: Vec<u8> = io
-            .tree(sys_state.image_id)
-            .get_bytes()
-            .or(Err(VerificationError::ReceiptFormatError))?;
-        let pc = io
-            .tree(sys_state.pc)
-            .get_u32()
-            .or(Err(VerificationError::ReceiptFormatError))?;
-        let merkle_root = Digest::try_from(bytes).or(Err(VerificationError::ReceiptFormatError))?;
-        Ok(Self { pc, merkle_root })
+// Returns the index of `receipt` within its session, for the kinds of receipt
+// that record one.
+fn segment_index(receipt: &dyn Receipt) -> Option<u32> {
+    let receipt = receipt.as_any();
+    #[cfg(feature = "zstd")]
+    if let Some(compressed) = receipt.downcast_ref::<CompressedSegmentReceipt>() {
+        return Some(compressed.index);
     }
+    receipt
+        .downcast_ref::<SegmentReceipt>()
+        .map(|segment| segment.index)
+}
 
-    fn compute_image_id(&self) -> Digest {
-        compute_image_id(&self.merkle_root, self.pc)
+// Returns `receipt` as a plain [SegmentReceipt], for the kinds of receipt that
+// store a segment's seal in some other form. A compressed seal that can't be
+// decompressed is left as it is.
+#[allow(unused_variables)]
+fn uncompressed_segment(receipt: &dyn Receipt) -> Option<SegmentReceipt> {
+    let receipt = receipt.as_any();
+    #[cfg(feature = "zstd")]
+    if let Some(compressed) = receipt.downcast_ref::<CompressedSegmentReceipt>() {
+        return compressed.decompress().ok();
     }
+    None
 }
 
-impl ReceiptMetadata {
-    fn decode_from_io(io: layout::OutBuffer) -> Result<Self, VerificationError> {
-        let body = layout::LAYOUT.mux.body;
-        let pre = SystemState::decode_from_io(io, body.global.pre)?;
-        let mut post = SystemState::decode_from_io(io, body.global.post)?;
-        // In order to avoid extra logic in the rv32im circuit to perform arthimetic on
-        // the PC with carry, the PC is always recorded as the current PC +
-        // 4. Thus we need to adjust the decoded PC for the post SystemState.
-        post.pc = post
-            .pc
-            .checked_sub(WORD_SIZE as u32)
+/// The progress of an incremental verification of a session.
+///
+/// This allows the segments of a session to be verified as they arrive,
+/// without verifying any segment twice. A cursor is small and serializable, so
+/// it can be persisted and verification resumed later, e.g. after a restart.
+/// Start from [VerifyCursor::new] or [SessionReceipt::verify_prefix], pass
+/// further segments in order to [VerifyCursor::verify_continue], and complete
+/// verification with [VerifyCursor::verify_finish].
+///
+/// A cursor records the segments it has accepted, not the segments
+/// themselves, so a persisted cursor must be stored where it can't be
+/// tampered with.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct VerifyCursor {
+    /// The index of the next segment to be verified.
+    pub next_index: u32,
+
+    /// The image ID that the next segment must start from.
+    pub expected_pre_image_id: Digest,
+
+    // The metadata of the last verified segment.
+    last: Option<ReceiptMetadata>,
+}
+
+impl VerifyCursor {
+    /// Construct a cursor for a session starting from `image_id`, with no
+    /// segments verified yet.
+    pub fn new(image_id: impl Into<Digest>) -> Self {
+        Self {
+            next_index: 0,
+            expected_pre_image_id: image_id.into(),
+            last: None,
+        }
+    }
+
+    /// Verifies `segments`, which must be the segments that follow the ones
+    /// already verified, and advances this cursor past them.
+    ///
+    /// If a segment fails to verify, the cursor is left after the last segment
+    /// that succeeded.
+    pub fn verify_continue(
+        &mut self,
+        ctx: &VerifierContext,
+        segments: &[Box<dyn Receipt>],
+    ) -> Result<(), VerificationError> {
+        for receipt in segments {
+            self.verify_segment(ctx, receipt.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Completes verification once every segment has been verified, checking
+    /// the final exit code and that `journal` matches the output committed by
+    /// the guest.
+    pub fn verify_finish(
+        &self,
+        ctx: &VerifierContext,
+        journal: &[u8],
+    ) -> Result<(), VerificationError> {
+        let last = self
+            .last
+            .as_ref()
             .ok_or(VerificationError::ReceiptFormatError)?;
-        let input_bytes: Vec<u8> = io
-            .tree(body.global.input)
-            .get_bytes()
-            .or(Err(VerificationError::ReceiptFormatError))?;
-        let input = Digest::try_from(input_bytes).or(Err(VerificationError::ReceiptFormatError))?;
-        let output_bytes: Vec<u8> = io
-            .tree(body.global.output)
-            .get_bytes()
-            .or(Err(VerificationError::ReceiptFormatError))?;
-        let output =
-            Digest::try_from(output_bytes).or(Err(VerificationError::ReceiptFormatError))?;
-        let sys_exit = io.get_u64(body.global.sys_exit_code) as u32;
-        let user_exit = io.get_u64(body.global.user_exit_code) as u32;
-        let exit_code = ReceiptMetadata::make_exit_code(sys_exit, user_exit)?;
-        Ok(Self {
-            pre,
-            post,
-            exit_code,
-            input,
-            output,
-        })
+
+        if ctx.check_journal {
+            ctx.commitment.verify_commitment(last, journal)?;
+        }
+
+        if last.exit_code == ExitCode::SystemSplit {
+            return Err(VerificationError::UnexpectedExitCode);
+        }
+
+        Ok(())
     }
 
-    pub(crate) fn get_exit_code_pairs(&self) -> Result<(u32, u32), VerificationError> {
-        match self.exit_code {
-            ExitCode::Halted(user_exit) => return Ok((0, user_exit)),
-            ExitCode::Paused(user_exit) => return Ok((1, user_exit)),
-            ExitCode::SystemSplit => return Ok((2, 0)),
-            _ => return Err(VerificationError::ReceiptFormatError),
+    fn verify_segment(
+        &mut self,
+        ctx: &VerifierContext,
+        receipt: &dyn Receipt,
+    ) -> Result<(), VerificationError> {
+        self.stitch_segment(ctx, receipt, true)
+    }
+
+    // Checks that `receipt` continues from the segments before it, verifying
+    // its seal only if `verify_seal` is set.
+    fn stitch_segment(
+        &mut self,
+        ctx: &VerifierContext,
+        receipt: &dyn Receipt,
+        verify_seal: bool,
+    ) -> Result<(), VerificationError> {
+        let metadata = if verify_seal {
+            receipt.verify_metadata(ctx)?
+        } else {
+            receipt.get_metadata()?
         };
+        self.stitch_metadata(metadata)
     }
 
-    pub(crate) fn make_exit_code(
-        sys_exit: u32,
-        user_exit: u32,
-    ) -> Result<ExitCode, VerificationError> {
-        match sys_exit {
-            0 => Ok(ExitCode::Halted(user_exit)),
-            1 => Ok(ExitCode::Paused(user_exit)),
-            2 => Ok(ExitCode::SystemSplit),
-            _ => Err(VerificationError::ReceiptFormatError),
+    // Checks that a segment with the given metadata continues from the
+    // segments before it. The metadata must already have been verified, or be
+    // knowingly trusted by the caller.
+    fn stitch_metadata(&mut self, metadata: ReceiptMetadata) -> Result<(), VerificationError> {
+        if let Some(last) = &self.last {
+            if last.exit_code != ExitCode::SystemSplit {
+                return Err(VerificationError::UnexpectedExitCode);
+            }
         }
+        log::debug!(target: LOG_METADATA, "metadata: {metadata:#?}");
+        if self.expected_pre_image_id != metadata.pre.compute_image_id() {
+            return Err(VerificationError::ImageVerificationError);
+        }
+        self.next_index += 1;
+        self.expected_pre_image_id = metadata.post.compute_image_id();
+        self.last = Some(metadata);
+        Ok(())
     }
 }
 
-/// Compute and return the ImageID of the given `(merkle_root, pc)` pair.
-pub fn compute_image_id(merkle_root: &Digest, pc: u32) -> Digest {
-    use risc0_zkp::core::{digest::DIGEST_WORDS, hash::sha::Sha256};
-    let mut pc_digest = [0u32; DIGEST_WORDS];
-    pc_digest[0] = pc;
-    let block2 = Digest::new(pc_digest);
-    *sha::Impl::compress(&SHA256_INIT, merkle_root, &block2)
-}
-
-impl Default for VerifierContext {
+/// Verifies the segments of a session one at a time, as they are proven.
+///
+/// Each segment is verified as soon as it is passed to
+/// [IncrementalVerifier::push], so that a bad segment is caught without
+/// waiting for the rest of the session to be proven. Once every segment has
+/// been pushed, [IncrementalVerifier::finish] completes verification against
+/// the journal. Together these perform the same checks as
+/// [SessionReceipt::verify_with_context].
+///
+/// ```rust
+/// use risc0_zkvm::IncrementalVerifier;
+/// # use risc0_zkvm::{default_executor_from_elf, ExecutorEnv};
+/// # use risc0_zkvm_methods::{FIB_ELF, FIB_ID};
+///
+/// # #[cfg(not(feature = "cuda"))]
+/// # {
+/// # let env = ExecutorEnv::builder().add_input(&[20]).build().unwrap();
+/// # let mut exec = default_executor_from_elf(env, FIB_ELF).unwrap();
+/// # let session = exec.run().unwrap();
+/// # let receipt = session.prove().unwrap();
+/// let mut verifier = IncrementalVerifier::new(FIB_ID);
+/// for segment in receipt.segments {
+///     verifier.push(segment).unwrap();
+/// }
+/// verifier.finish(&receipt.journal).unwrap();
+/// # }
+/// ```
+///
+/// This is a [VerifyCursor] bundled with the context to verify with; use a
+/// cursor directly to persist the progress of verification.
+#[derive(Clone)]
+pub struct IncrementalVerifier {
+    ctx: VerifierContext,
+    cursor: VerifyCursor,
+}
+
+impl IncrementalVerifier {
+    /// Construct a verifier for a session starting from `image_id`, using the
+    /// default [VerifierContext].
+    pub fn new(image_id: impl Into<Digest>) -> Self {
+        Self::with_context(VerifierContext::default(), image_id)
+    }
+
+    /// Construct a verifier for a session starting from `image_id`, using
+    /// `ctx`.
+    pub fn with_context(ctx: VerifierContext, image_id: impl Into<Digest>) -> Self {
+        Self {
+            ctx,
+            cursor: VerifyCursor::new(image_id),
+        }
+    }
+
+    /// Returns the progress of verification so far.
+    pub fn cursor(&self) -> &VerifyCursor {
+        &self.cursor
+    }
+
+    /// Verifies `receipt`, the next segment of the session.
+    ///
+    /// The seal is verified, and the segment must start from the image ID that
+    /// the previous segment ended with, or from the image ID of the session if
+    /// it is the first. Returns [VerificationError::UnexpectedExitCode] if the
+    /// previous segment ended the session. If the segment fails to verify, it
+    /// is not counted, and the correct segment may still be pushed in its
+    /// place.
+    pub fn push(&mut self, receipt: Box<dyn Receipt>) -> Result<(), VerificationError> {
+        self.cursor.verify_segment(&self.ctx, receipt.as_ref())
+    }
+
+    /// Completes verification once every segment has been pushed, checking
+    /// the final exit code and that `journal` matches the output committed by
+    /// the guest; see [VerifyCursor::verify_finish].
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if no segments were
+    /// pushed.
+    pub fn finish(self, journal: &[u8]) -> Result<(), VerificationError> {
+        self.cursor.verify_finish(&self.ctx, journal)
+    }
+}
+
+/// Context available to the verification process.
+///
+/// Constructing a context with [VerifierContext::default] builds a fresh set
+/// of hash suites. The suites hold no precomputed state (e.g. the Poseidon
+/// round constants are compile-time constants), so this costs only a handful
+/// of small allocations, but it is still wasteful to repeat for every
+/// verification in a busy server; the `verifier_context` benchmark measures
+/// it. A context is `Send + Sync`, so a single instance can instead be shared
+/// by reference across threads; it is also cheap to clone, since the hash
+/// suites' internals are reference counted rather than copied.
+///
+/// Verification uses no randomness beyond the Fiat-Shamir challenges derived
+/// from each seal (see [risc0_zkp::verify::verify]), so the result is fully
+/// determined by the receipt and the context.
+#[derive(Clone)]
+pub struct VerifierContext {
+    /// A registry of hash functions to be used by the verification process.
+    pub suites: BTreeMap<String, HashSuite<BabyBear>>,
+
+    /// Whether to check the journal against the `output` digest committed by
+    /// the guest.
+    ///
+    /// Defaults to true. When false, verification only establishes that the
+    /// computation ran from the given image ID, and says nothing about the
+    /// journal; a [DetachedSessionReceipt] may then be verified without its
+    /// journal. This is also how to verify a guest whose `output`
+    /// commits to something other than its journal: verify with this set to
+    /// false, then check [ReceiptMetadata::output] directly, or supply a
+    /// [VerifierContext::commitment] for its scheme.
+    pub check_journal: bool,
+
+    /// The scheme by which the guest commits to its journal.
+    ///
+    /// Defaults to [JournalHash::Sha256], the scheme of the standard zkVM
+    /// guest, which checks the journal against [ReceiptMetadata::output].
+    /// Another [JournalHash], or any other [CommitmentVerifier], supports
+    /// guests that commit to their journal differently. The journal is only
+    /// checked if [VerifierContext::check_journal] is set.
+    pub commitment: Arc<dyn CommitmentVerifier>,
+
+    /// The root of a Merkle tree over the control IDs to accept, if any.
+    ///
+    /// Defaults to `None`, in which case only the control IDs built into this
+    /// crate are accepted. When set, a seal's control ID is instead accepted
+    /// if [VerifierContext::control_proofs] holds a proof of its inclusion
+    /// under this root. See [compute_control_root] for how the tree is built.
+    /// The root may commit to control IDs of any circuit, built in or not; a
+    /// seal is verified against [VerifierContext::circuit] either way, and its
+    /// metadata decoded with the layout of that circuit.
+    pub control_root: Option<Digest>,
+
+    /// Inclusion proofs for control IDs, checked against
+    /// [VerifierContext::control_root].
+    ///
+    /// Only the root needs to be trusted; the proofs can come from anywhere,
+    /// and only need to cover the control IDs actually used by the receipts
+    /// being verified.
+    pub control_proofs: BTreeMap<Digest, ControlInclusionProof>,
+
+    /// Hash functions that are still accepted, but that
+    /// [SessionReceipt::verify_with_warnings] reports with
+    /// [VerifyWarning::DeprecatedHashFn]. Defaults to none.
+    pub deprecated_hashfns: BTreeSet<String>,
+
+    /// The journal length in bytes above which
+    /// [SessionReceipt::verify_with_warnings] reports
+    /// [VerifyWarning::LargeJournal]. Defaults to 1 MiB.
+    pub large_journal_bytes: usize,
+
+    /// Whether to accept [FakeReceipt]s. **This is insecure.**
+    ///
+    /// A FakeReceipt carries no proof, so with this set anyone can produce a
+    /// receipt for any image ID and journal that verifies successfully. It is
+    /// intended only for development, where proving is skipped for speed, and
+    /// must never be enabled in production. Defaults to `false`, in which case
+    /// verifying a FakeReceipt fails with
+    /// [VerificationError::DevModeDisabled].
+    pub dev_mode: bool,
+
+    /// The proof system parameters that seals are verified with, overriding
+    /// the standard ones.
+    ///
+    /// Defaults to `None`, in which case the standard parameters are used, as
+    /// by the prover in this crate. This allows verifying receipts from a
+    /// prover configured with other parameters; a receipt proven with
+    /// parameters other than these fails to verify, and parameters that no
+    /// seal can be verified with fail with [VerificationError::Proof]. The
+    /// local prover proves with these parameters too, except that it can't
+    /// change the folding factor. See [VerifierParams].
+    pub params: Option<VerifierParams>,
+
+    /// The circuit that segment seals are verified against.
+    ///
+    /// Defaults to the rv32im circuit built into this crate. A modified or
+    /// versioned circuit can be supplied instead. Its control IDs will
+    /// generally differ from the built-in ones, so they must be accepted
+    /// through [VerifierContext::control_root]. Receipts must record the
+    /// circuit's [VerifierCircuit::layout_version], and their [ReceiptMetadata]
+    /// can only be decoded if that is [LAYOUT_VERSION].
+    pub circuit: &'static dyn VerifierCircuit,
+
+    /// Identifies the implementations of the hash suites,
+    /// [VerifierContext::commitment] and [VerifierContext::circuit] of this
+    /// context, for caching verification results.
+    ///
+    /// Caches such as [VerificationCache] compare contexts by value, which
+    /// isn't possible for these implementations, so they compare this id
+    /// instead. Defaults to 0, which stands for those of
+    /// [VerifierContext::default]. A context that uses any others, e.g. a
+    /// custom circuit or [JournalHash::Poseidon], must be given an id of its
+    /// own, shared only by contexts using the same implementations, or a
+    /// cached result verified with one of them may be reused by another.
+    pub cache_id: u64,
+}
+
+/// A scheme by which a guest commits to its journal, see
+/// [VerifierContext::commitment].
+///
+/// The standard zkVM guest writes a digest of its journal to
+/// [ReceiptMetadata::output], which is checked by the implementation for
+/// [JournalHash]. A guest that commits to its outputs some other way, e.g.
+/// through a region of its memory, can be verified with its own
+/// implementation.
+pub trait CommitmentVerifier: Send + Sync {
+    /// Checks that the guest committed to `journal`, given `metadata`, the
+    /// metadata of the final segment of the session.
+    ///
+    /// The metadata has been verified by the time this is called. Returns
+    /// [VerificationError::JournalDigestMismatch] if the commitment doesn't
+    /// match, by convention.
+    fn verify_commitment(
+        &self,
+        metadata: &ReceiptMetadata,
+        journal: &[u8],
+    ) -> Result<(), VerificationError>;
+}
+
+impl CommitmentVerifier for JournalHash {
+    fn verify_commitment(
+        &self,
+        metadata: &ReceiptMetadata,
+        journal: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.check(journal, &metadata.output)
+    }
+}
+
+/// A circuit that seals can be verified against, see
+/// [VerifierContext::circuit].
+///
+/// This is implemented for every circuit definition generated for the
+/// [BabyBear] field, and wraps [risc0_zkp::verify::verify_with_params] so that
+/// the circuit can be chosen at runtime.
+pub trait VerifierCircuit: Send + Sync {
+    /// Verify that `seal` is valid for this circuit, using `suite` and
+    /// `params`, and calling `check_code` to validate the control ID.
+    fn verify(
+        &self,
+        suite: &HashSuite<BabyBear>,
+        seal: &[u32],
+        check_code: &dyn Fn(u32, &Digest) -> Result<(), ProofError>,
+        params: &VerifierParams,
+    ) -> Result<(), ProofError>;
+
+    /// The version of the layout of this circuit's outputs, which receipts
+    /// proven with it must record as their [SegmentReceipt::layout_version].
+    ///
+    /// Defaults to [LAYOUT_VERSION], the layout of the circuit built into this
+    /// crate. A circuit with another layout must implement this trait itself,
+    /// e.g. on a wrapper type, to report its version.
+    fn layout_version(&self) -> u32 {
+        LAYOUT_VERSION
+    }
+}
+
+impl<C: CircuitCoreDef<BabyBear> + Send + Sync> VerifierCircuit for C {
+    fn verify(
+        &self,
+        suite: &HashSuite<BabyBear>,
+        seal: &[u32],
+        check_code: &dyn Fn(u32, &Digest) -> Result<(), ProofError>,
+        params: &VerifierParams,
+    ) -> Result<(), ProofError> {
+        risc0_zkp::verify::verify_with_params(self, suite, seal, check_code, params)
+    }
+}
+
+/// A strategy for verifying [SessionReceipt]s, so that code can be generic
+/// over how receipts are verified, e.g. to substitute a mock in tests.
+///
+/// [LocalVerifier] verifies receipts with the verifier in this crate, and is
+/// what [SessionReceipt::verify] uses. Other implementations may verify
+/// receipts by other means, such as `RemoteVerifier` with the `remote`
+/// feature, and may carry different trust assumptions; the result means the
+/// same as that of [SessionReceipt::verify].
+pub trait Verifier {
+    /// Verifies the integrity of `receipt`, and that it starts from
+    /// `image_id`.
+    fn verify(&self, receipt: &SessionReceipt, image_id: Digest) -> Result<(), VerificationError>;
+}
+
+/// A [Verifier] that verifies receipts on the CPU with the verifier in this
+/// crate, using a [VerifierContext].
+///
+/// The default uses the default [VerifierContext], as
+/// [SessionReceipt::verify] does.
+#[derive(Clone, Default)]
+pub struct LocalVerifier {
+    ctx: VerifierContext,
+}
+
+impl LocalVerifier {
+    /// Construct a [LocalVerifier] that verifies receipts as
+    /// [SessionReceipt::verify_with_context] does with `ctx`.
+    pub fn new(ctx: VerifierContext) -> Self {
+        Self { ctx }
+    }
+
+    /// Returns the context that receipts are verified with.
+    pub fn context(&self) -> &VerifierContext {
+        &self.ctx
+    }
+}
+
+impl Verifier for LocalVerifier {
+    fn verify(&self, receipt: &SessionReceipt, image_id: Digest) -> Result<(), VerificationError> {
+        receipt.verify_with_context(&self.ctx, image_id)
+    }
+}
+
+impl SessionReceipt {
+    /// Construct a new SessionReceipt
+    ///
+    /// No checks are made on `segments`; use [SessionReceipt::builder] to
+    /// construct a receipt whose structure is validated.
+    pub fn new(segments: Vec<Box<dyn Receipt>>, journal: Vec<u8>) -> Self {
+        Self { segments, journal }
+    }
+
+    /// Construct a [SessionReceiptBuilder], which checks the structure of the
+    /// receipt it builds.
+    pub fn builder() -> SessionReceiptBuilder {
+        SessionReceiptBuilder::default()
+    }
+
+    /// Verifies the integrity of this receipt.
+    ///
+    /// Uses the ZKP system to cryptographically verify that each constituent
+    /// Segment has a valid receipt, and validates that these [SegmentReceipt]s
+    /// stitch together correctly, and that the initial memory image matches the
+    /// given `image_id` parameter.
+    ///
+    /// The `image_id` may be given as a [Digest], as the `[u32; 8]` words
+    /// generated for a guest method, or as its 32 bytes in the order of
+    /// [Digest::as_bytes] (see the conversion from `[u8; 32]` for details).
+    #[must_use]
+    pub fn verify(&self, image_id: impl Into<Digest>) -> Result<(), VerificationError> {
+        LocalVerifier::default().verify(self, image_id.into())
+    }
+
+    /// Verifies the integrity of this receipt, and that every segment provides
+    /// at least `bits` bits of security.
+    ///
+    /// The security of each segment is estimated as by
+    /// [SegmentReceipt::security_bits]. Returns
+    /// [VerificationError::InsufficientSecurity] if any segment falls short,
+    /// before any seal is verified, and the error if the security of a segment
+    /// can't be estimated, e.g. because its hash function is unknown.
+    pub fn verify_with_min_security(
+        &self,
+        image_id: impl Into<Digest>,
+        bits: f64,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_min_security_and_context(&VerifierContext::default(), image_id, bits)
+    }
+
+    /// Verifies the integrity of this receipt, and that every segment provides
+    /// at least `bits` bits of security, as for
+    /// [SessionReceipt::verify_with_min_security] but with the hash functions
+    /// and parameters of `ctx`, see [VerifierContext::security_bits].
+    pub fn verify_with_min_security_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        bits: f64,
+    ) -> Result<(), VerificationError> {
+        if self.security_bits_with_context(ctx)? < bits {
+            return Err(VerificationError::InsufficientSecurity);
+        }
+        self.verify_with_context(ctx, image_id)
+    }
+
+    /// Verifies the integrity of this receipt, and that every segment was
+    /// proven with one of the `allowed` hash functions.
+    ///
+    /// This enforces a policy on the hash functions accepted across the whole
+    /// session, e.g. `&["sha-256", "poseidon"]`. Returns
+    /// [VerificationError::DisallowedHashFn] naming the first hash function
+    /// that isn't allowed, before any seal is verified.
+    pub fn verify_with_allowed_hashfns(
+        &self,
+        image_id: impl Into<Digest>,
+        allowed: &[&str],
+    ) -> Result<(), VerificationError> {
+        self.verify_with_allowed_hashfns_and_context(&VerifierContext::default(), image_id, allowed)
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and that
+    /// every segment was proven with one of the `allowed` hash functions; see
+    /// [SessionReceipt::verify_with_allowed_hashfns].
+    pub fn verify_with_allowed_hashfns_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        allowed: &[&str],
+    ) -> Result<(), VerificationError> {
+        for receipt in self.segments.iter() {
+            let hashfn = receipt.hashfn();
+            if !allowed.contains(&hashfn) {
+                return Err(VerificationError::DisallowedHashFn(hashfn.into()));
+            }
+        }
+        self.verify_with_context(ctx, image_id)
+    }
+
+    /// Verifies the integrity of this receipt, and that it has exactly `count`
+    /// segments.
+    ///
+    /// This suits protocols in which a program always produces the same number
+    /// of segments, so that any other count indicates a tampered receipt or a
+    /// different version of the program. Returns
+    /// [VerificationError::UnexpectedSegmentCount] if the count differs,
+    /// before any seal is verified.
+    pub fn verify_with_expected_segments(
+        &self,
+        image_id: impl Into<Digest>,
+        count: usize,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_expected_segments_and_context(&VerifierContext::default(), image_id, count)
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and that
+    /// it has exactly `count` segments; see
+    /// [SessionReceipt::verify_with_expected_segments].
+    pub fn verify_with_expected_segments_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        count: usize,
+    ) -> Result<(), VerificationError> {
+        if self.segments.len() != count {
+            return Err(VerificationError::UnexpectedSegmentCount {
+                expected: count,
+                actual: self.segments.len(),
+            });
+        }
+        self.verify_with_context(ctx, image_id)
+    }
+
+    /// Verifies the integrity of this receipt against a journal supplied
+    /// separately from it.
+    ///
+    /// The `journal` field of this receipt is ignored; instead `journal` is
+    /// checked against the output committed to by the final segment. This
+    /// avoids mutating the receipt when the proof and the journal are
+    /// delivered through different channels.
+    pub fn verify_with_journal(
+        &self,
+        image_id: impl Into<Digest>,
+        journal: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_with_journal_and_context(&VerifierContext::default(), image_id, journal)
+    }
+
+    /// Verifies the integrity of this receipt with the given context against a
+    /// journal supplied separately from it; see
+    /// [SessionReceipt::verify_with_journal].
+    pub fn verify_with_journal_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        journal: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_prefix(ctx, image_id)?
+            .verify_finish(ctx, journal)
+    }
+
+    /// Verifies the integrity of this receipt, and that its image ID and
+    /// journal match a published `commitment`.
+    ///
+    /// This supports commit-reveal protocols, where a commitment to the
+    /// expected execution is published before the receipt is presented. The
+    /// commitment is computed by [compute_journal_commitment], whose
+    /// documentation gives the exact preimage. Returns
+    /// [VerificationError::CommitmentMismatch] if the receipt verifies but
+    /// doesn't match the commitment.
+    pub fn verify_against_commitment(
+        &self,
+        image_id: impl Into<Digest>,
+        commitment: Digest,
+    ) -> Result<(), VerificationError> {
+        self.verify_against_commitment_with_context(
+            &VerifierContext::default(),
+            image_id,
+            commitment,
+        )
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and that
+    /// its image ID and journal match a published `commitment`; see
+    /// [SessionReceipt::verify_against_commitment].
+    pub fn verify_against_commitment_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        commitment: Digest,
+    ) -> Result<(), VerificationError> {
+        let image_id = image_id.into();
+        self.verify_with_context(ctx, image_id)?;
+        if compute_journal_commitment(&image_id, &self.journal) != commitment {
+            return Err(VerificationError::CommitmentMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verifies the integrity of this receipt, and that its journal decodes to
+    /// `expected`.
+    ///
+    /// The journal is decoded with [crate::serde::from_slice], i.e. as written
+    /// by `env::commit` in the guest. This checks in one call that the guest
+    /// produced exactly the given result, so that neither verification nor
+    /// the comparison can be skipped by mistake. Returns
+    /// [VerificationError::JournalValueMismatch] if the receipt verifies but
+    /// its journal can't be decoded as a `T`, has data left over after the
+    /// `T`, or decodes to a different value.
+    pub fn verify_and_check<T: DeserializeOwned + PartialEq>(
+        &self,
+        image_id: impl Into<Digest>,
+        expected: &T,
+    ) -> Result<(), VerificationError> {
+        self.verify_and_check_with_context(&VerifierContext::default(), image_id, expected)
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and that
+    /// its journal decodes to `expected`; see
+    /// [SessionReceipt::verify_and_check].
+    pub fn verify_and_check_with_context<T: DeserializeOwned + PartialEq>(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        expected: &T,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_context(ctx, image_id)?;
+        // The journal is a sequence of words, so any other length can't have
+        // been written by `env::commit`.
+        if self.journal.len() % WORD_SIZE != 0 {
+            return Err(VerificationError::JournalValueMismatch);
+        }
+        let words: Vec<u32> = bytemuck::pod_collect_to_vec(&self.journal);
+        let mut rest = words.as_slice();
+        match T::deserialize(&mut crate::serde::Deserializer::new(&mut rest)) {
+            Ok(value) if rest.is_empty() && value == *expected => Ok(()),
+            _ => Err(VerificationError::JournalValueMismatch),
+        }
+    }
+
+    /// Verifies the integrity of this receipt against an image ID given as a
+    /// hex string.
+    ///
+    /// The `0x` prefix is optional. The string is parsed with the same
+    /// [hex::FromHex] convention used for control IDs, i.e. the bytes of the
+    /// [Digest] in memory order, which matches the output of
+    /// `hex::encode(image_id)`. Returns [VerificationError::MalformedImageId]
+    /// if the string isn't a valid 32-byte hex value.
+    pub fn verify_hex(&self, image_id_hex: &str) -> Result<(), VerificationError> {
+        self.verify_hex_with_context(&VerifierContext::default(), image_id_hex)
+    }
+
+    /// Verifies the integrity of this receipt with the given context against an
+    /// image ID given as a hex string; see [SessionReceipt::verify_hex].
+    pub fn verify_hex_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id_hex: &str,
+    ) -> Result<(), VerificationError> {
+        use hex::FromHex;
+        let hex = image_id_hex.strip_prefix("0x").unwrap_or(image_id_hex);
+        let image_id = Digest::from_hex(hex).or(Err(VerificationError::MalformedImageId))?;
+        self.verify_with_context(ctx, image_id)
+    }
+
+    /// Verifies the integrity of this receipt, and that the guest finished
+    /// with its program counter at `pc`.
+    ///
+    /// This is useful for guests that are expected to always halt at a known
+    /// address, e.g. the ELF's halt handler. `pc` is the address of the
+    /// instruction the guest halted at, as reported in
+    /// [ReceiptMetadata::post]; the circuit's own post-state pc is one word
+    /// past that, and is adjusted when the metadata is decoded. Returns
+    /// [VerificationError::PostStateMismatch] if the pc doesn't match.
+    pub fn verify_with_expected_post_pc(
+        &self,
+        image_id: impl Into<Digest>,
+        pc: u32,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_expected_post_pc_and_context(&VerifierContext::default(), image_id, pc)
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and that
+    /// the guest finished with its program counter at `pc`; see
+    /// [SessionReceipt::verify_with_expected_post_pc].
+    pub fn verify_with_expected_post_pc_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        pc: u32,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_context(ctx, image_id)?;
+        let final_receipt = self
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        let post = final_receipt.get_metadata()?.post;
+        if post.pc != pc {
+            log::debug!(
+                target: LOG_MISMATCH,
+                "post pc: 0x{:08x}, expected: 0x{pc:08x}",
+                post.pc
+            );
+            return Err(VerificationError::PostStateMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verifies the integrity of this receipt, and that the metadata of its
+    /// final segment is exactly `expected`.
+    ///
+    /// This is useful for regression testing a guest whose execution is
+    /// deterministic, as it pins down every field of the [ReceiptMetadata] at
+    /// once. Returns [VerificationError::MetadataMismatch] listing each field
+    /// that differs, as reported by [ReceiptMetadata::diff].
+    pub fn verify_matches_metadata(
+        &self,
+        image_id: impl Into<Digest>,
+        expected: &ReceiptMetadata,
+    ) -> Result<(), VerificationError> {
+        self.verify_matches_metadata_with_context(&VerifierContext::default(), image_id, expected)
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and that
+    /// the metadata of its final segment is exactly `expected`; see
+    /// [SessionReceipt::verify_matches_metadata].
+    pub fn verify_matches_metadata_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        expected: &ReceiptMetadata,
+    ) -> Result<(), VerificationError> {
+        self.verify_with_context(ctx, image_id)?;
+        let final_receipt = self
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        let metadata = final_receipt.get_metadata()?;
+        if metadata != *expected {
+            let diff = metadata
+                .diff(expected)
+                .iter()
+                .map(|diff| format!("{diff}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(VerificationError::MetadataMismatch { diff });
+        }
+        Ok(())
+    }
+
+    /// Verifies the integrity of this receipt.
+    ///
+    /// Uses the ZKP system to cryptographically verify that each constituent
+    /// Segment has a valid receipt, and validates that these [SegmentReceipt]s
+    /// stitch together correctly, and that the initial memory image matches the
+    /// given `image_id` parameter.
+    ///
+    /// The image ID commits to the initial program counter as well as the
+    /// memory image (see [compute_image_id]), so this also checks that the
+    /// session started at the entry point of the program the image ID was
+    /// computed for: a receipt whose first segment starts at any other pc
+    /// fails with [VerificationError::ImageVerificationError], and no separate
+    /// check of the entry point is needed. A session resumed from a paused one
+    /// is instead verified against [SessionReceipt::resume_image_id], and
+    /// starts where that session paused.
+    #[must_use]
+    pub fn verify_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<(), VerificationError> {
+        self.verify_impl(ctx, image_id.into(), || Ok(()))
+    }
+
+    /// Verifies the integrity of this receipt on a blocking thread.
+    ///
+    /// This behaves like [SessionReceipt::verify_with_context], but runs
+    /// verification with [tokio::task::spawn_blocking] so that async callers
+    /// don't block their executor while it runs. Must be called from within a
+    /// Tokio runtime. A panic during verification is propagated to the caller,
+    /// and [VerificationError::Cancelled] is returned if the blocking task is
+    /// cancelled, e.g. because the runtime is shutting down.
+    #[cfg(feature = "async")]
+    pub async fn verify_async(
+        self: alloc::sync::Arc<Self>,
+        ctx: VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<(), VerificationError> {
+        let image_id = image_id.into();
+        let task = tokio::task::spawn_blocking(move || self.verify_with_context(&ctx, image_id));
+        match task.await {
+            Ok(result) => result,
+            Err(err) => match err.try_into_panic() {
+                Ok(panic) => std::panic::resume_unwind(panic),
+                Err(_) => Err(VerificationError::Cancelled),
+            },
+        }
+    }
+
+    /// Verifies the integrity of this receipt, giving up once `deadline` has
+    /// passed.
+    ///
+    /// This behaves like [SessionReceipt::verify_with_context], except that
+    /// [VerificationError::DeadlineExceeded] is returned if the deadline has
+    /// passed before all segments are verified. The deadline is only checked
+    /// between segments, since the verification of a single segment can't be
+    /// interrupted; verification may therefore overrun the deadline by up to
+    /// the time it takes to verify one segment.
+    #[cfg(feature = "std")]
+    pub fn verify_with_deadline(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        deadline: std::time::Instant,
+    ) -> Result<(), VerificationError> {
+        self.verify_impl(ctx, image_id.into(), || {
+            if std::time::Instant::now() >= deadline {
+                return Err(VerificationError::DeadlineExceeded);
+            }
+            Ok(())
+        })
+    }
+
+    /// Verifies the seals of only the segments at the given indices, while
+    /// checking the structure of the whole receipt.
+    ///
+    /// **This provides only probabilistic assurance**, and is not a substitute
+    /// for [SessionReceipt::verify_with_context]. The image ID chaining, exit
+    /// codes and journal are checked for every segment, but these rely on
+    /// metadata that is only authenticated by a segment's seal, so a forged
+    /// segment is detected only if its index is in `sample`. It is intended for
+    /// spot-checking large batches of receipts, e.g. by sampling indices at
+    /// random, where the time saved is worth the reduced assurance.
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if any index in `sample`
+    /// is out of range.
+    pub fn verify_sampled(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        sample: &[usize],
+    ) -> Result<(), VerificationError> {
+        let sample: BTreeSet<usize> = sample.iter().copied().collect();
+        if sample.last().map_or(false, |i| *i >= self.segments.len()) {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        let mut cursor = VerifyCursor::new(image_id);
+        for (i, receipt) in self.segments.iter().enumerate() {
+            cursor.stitch_segment(ctx, receipt.as_ref(), sample.contains(&i))?;
+        }
+        cursor.verify_finish(ctx, &self.journal)
+    }
+
+    /// Verifies the integrity of this receipt, and reports how long each step
+    /// of verification took.
+    ///
+    /// This performs the same checks as [SessionReceipt::verify_with_context].
+    /// The [VerifyTiming] records the hash function and po2 of each segment
+    /// alongside its verification time, which helps find segments that are
+    /// unusually slow to verify.
+    #[cfg(feature = "std")]
+    pub fn verify_with_timing(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<VerifyTiming, VerificationError> {
+        use std::time::Instant;
+
+        let start = Instant::now();
+        let mut cursor = VerifyCursor::new(image_id);
+        let mut segments = Vec::with_capacity(self.segments.len());
+        for receipt in self.segments.iter() {
+            let segment_start = Instant::now();
+            cursor.verify_segment(ctx, receipt.as_ref())?;
+            let duration = segment_start.elapsed();
+            let seal = receipt.get_seal()?;
+            segments.push(SegmentTiming {
+                hashfn: receipt.hashfn().into(),
+                po2: (!seal.is_empty()).then(|| segment_po2(seal)).transpose()?,
+                duration,
+            });
+        }
+        let journal_start = Instant::now();
+        cursor.verify_finish(ctx, &self.journal)?;
+        let journal = journal_start.elapsed();
+        Ok(VerifyTiming {
+            segments,
+            journal,
+            total: start.elapsed(),
+        })
+    }
+
+    /// Verifies the integrity of this receipt, and reports any advisory
+    /// [VerifyWarning]s about it.
+    ///
+    /// This performs the same checks as [SessionReceipt::verify_with_context],
+    /// and fails in the same cases. Once the receipt has verified, it is
+    /// checked against the policies configured in `ctx`, and any concerns are
+    /// returned rather than failing verification, in the order of the
+    /// [VerifyWarning] variants.
+    pub fn verify_with_warnings(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<Vec<VerifyWarning>, VerificationError> {
+        self.verify_with_context(ctx, image_id)?;
+        let mut warnings = Vec::new();
+        for (segment, receipt) in self.segments.iter().enumerate() {
+            if ctx.deprecated_hashfns.contains(receipt.hashfn()) {
+                warnings.push(VerifyWarning::DeprecatedHashFn {
+                    segment,
+                    hashfn: receipt.hashfn().into(),
+                });
+            }
+        }
+        if self.journal.len() > ctx.large_journal_bytes {
+            warnings.push(VerifyWarning::LargeJournal(self.journal.len()));
+        }
+        // Verification succeeded, so there is a final segment.
+        let exit_code = self.segments.last().unwrap().get_metadata()?.exit_code;
+        if let Some(code @ 1..) = exit_code.user_code() {
+            warnings.push(VerifyWarning::NonzeroExit(code));
+        }
+        Ok(warnings)
+    }
+
+    // Verifies this receipt, calling `checkpoint` before each segment is
+    // verified so that the caller can abort verification early.
+    fn verify_impl(
+        &self,
+        ctx: &VerifierContext,
+        image_id: Digest,
+        mut checkpoint: impl FnMut() -> Result<(), VerificationError>,
+    ) -> Result<(), VerificationError> {
+        let mut cursor = VerifyCursor::new(image_id);
+        for receipt in self.segments.iter() {
+            checkpoint()?;
+            cursor.verify_segment(ctx, receipt.as_ref())?;
+        }
+        cursor.verify_finish(ctx, &self.journal)
+    }
+
+    /// Verifies the segments of this receipt, and returns a [VerifyCursor] from
+    /// which verification can be continued as further segments arrive.
+    ///
+    /// Unlike [SessionReceipt::verify_with_context], this doesn't require the
+    /// receipt to be complete: the journal and the final exit code are only
+    /// checked by [VerifyCursor::verify_finish].
+    pub fn verify_prefix(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<VerifyCursor, VerificationError> {
+        let mut cursor = VerifyCursor::new(image_id);
+        cursor.verify_continue(ctx, &self.segments)?;
+        Ok(cursor)
+    }
+
+    /// Sorts the segments of this receipt by their index, and checks that the
+    /// indices count up from zero without gaps.
+    ///
+    /// This prepares a receipt whose segments were collected out of order,
+    /// e.g. from distributed workers, for [SessionReceipt::verify]. Returns
+    /// [VerificationError::MissingSegment] with the first index that is
+    /// missing, or [VerificationError::DuplicateSegment] with the first index
+    /// that appears more than once; the segments are left sorted either way.
+    /// Returns [VerificationError::ReceiptFormatError], leaving the receipt
+    /// unchanged, if any segment is of a kind that doesn't record an index.
+    pub fn normalize(&mut self) -> Result<(), VerificationError> {
+        if self
+            .segments
+            .iter()
+            .any(|receipt| segment_index(receipt.as_ref()).is_none())
+        {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        self.segments
+            .sort_by_key(|receipt| segment_index(receipt.as_ref()));
+        for (expected, receipt) in self.segments.iter().enumerate() {
+            match segment_index(receipt.as_ref()) {
+                Some(index) if index as usize == expected => {}
+                Some(index) if (index as usize) < expected => {
+                    return Err(VerificationError::DuplicateSegment(index));
+                }
+                _ => return Err(VerificationError::MissingSegment(expected as u32)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts this receipt into a canonical form, so that receipts with the same
+    /// content compare equal.
+    ///
+    /// This normalizes exactly two things:
+    ///
+    /// - Each `CompressedSegmentReceipt` is replaced by the equivalent
+    ///   [SegmentReceipt], so the form in which a seal was stored doesn't
+    ///   matter. A compressed seal that fails to decompress is left compressed.
+    /// - The segments are sorted by index. The sort is stable, and segments of
+    ///   a kind that doesn't record an index, such as a [FakeReceipt], keep
+    ///   their relative order ahead of the indexed segments.
+    ///
+    /// Nothing else is changed: in particular, missing or duplicate segments
+    /// are kept as they are. Use [SessionReceipt::normalize] to check the
+    /// indices, and verify the receipt as usual before relying on its contents.
+    pub fn canonicalize(&mut self) {
+        for receipt in self.segments.iter_mut() {
+            if let Some(segment) = uncompressed_segment(receipt.as_ref()) {
+                *receipt = Box::new(segment);
+            }
+        }
+        self.segments
+            .sort_by_key(|receipt| segment_index(receipt.as_ref()));
+    }
+
+    /// Appends a segment receipt to the end of this receipt.
+    ///
+    /// Performs a cheap structural check that the new segment continues from
+    /// the current end of the receipt: the last segment must have ended with
+    /// [ExitCode::SystemSplit], and its post-state image ID must match the
+    /// pre-state image ID of `receipt`. This catches stitching errors as
+    /// segments are assembled, but does not verify the seal; that still happens
+    /// in [SessionReceipt::verify].
+    pub fn append_segment(&mut self, receipt: Box<dyn Receipt>) -> Result<(), VerificationError> {
+        let metadata = receipt.get_metadata()?;
+        if let Some(last) = self.segments.last() {
+            let last = last.get_metadata()?;
+            if last.exit_code != ExitCode::SystemSplit {
+                return Err(VerificationError::UnexpectedExitCode);
+            }
+            if last.post.compute_image_id() != metadata.pre.compute_image_id() {
+                return Err(VerificationError::ImageVerificationError);
+            }
+        }
+        self.segments.push(receipt);
+        Ok(())
+    }
+
+    /// Splits this receipt into two, with the segments before `index` in the
+    /// first and the rest in the second.
+    ///
+    /// The segment before `index` must have ended with
+    /// [ExitCode::SystemSplit], so that the second receipt continues where the
+    /// first left off. The journal belongs to the second receipt, since it is
+    /// the one that finishes the session; the first gets an empty journal. As
+    /// the first receipt doesn't finish the session it can't be checked with
+    /// [SessionReceipt::verify], but [SessionReceipt::verify_prefix] accepts
+    /// it. The second verifies against the image ID of the first's final
+    /// post-state.
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if either part would be
+    /// empty, or [VerificationError::UnexpectedExitCode] if the segment before
+    /// `index` didn't end with a split.
+    pub fn split_at(
+        mut self,
+        index: usize,
+    ) -> Result<(SessionReceipt, SessionReceipt), VerificationError> {
+        if index == 0 || index >= self.segments.len() {
+            return Err(VerificationError::ReceiptFormatError);
+        }
+        if self.segments[index - 1].get_metadata()?.exit_code != ExitCode::SystemSplit {
+            return Err(VerificationError::UnexpectedExitCode);
+        }
+        let suffix = self.segments.split_off(index);
+        let prefix = SessionReceipt::new(self.segments, Vec::new());
+        Ok((prefix, SessionReceipt::new(suffix, self.journal)))
+    }
+
+    /// Verifies the integrity of this receipt, consulting `cache` first.
+    ///
+    /// If `cache` records that this receipt has already been verified against
+    /// `image_id` with `ctx`, this returns `Ok` without running the ZKP
+    /// verifier. Otherwise the receipt is verified with
+    /// [SessionReceipt::verify_with_context], and recorded in `cache` if it
+    /// succeeds. See [VerificationCache] for the trust assumptions involved.
+    pub fn verify_cached(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        cache: &mut VerificationCache,
+    ) -> Result<(), VerificationError> {
+        let key = (self.fingerprint()?, image_id.into(), ctx.cache_digest());
+        if cache.contains(&key) {
+            return Ok(());
+        }
+        self.verify_with_context(ctx, key.1)?;
+        cache.insert(key);
+        Ok(())
+    }
+
+    /// Returns a SHA-256 digest of the serialized form of this receipt.
+    ///
+    /// Two receipts have the same fingerprint exactly when their segments and
+    /// journal are the same.
+    ///
+    /// Returns [VerificationError::ReceiptTooLarge] if the receipt is too large
+    /// to serialize, which takes a seal or journal of gigabytes.
+    pub fn fingerprint(&self) -> Result<Digest, VerificationError> {
+        serialized_digest(self)
+    }
+
+    /// Returns the sequence of image IDs this receipt passes through.
+    ///
+    /// The first entry is the image ID the first segment starts from, followed
+    /// by the image ID each segment ends with, so the result has one more entry
+    /// than there are segments. The image IDs are computed from the metadata
+    /// of each segment without any verification, so they must not be trusted
+    /// until [SessionReceipt::verify] has succeeded.
+    pub fn image_id_chain(&self) -> Result<Vec<Digest>, VerificationError> {
+        let first = self
+            .segments
+            .first()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        let mut chain = Vec::with_capacity(self.segments.len() + 1);
+        chain.push(first.get_metadata()?.pre.compute_image_id());
+        for receipt in self.segments.iter() {
+            chain.push(receipt.get_metadata()?.post.compute_image_id());
+        }
+        Ok(chain)
+    }
+
+    /// Verifies this receipt and `other`, and returns whether they prove the
+    /// execution of the same program, i.e. start from the same image ID.
+    ///
+    /// Each receipt is verified against the image ID that its first segment
+    /// claims to start from, so this establishes that both receipts are valid
+    /// and compares their image IDs without the image ID being known in
+    /// advance. It says nothing about which program that is; verify against
+    /// the expected image ID for that. Returns an error if either receipt
+    /// fails to verify.
+    pub fn same_program(&self, other: &SessionReceipt) -> Result<bool, VerificationError> {
+        self.same_program_with_context(&VerifierContext::default(), other)
+    }
+
+    /// Verifies this receipt and `other` with the given context, and returns
+    /// whether they prove the execution of the same program; see
+    /// [SessionReceipt::same_program].
+    pub fn same_program_with_context(
+        &self,
+        ctx: &VerifierContext,
+        other: &SessionReceipt,
+    ) -> Result<bool, VerificationError> {
+        let image_id = self.claimed_image_id()?;
+        self.verify_with_context(ctx, image_id)?;
+        let other_image_id = other.claimed_image_id()?;
+        other.verify_with_context(ctx, other_image_id)?;
+        Ok(image_id == other_image_id)
+    }
+
+    // The image ID that the first segment claims to start from.
+    fn claimed_image_id(&self) -> Result<Digest, VerificationError> {
+        let first = self
+            .segments
+            .first()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        Ok(first.get_metadata()?.pre.compute_image_id())
+    }
+
+    /// Returns the segment at index `i` as a [SegmentReceipt].
+    ///
+    /// Returns `None` if `i` is out of bounds, or if the segment is some other
+    /// kind of [Receipt]. Other concrete types can be recovered through the
+    /// `as_any` method that every [Receipt] provides.
+    pub fn as_segment(&self, i: usize) -> Option<&SegmentReceipt> {
+        self.segments.get(i)?.as_any().downcast_ref()
+    }
+
+    /// Returns the image ID that a session resuming from this one starts
+    /// from.
+    ///
+    /// For a receipt whose final segment ended with [ExitCode::Paused], this is
+    /// the image ID of its post-state, which is the `image_id` to verify the
+    /// receipt of the resumed session against. Returns
+    /// [VerificationError::UnexpectedExitCode] if the session didn't pause.
+    /// Like the rest of the metadata, the result must not be trusted until
+    /// [SessionReceipt::verify] has succeeded.
+    pub fn resume_image_id(&self) -> Result<Digest, VerificationError> {
+        let final_receipt = self
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        let metadata = final_receipt.get_metadata()?;
+        match metadata.exit_code {
+            ExitCode::Paused(_) => Ok(metadata.post.compute_image_id()),
+            _ => Err(VerificationError::UnexpectedExitCode),
+        }
+    }
+
+    /// Returns the code the session paused with, if it paused.
+    ///
+    /// This is `Some(code)` if the final segment ended with
+    /// [ExitCode::Paused], in which case the session can be resumed from
+    /// [SessionReceipt::resume_image_id], and `None` if it ended with
+    /// [ExitCode::Halted]. Returns [VerificationError::UnexpectedExitCode] if
+    /// the session is incomplete, i.e. ended with [ExitCode::SystemSplit].
+    /// Like the rest of the metadata, the result must not be trusted until
+    /// [SessionReceipt::verify] has succeeded.
+    pub fn pause_code(&self) -> Result<Option<u32>, VerificationError> {
+        let final_receipt = self
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        match final_receipt.get_metadata()?.exit_code {
+            ExitCode::Paused(code) => Ok(Some(code)),
+            ExitCode::Halted(_) => Ok(None),
+            ExitCode::SystemSplit | ExitCode::SessionLimit => {
+                Err(VerificationError::UnexpectedExitCode)
+            }
+        }
+    }
+
+    /// Verifies the seal of each segment, and returns the distinct control IDs
+    /// of the circuits that they were proven against.
+    ///
+    /// The control IDs are those recovered by the verifier from each seal (see
+    /// [Receipt::verify_control_id]), which makes this a reliable record of
+    /// which circuits produced the receipt, e.g. for auditing. The segments
+    /// are verified with the default [VerifierContext], but aren't checked to
+    /// stitch together, and the journal isn't checked; use
+    /// [SessionReceipt::verify] for that.
+    pub fn control_ids(&self) -> Result<BTreeSet<Digest>, VerificationError> {
+        self.control_ids_with_context(&VerifierContext::default())
+    }
+
+    /// Verifies each segment of this receipt with the given context, and
+    /// returns the distinct control IDs it was proven against; see
+    /// [SessionReceipt::control_ids].
+    pub fn control_ids_with_context(
+        &self,
+        ctx: &VerifierContext,
+    ) -> Result<BTreeSet<Digest>, VerificationError> {
+        self.segments
+            .iter()
+            .map(|receipt| receipt.verify_control_id(ctx))
+            .collect()
+    }
+
+    /// Returns whether the validity of this receipt is self-contained, rather
+    /// than conditional on assumptions that must be resolved separately.
+    ///
+    /// Receipts don't yet carry assumptions, so this always returns `true`.
+    /// It is defined ahead of receipt composition so that verifiers which
+    /// can't check assumptions can already refuse receipts that have them;
+    /// once composition lands, this returns `false` for any receipt that
+    /// carries unresolved assumptions, and may return an error if they are
+    /// malformed.
+    pub fn is_unconditional(&self) -> Result<bool, VerificationError> {
+        Ok(true)
+    }
+
+    /// Returns a rough estimate of the cost of verifying this receipt, in the
+    /// arbitrary cost points of [Receipt::verify_cost_estimate].
+    ///
+    /// This is the sum of the estimates for each segment.
+    pub fn verify_cost_estimate(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|receipt| receipt.verify_cost_estimate())
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Returns whether the session this receipt attests to has terminated.
+    ///
+    /// A session is complete when its final segment ended with
+    /// [ExitCode::Halted] or [ExitCode::Paused], and such a receipt is checked
+    /// with [SessionReceipt::verify]. A final segment that ended with
+    /// [ExitCode::SystemSplit] means more segments are to follow, and the
+    /// receipt can only be checked with [SessionReceipt::verify_prefix]. Like
+    /// the rest of the metadata, the result must not be trusted until the
+    /// receipt has been verified.
+    pub fn is_complete(&self) -> Result<bool, VerificationError> {
+        let final_receipt = self
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        match final_receipt.get_metadata()?.exit_code {
+            ExitCode::Halted(_) | ExitCode::Paused(_) => Ok(true),
+            ExitCode::SystemSplit | ExitCode::SessionLimit => Ok(false),
+        }
+    }
+
+    /// Returns the number of segments of each size in this receipt, keyed by
+    /// po2.
+    ///
+    /// This is useful for capacity planning, and for spotting unusually large
+    /// segments. Like [SegmentReceipt::po2], the sizes are read from the layout
+    /// of each seal without any cryptographic verification.
+    pub fn po2_histogram(&self) -> Result<BTreeMap<u32, usize>, VerificationError> {
+        let mut histogram = BTreeMap::new();
+        for receipt in self.segments.iter() {
+            *histogram
+                .entry(segment_po2(receipt.get_seal()?)?)
+                .or_default() += 1;
+        }
+        Ok(histogram)
+    }
+
+    /// Returns the total number of cycles proven by this receipt, i.e. the sum
+    /// of `2^po2` over its segments.
+    ///
+    /// This is the padded size of each segment's trace, not the number of
+    /// cycles executed by the guest: the circuit doesn't commit to the number
+    /// of user cycles, so it can't be recovered from a receipt. The padded
+    /// total is an upper bound on the user cycles, and is what determines the
+    /// cost of proving; use the `insn_cycles` of the executor's `Segment`s
+    /// where the user cycles are needed. Like [SessionReceipt::po2_histogram],
+    /// the sizes are read from the layout of each seal without any
+    /// cryptographic verification.
+    pub fn padded_cycles(&self) -> Result<u64, VerificationError> {
+        let mut cycles = 0u64;
+        for receipt in self.segments.iter() {
+            cycles += 1 << segment_po2(receipt.get_seal()?)?;
+        }
+        Ok(cycles)
+    }
+
+    /// Returns the least [SegmentReceipt::security_bits] of any segment of
+    /// this receipt.
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if the receipt has no
+    /// segments, or if any segment has no seal to estimate from, such as a
+    /// [FakeReceipt].
+    pub fn security_bits(&self) -> Result<f64, VerificationError> {
+        self.security_bits_with_context(&VerifierContext::default())
+    }
+
+    /// Returns the least security of any segment of this receipt when verified
+    /// with the given context, as by
+    /// [SegmentReceipt::security_bits_with_context];
+    /// see [SessionReceipt::security_bits].
+    pub fn security_bits_with_context(
+        &self,
+        ctx: &VerifierContext,
+    ) -> Result<f64, VerificationError> {
+        let mut min: Option<f64> = None;
+        for receipt in self.segments.iter() {
+            let bits = seal_security_bits(ctx, receipt.hashfn(), receipt.get_seal()?)?;
+            min = Some(min.map_or(bits, |min| min.min(bits)));
+        }
+        min.ok_or(VerificationError::ReceiptFormatError)
+    }
+
+    /// Returns the output digest committed by the guest, as recorded in the
+    /// metadata of the final segment.
+    ///
+    /// This allows the output to be compared against an expected digest
+    /// without the journal being available. The value is decoded from the
+    /// seal without any verification, so it must not be trusted until
+    /// [SessionReceipt::verify] has succeeded.
+    pub fn output_digest(&self) -> Result<Digest, VerificationError> {
+        let final_receipt = self
+            .segments
+            .last()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        Ok(final_receipt.get_metadata()?.output)
+    }
+
+    /// Returns the Merkle root of the memory image that the first segment
+    /// starts from.
+    ///
+    /// This is the root of the Merkle tree over the pages of the initial
+    /// memory image, as computed by `MemoryImage::compute_root_hash`. It
+    /// differs from the image ID, which also commits to the initial pc:
+    /// [compute_image_id] of this root and the initial pc gives the image ID
+    /// that the receipt must be verified against. The value is decoded from
+    /// the seal without any verification, so it must not be trusted until
+    /// [SessionReceipt::verify] has succeeded.
+    pub fn initial_merkle_root(&self) -> Result<Digest, VerificationError> {
+        let first_receipt = self
+            .segments
+            .first()
+            .ok_or(VerificationError::ReceiptFormatError)?;
+        Ok(first_receipt.get_metadata()?.pre.merkle_root)
+    }
+
+    /// Returns a compact, human-readable summary of this receipt.
+    ///
+    /// The summary lists the number of segments, the image ID and exit code
+    /// claimed by the receipt, the journal length and SHA-256 digest, the total
+    /// size of the seals, and the hash function used by the segments. This is
+    /// intended for display by command-line tools; no verification is
+    /// performed, so the values reported are only those claimed by the
+    /// receipt.
+    pub fn summary_string(&self) -> String {
+        let unknown = || String::from("<unknown>");
+        let image_id = self
+            .segments
+            .first()
+            .and_then(|receipt| receipt.get_metadata().ok())
+            .map_or_else(unknown, |metadata| {
+                format!("0x{}", hex::encode(metadata.pre.compute_image_id()))
+            });
+        let exit_code = self
+            .segments
+            .last()
+            .and_then(|receipt| receipt.get_metadata().ok())
+            .map_or_else(unknown, |metadata| format!("{:?}", metadata.exit_code));
+        let seal = self
+            .segments
+            .iter()
+            .map(|receipt| receipt.get_seal_bytes().map(<[u8]>::len))
+            .sum::<Result<usize, _>>()
+            .map_or_else(|_| unknown(), |len| format!("{len} bytes"));
+        let hashfns: BTreeSet<&str> = self
+            .segments
+            .iter()
+            .map(|receipt| receipt.hashfn())
+            .collect();
+        let hashfn = match hashfns.len() {
+            0 => "<none>",
+            1 => *hashfns.first().unwrap(),
+            _ => "mixed",
+        };
+        let journal = format!(
+            "{} bytes, sha-256: 0x{}",
+            self.journal.len(),
+            hex::encode(JournalHash::Sha256.digest(&self.journal))
+        );
+        format!(
+            "segments: {}\n\
+             image_id: {image_id}\n\
+             exit_code: {exit_code}\n\
+             journal: {journal}\n\
+             seal: {seal}\n\
+             hashfn: {hashfn}",
+            self.segments.len(),
+        )
+    }
+}
+
+impl SegmentReceipt {
+    /// Constructs a new [SegmentReceipt], checking that `hashfn` is supported.
+    ///
+    /// Returns [VerificationError::InvalidHashSuite] if `hashfn` isn't one of
+    /// [VerifierContext::supported_hashfns] for the default context, which
+    /// catches a misspelt name such as `"sha256"` where `"sha-256"` is meant.
+    /// Returns [VerificationError::ReceiptTooLarge] if the seal is larger than
+    /// [MAX_SEAL_BYTES]. No legitimate seal comes close to this size, so this
+    /// only rejects seals built from untrusted lengths.
+    pub fn new(
+        seal: Vec<u32>,
+        index: u32,
+        hashfn: impl Into<String>,
+    ) -> Result<Self, VerificationError> {
+        let hashfn = hashfn.into();
+        if !VerifierContext::default().suites.contains_key(&hashfn) {
+            return Err(VerificationError::InvalidHashSuite);
+        }
+        check_seal_len(seal.len())?;
+        Ok(Self {
+            seal: seal.into(),
+            index,
+            hashfn,
+            layout_version: LAYOUT_VERSION,
+        })
+    }
+
+    /// Decodes only the exit code from this receipt's seal.
+    ///
+    /// This returns the same value as the `exit_code` of
+    /// [Receipt::get_metadata], but skips decoding the rest of the metadata,
+    /// which makes it cheaper when checking the exit codes of many segments.
+    /// Like [Receipt::get_metadata], the result must not be trusted until
+    /// the receipt has been verified.
+    pub fn decode_exit_code(&self) -> Result<ExitCode, VerificationError> {
+        check_layout_version(self.layout_version)?;
+        ReceiptMetadata::decode_exit_code_from_io(segment_io(&self.seal)?)
+    }
+
+    /// Encodes the seal of this receipt for an on-chain verifier.
+    ///
+    /// The result is meant to be passed as a Solidity `bytes` argument. It
+    /// holds the words of the seal in order, i.e. the circuit outputs, the
+    /// po2, and then the proof, with each word encoded in big-endian byte
+    /// order as 4 bytes, so that it can be read in Solidity as
+    /// `uint32(bytes4(seal[4 * i:4 * i + 4]))`. No length or other header is
+    /// added, since the ABI encoding of `bytes` already carries the length.
+    ///
+    /// This differs from [Receipt::get_seal_bytes], which keeps each word in
+    /// the host's byte order. The segment index and hash function aren't
+    /// included, since on-chain verifiers only accept `"sha-256"` receipts,
+    /// for which the EVM has a precompile. Returns
+    /// [VerificationError::DisallowedHashFn] for a receipt proven with any
+    /// other hash function.
+    pub fn to_onchain_seal(&self) -> Result<Vec<u8>, VerificationError> {
+        if self.hashfn != "sha-256" {
+            return Err(VerificationError::DisallowedHashFn(self.hashfn.clone()));
+        }
+        Ok(self
+            .seal
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect())
+    }
+
+    /// Returns the log2 of the number of cycles in this segment's trace.
+    ///
+    /// This is read from the layout of the seal without any verification, so
+    /// it must not be trusted until the receipt has been verified.
+    pub fn po2(&self) -> Result<u32, VerificationError> {
+        segment_po2(&self.seal)
+    }
+
+    /// Estimates the bits of security provided by this receipt.
+    ///
+    /// This is the conjectured soundness of the proof, the least of:
+    ///
+    /// * The FRI query phase and the collision resistance of the hash function,
+    ///   as given by [VerifierContext::security_bits] for the default context,
+    ///   i.e. 50 queries at a blowup factor of 4. No proof-of-work grinding is
+    ///   used.
+    /// * The field size: `log2(|F|) - log2(INV_RATE * 2^po2)`, where `F` is the
+    ///   degree 4 extension of the BabyBear field from which the verifier's
+    ///   random challenges are drawn. This bounds the probability that a
+    ///   challenge lands on one of the points of the evaluation domain that let
+    ///   a bad proof through, so it shrinks as the segment grows.
+    ///
+    /// The estimate assumes the conjectured soundness of FRI, as is usual for
+    /// STARKs, rather than its proven soundness, which is lower. The po2 is
+    /// read from the seal without verification, but a seal that misstates it
+    /// won't verify. Returns [VerificationError::InvalidHashSuite] if the hash
+    /// function is unknown.
+    pub fn security_bits(&self) -> Result<f64, VerificationError> {
+        self.security_bits_with_context(&VerifierContext::default())
+    }
+
+    /// Returns the conjectured bits of security of this receipt when verified
+    /// with the given context, i.e. with the queries and hash functions of
+    /// `ctx` in place of the defaults; see [SegmentReceipt::security_bits].
+    pub fn security_bits_with_context(
+        &self,
+        ctx: &VerifierContext,
+    ) -> Result<f64, VerificationError> {
+        seal_security_bits(ctx, &self.hashfn, &self.seal)
+    }
+
+    /// Verifies this receipt and checks that it transitions from
+    /// `expected_pre` to `expected_post`.
+    ///
+    /// The states are compared against those reported by
+    /// [Receipt::get_metadata], so `expected_post.pc` is the adjusted PC at
+    /// which execution stopped, not the raw value recorded by the circuit.
+    /// Returns [VerificationError::PreStateMismatch] or
+    /// [VerificationError::PostStateMismatch] depending on which state differs,
+    /// which helps pinpoint where a continuation diverges from expectations.
+    pub fn verify_transition(
+        &self,
+        ctx: &VerifierContext,
+        expected_pre: &SystemState,
+        expected_post: &SystemState,
+    ) -> Result<(), VerificationError> {
+        let metadata = self.verify_metadata(ctx)?;
+        if metadata.pre != *expected_pre {
+            log::debug!(
+                target: LOG_MISMATCH,
+                "pre: {:?}, expected: {expected_pre:?}",
+                metadata.pre
+            );
+            return Err(VerificationError::PreStateMismatch);
+        }
+        if metadata.post != *expected_post {
+            log::debug!(
+                target: LOG_MISMATCH,
+                "post: {:?}, expected: {expected_post:?}",
+                metadata.post
+            );
+            return Err(VerificationError::PostStateMismatch);
+        }
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+impl Receipt for SegmentReceipt {
+    fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
+        self.verify_control_id(ctx).map(|_| ())
+    }
+
+    fn verify_control_id(&self, ctx: &VerifierContext) -> Result<Digest, VerificationError> {
+        verify_segment_seal(ctx, &self.hashfn, self.get_seal()?, self.layout_version)
+    }
+
+    fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
+        decode_segment_metadata(&self.seal, self.layout_version)
+    }
+
+    fn get_seal(&self) -> Result<&[u32], VerificationError> {
+        check_seal_len(self.seal.len())?;
+        Ok(&self.seal)
+    }
+
+    fn hashfn(&self) -> &str {
+        &self.hashfn
+    }
+
+    fn clone_box(&self) -> Box<dyn Receipt> {
+        Box::new(self.clone())
+    }
+}
+
+// Verifies a segment seal, and returns the control ID it was proven against.
+//
+// The `layout_version` claimed by the receipt isn't covered by the seal, so
+// once the seal has verified against [VerifierContext::circuit], it is checked
+// against the layout of that circuit. The metadata is then decoded with the
+// layout it was proven with.
+fn verify_segment_seal(
+    ctx: &VerifierContext,
+    hashfn: &str,
+    seal: &[u32],
+    layout_version: u32,
+) -> Result<Digest, VerificationError> {
+    let verified_id = Cell::new(None);
+    let check_code = |_, control_id: &Digest| -> Result<(), ProofError> {
+        if let Some(control_root) = ctx.control_root {
+            ctx.control_proofs
+                .get(control_id)
+                .filter(|proof| proof.root(control_id) == control_root)
+                .ok_or(ProofError::ControlVerificationError {
+                    control_id: *control_id,
+                })?;
+        } else if !is_builtin_control_id(control_id) {
+            return Err(ProofError::ControlVerificationError {
+                control_id: *control_id,
+            });
+        }
+        verified_id.set(Some(*control_id));
+        Ok(())
+    };
+    let suite = ctx
+        .suites
+        .get(hashfn)
+        .ok_or(VerificationError::InvalidHashSuite)?;
+    ctx.circuit
+        .verify(suite, seal, &check_code, &ctx.params.unwrap_or_default())?;
+    // A circuit that never checks the control ID hasn't verified anything.
+    let control_id = verified_id.get().ok_or(ProofError::InvalidProof)?;
+    if layout_version != ctx.circuit.layout_version() {
+        return Err(VerificationError::LayoutVersionMismatch(layout_version));
+    }
+    Ok(control_id)
+}
+
+// Whether `control_id` is one of the control IDs of the circuit built into
+// this crate, which are accepted unless [VerifierContext::control_root] is
+// set.
+fn is_builtin_control_id(control_id: &Digest) -> bool {
+    use hex::FromHex;
+    POSEIDON_CONTROL_ID
+        .into_iter()
+        .chain(SHA256_CONTROL_ID)
+        .chain(BLAKE2B_CONTROL_ID)
+        .any(|x| Digest::from_hex(x).unwrap() == *control_id)
+}
+
+fn segment_io(seal: &[u32]) -> Result<layout::OutBuffer, VerificationError> {
+    // The seal comes from untrusted input; reject anything too short to hold
+    // the circuit outputs before the layout tree indexes into it.
+    if seal.len() < CircuitImpl::OUTPUT_SIZE {
+        return Err(VerificationError::ReceiptFormatError);
+    }
+    let elems = bytemuck::cast_slice(&seal[..CircuitImpl::OUTPUT_SIZE]);
+    Ok(layout::OutBuffer(elems))
+}
+
+// The po2 of the segment follows the circuit outputs in the seal.
+fn segment_po2(seal: &[u32]) -> Result<u32, VerificationError> {
+    seal.get(CircuitImpl::OUTPUT_SIZE)
+        .copied()
+        .filter(|po2| *po2 as usize <= risc0_zkp::MAX_CYCLES_PO2)
+        .ok_or(VerificationError::ReceiptFormatError)
+}
+
+// The conjectured security of a seal, see [SegmentReceipt::security_bits].
+fn seal_security_bits(
+    ctx: &VerifierContext,
+    hashfn: &str,
+    seal: &[u32],
+) -> Result<f64, VerificationError> {
+    let suite_bits = ctx.security_bits(hashfn)?;
+    let domain_po2 = segment_po2(seal)? + risc0_zkp::INV_RATE.ilog2();
+    let modulus = (BabyBearElem::ZERO - BabyBearElem::ONE).as_u32() as f64 + 1.0;
+    let field_bits = BabyBearExtElem::EXT_SIZE as f64 * libm::log2(modulus);
+    Ok((field_bits - domain_po2 as f64).min(suite_bits))
+}
+
+fn decode_segment_metadata(
+    seal: &[u32],
+    layout_version: u32,
+) -> Result<ReceiptMetadata, VerificationError> {
+    check_layout_version(layout_version)?;
+    ReceiptMetadata::decode_from_io(segment_io(seal)?)
+}
+
+impl SystemState {
+    fn decode_from_io(
+        io: layout::OutBuffer,
+        sys_state: &layout::SystemState,
+    ) -> Result<Self, VerificationError> {
+        let mut bytes: [u8; DIGEST_BYTES] = io
+            .tree(sys_state.image_id)
+            .get_byte_array()
+            .or(Err(VerificationError::MalformedSealImageId))?;
+        let merkle_root = take_digest(&mut bytes);
+        let pc = io
+            .tree(sys_state.pc)
+            .get_u32()
+            .or(Err(VerificationError::MalformedPc))?;
+        Ok(Self { pc, merkle_root })
+    }
+
+    fn compute_image_id(&self) -> Digest {
+        compute_image_id(&self.merkle_root, self.pc)
+    }
+}
+
+impl ReceiptMetadata {
+    /// Returns the fields that differ between this metadata and `other`, in
+    /// declaration order.
+    ///
+    /// The result is empty exactly when the two values are equal.
+    pub fn diff(&self, other: &ReceiptMetadata) -> Vec<MetadataDiff> {
+        let mut diffs = Vec::new();
+        if self.pre.pc != other.pre.pc {
+            diffs.push(MetadataDiff::PrePc(self.pre.pc, other.pre.pc));
+        }
+        if self.pre.merkle_root != other.pre.merkle_root {
+            diffs.push(MetadataDiff::PreMerkleRoot(
+                self.pre.merkle_root,
+                other.pre.merkle_root,
+            ));
+        }
+        if self.post.pc != other.post.pc {
+            diffs.push(MetadataDiff::PostPc(self.post.pc, other.post.pc));
+        }
+        if self.post.merkle_root != other.post.merkle_root {
+            diffs.push(MetadataDiff::PostMerkleRoot(
+                self.post.merkle_root,
+                other.post.merkle_root,
+            ));
+        }
+        if self.exit_code != other.exit_code {
+            diffs.push(MetadataDiff::ExitCode(self.exit_code, other.exit_code));
+        }
+        if self.input != other.input {
+            diffs.push(MetadataDiff::Input(self.input, other.input));
+        }
+        if self.output != other.output {
+            diffs.push(MetadataDiff::Output(self.output, other.output));
+        }
+        diffs
+    }
+
+    fn decode_from_io(io: layout::OutBuffer) -> Result<Self, VerificationError> {
+        let body = layout::LAYOUT.mux.body;
+        let pre = SystemState::decode_from_io(io, body.global.pre)?;
+        let mut post = SystemState::decode_from_io(io, body.global.post)?;
+        post.pc = ReceiptMetadata::adjust_post_pc(post.pc)?;
+        let mut input_bytes: [u8; DIGEST_BYTES] = io
+            .tree(body.global.input)
+            .get_byte_array()
+            .or(Err(VerificationError::MalformedInput))?;
+        let input = take_digest(&mut input_bytes);
+        let mut output_bytes: [u8; DIGEST_BYTES] = io
+            .tree(body.global.output)
+            .get_byte_array()
+            .or(Err(VerificationError::MalformedOutput))?;
+        let output = take_digest(&mut output_bytes);
+        let exit_code = ReceiptMetadata::decode_exit_code_from_io(io)?;
+        Ok(Self {
+            pre,
+            post,
+            exit_code,
+            input,
+            output,
+        })
+    }
+
+    // In order to avoid extra logic in the rv32im circuit to perform arthimetic on
+    // the PC with carry, the PC is always recorded as the current PC +
+    // 4. Thus we need to adjust the decoded PC for the post SystemState.
+    //
+    // The adjusted PC must still be a word-aligned address within guest memory;
+    // anything else can only come from a malformed seal.
+    fn adjust_post_pc(pc: u32) -> Result<u32, VerificationError> {
+        let pc = pc
+            .checked_sub(WORD_SIZE as u32)
+            .ok_or(VerificationError::MalformedPc)?;
+        if pc % WORD_SIZE as u32 != 0 || pc as usize >= MEM_SIZE {
+            return Err(VerificationError::MalformedPc);
+        }
+        Ok(pc)
+    }
+
+    pub(crate) fn get_exit_code_pairs(&self) -> Result<(u32, u32), VerificationError> {
+        match self.exit_code {
+            ExitCode::Halted(user_exit) => Ok((0, user_exit)),
+            ExitCode::Paused(user_exit) => Ok((1, user_exit)),
+            ExitCode::SystemSplit => Ok((2, 0)),
+            // The executor never proves a segment that hit the session limit.
+            ExitCode::SessionLimit => Err(VerificationError::ReceiptFormatError),
+        }
+    }
+
+    fn decode_exit_code_from_io(io: layout::OutBuffer) -> Result<ExitCode, VerificationError> {
+        let body = layout::LAYOUT.mux.body;
+        let sys_exit = io.get_u64(body.global.sys_exit_code) as u32;
+        let user_exit = io.get_u64(body.global.user_exit_code) as u32;
+        ReceiptMetadata::make_exit_code(sys_exit, user_exit)
+    }
+
+    pub(crate) fn make_exit_code(
+        sys_exit: u32,
+        user_exit: u32,
+    ) -> Result<ExitCode, VerificationError> {
+        match sys_exit {
+            0 => Ok(ExitCode::Halted(user_exit)),
+            1 => Ok(ExitCode::Paused(user_exit)),
+            2 => Ok(ExitCode::SystemSplit),
+            _ => Err(VerificationError::MalformedExitCode),
+        }
+    }
+}
+
+impl JournalHash {
+    /// Compute the digest of the given `journal` using this hash function.
+    ///
+    /// On x86 hosts, SHA-256 uses the CPU's SHA extensions when they are
+    /// detected at runtime. The `sha-asm` feature additionally enables the
+    /// SHA-2 instructions on AArch64, and assembly implementations for CPUs
+    /// without them, which is worthwhile when verifying large journals.
+    pub fn digest(&self, journal: &[u8]) -> Digest {
+        match self {
+            JournalHash::Sha256 => journal_digest(journal),
+            JournalHash::Poseidon => {
+                let elems: Vec<BabyBearElem> = journal
+                    .iter()
+                    .map(|byte| BabyBearElem::new(*byte as u32))
+                    .collect();
+                *PoseidonHashSuite::new().hashfn.hash_elem_slice(&elems)
+            }
+        }
+    }
+
+    /// Checks that `output` commits to `journal`.
+    ///
+    /// The guest commits to its journal by writing the journal's digest to
+    /// `output`. A guest that never writes to its journal leaves `output` as
+    /// all zeros, so an empty journal is also accepted with an all-zero
+    /// `output`. Any other `output`, including a nonzero one alongside an
+    /// empty journal, commits to something other than `journal` and is
+    /// rejected.
+    fn check(&self, journal: &[u8], output: &Digest) -> Result<(), VerificationError> {
+        let digest = self.digest(journal);
+        let digest_words = digest.as_words();
+        let output_words = output.as_words();
+        let is_journal_valid = || {
+            (journal.is_empty() && output_words.iter().all(|x| *x == 0))
+                || digest_words == output_words
+        };
+        if !is_journal_valid() {
+            log::debug!(
+                target: LOG_MISMATCH,
+                "journal length: {}, digest: 0x{}, output: 0x{}",
+                journal.len(),
+                hex::encode(bytemuck::cast_slice(digest_words)),
+                hex::encode(bytemuck::cast_slice(output_words))
+            );
+            log::trace!(
+                target: LOG_MISMATCH,
+                "journal: \"{}\", {:?}",
+                hex::encode(journal),
+                journal
+            );
+            return Err(VerificationError::JournalDigestMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Verifies that receipt `b` consumes the output of receipt `a`.
+///
+/// Both receipts are verified against their image IDs, then the output digest
+/// committed by `a` (see [SessionReceipt::output_digest]) is checked against
+/// the input digest committed by `b` (see [ReceiptMetadata::input]). Returns
+/// [VerificationError::ChainMismatch] if they differ. This is the building
+/// block for pipelines of proofs, where each proof's input is the previous
+/// proof's output.
+pub fn verify_chain(
+    a: &SessionReceipt,
+    b: &SessionReceipt,
+    image_id_a: impl Into<Digest>,
+    image_id_b: impl Into<Digest>,
+) -> Result<(), VerificationError> {
+    verify_chain_with_context(&VerifierContext::default(), a, b, image_id_a, image_id_b)
+}
+
+/// Verifies that receipt `b` consumes the output of receipt `a`, using the
+/// given [VerifierContext].
+///
+/// See [verify_chain].
+pub fn verify_chain_with_context(
+    ctx: &VerifierContext,
+    a: &SessionReceipt,
+    b: &SessionReceipt,
+    image_id_a: impl Into<Digest>,
+    image_id_b: impl Into<Digest>,
+) -> Result<(), VerificationError> {
+    a.verify_with_context(ctx, image_id_a)?;
+    b.verify_with_context(ctx, image_id_b)?;
+    let input = b
+        .segments
+        .first()
+        .ok_or(VerificationError::ReceiptFormatError)?
+        .get_metadata()?
+        .input;
+    if a.output_digest()? != input {
+        return Err(VerificationError::ChainMismatch);
+    }
+    Ok(())
+}
+
+/// Verifies each receipt in `receipts` against its paired image ID, sharing
+/// one [VerifierContext].
+///
+/// Returns the result for each receipt, in order. Unlike verifying the
+/// receipts in a loop with `?`, a receipt that fails doesn't stop the
+/// remaining receipts from being verified. The receipts are verified one after
+/// another; since receipts and contexts are `Send + Sync`, callers that want
+/// parallelism can split the batch across threads.
+pub fn verify_batch(
+    receipts: &[(SessionReceipt, Digest)],
+    ctx: &VerifierContext,
+) -> Vec<Result<(), VerificationError>> {
+    receipts
+        .iter()
+        .map(|(receipt, image_id)| receipt.verify_with_context(ctx, *image_id))
+        .collect()
+}
+
+/// Compute the `output` digest that commits to `journal`.
+///
+/// This is the digest that the standard zkVM guest writes to
+/// [ReceiptMetadata::output], and that [SessionReceipt::verify_with_context]
+/// checks the journal against with the default [JournalHash::Sha256]: the
+/// SHA-256 hash of the journal's bytes. Verification calls this same function,
+/// so the two can't diverge. A guest that never writes to its journal leaves
+/// `output` as all zeros rather than the digest of an empty journal; see
+/// [VerifierContext::commitment] for guests that commit with another hash.
+pub fn journal_digest(journal: &[u8]) -> Digest {
+    // Use the same SHA-256 implementation as image IDs.
+    *sha::Impl::hash_bytes(journal)
+}
+
+/// Compute the commitment to an `(image_id, journal)` pair that is checked by
+/// [SessionReceipt::verify_against_commitment].
+///
+/// The commitment is the standard SHA-256 hash of a 64-byte preimage: the 32
+/// bytes of `image_id`, followed by the 32 bytes of the SHA-256 hash of
+/// `journal`. Each [Digest] contributes its bytes in memory order, i.e. as
+/// returned by [Digest::as_bytes] and as printed by `hex::encode`.
+pub fn compute_journal_commitment(image_id: &Digest, journal: &[u8]) -> Digest {
+    let mut preimage = [0u8; 64];
+    preimage[..32].copy_from_slice(image_id.as_bytes());
+    preimage[32..].copy_from_slice(journal_digest(journal).as_bytes());
+    *sha::Impl::hash_bytes(&preimage)
+}
+
+/// Compute and return the ImageID of the given `(merkle_root, pc)` pair.
+///
+/// The image ID is the output of a single SHA-256 compression, without
+/// padding, of a 64-byte block: the 32 bytes of `merkle_root` as returned by
+/// [Digest::as_bytes], followed by `pc` in little-endian byte order, followed
+/// by 28 zero bytes. Its bytes are the SHA-256 state in the standard
+/// (big-endian) byte order, as for any digest computed by [crate::sha].
+pub fn compute_image_id(merkle_root: &Digest, pc: u32) -> Digest {
+    let mut block2 = Digest::default();
+    block2.as_mut_words()[0] = pc;
+    compress_image_id(merkle_root, &mut block2)
+}
+
+// Compresses the image ID block, wiping the second half of the block and the
+// intermediate result.
+fn compress_image_id(merkle_root: &Digest, block2: &mut Digest) -> Digest {
+    use risc0_zkp::core::hash::sha::Sha256;
+    let mut result = sha::Impl::compress(&SHA256_INIT, merkle_root, block2);
+    let image_id = *result;
+    wipe(block2.as_mut_words());
+    wipe(result.as_mut_words());
+    image_id
+}
+
+// Converts digest bytes decoded from a seal into a digest, wiping the bytes.
+fn take_digest(bytes: &mut [u8; DIGEST_BYTES]) -> Digest {
+    let digest = Digest::from(*bytes);
+    wipe(bytes);
+    digest
+}
+
+// Overwrites `buf` with zeros if the `zeroize` feature is enabled, so that
+// scratch data doesn't linger in memory once verification is done with it.
+#[cfg(feature = "zeroize")]
+fn wipe<T: zeroize::Zeroize + ?Sized>(buf: &mut T) {
+    buf.zeroize();
+}
+
+#[cfg(not(feature = "zeroize"))]
+fn wipe<T: ?Sized>(_buf: &mut T) {}
+
+impl VerifierContext {
+    // A digest of the settings that can change the result of verifying with
+    // this context, for keying cached results. Those that can't be compared by
+    // value are identified by [VerifierContext::cache_id].
+    pub(crate) fn cache_digest(&self) -> Digest {
+        fn push_u64(words: &mut Vec<u32>, value: u64) {
+            words.extend([value as u32, (value >> 32) as u32]);
+        }
+
+        let mut words = Vec::new();
+        push_u64(&mut words, self.cache_id);
+        push_u64(&mut words, self.suites.len() as u64);
+        for name in self.suites.keys() {
+            words.extend(sha::Impl::hash_bytes(name.as_bytes()).as_words());
+        }
+        words.push(self.check_journal as u32);
+        match self.control_root {
+            Some(root) => {
+                words.push(1);
+                words.extend(root.as_words());
+            }
+            None => words.push(0),
+        }
+        push_u64(&mut words, self.control_proofs.len() as u64);
+        for (control_id, proof) in self.control_proofs.iter() {
+            words.extend(control_id.as_words());
+            words.push(proof.index);
+            push_u64(&mut words, proof.digests.len() as u64);
+            for digest in proof.digests.iter() {
+                words.extend(digest.as_words());
+            }
+        }
+        words.push(self.dev_mode as u32);
+        match self.params {
+            Some(params) => {
+                words.push(1);
+                push_u64(&mut words, params.queries as u64);
+                push_u64(&mut words, params.fri_fold_po2 as u64);
+                push_u64(&mut words, params.fri_min_degree as u64);
+            }
+            None => words.push(0),
+        }
+        *sha::Impl::hash_words(&words)
+    }
+
+    /// Returns the names of the hash functions this context can verify, in
+    /// sorted order.
+    ///
+    /// These are the values accepted in [SegmentReceipt::hashfn].
+    pub fn supported_hashfns(&self) -> Vec<&str> {
+        self.suites.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the bits of security provided by receipts proven with the hash
+    /// function named `hashfn` and verified with this context.
+    ///
+    /// This is the lesser of the conjectured soundness of the FRI query phase,
+    /// `queries * log2(INV_RATE)` with the queries of
+    /// [VerifierContext::params], and the
+    /// [risc0_zkp::core::hash::HashFn::collision_bits] of the suite's hash
+    /// function. Returns [VerificationError::InvalidHashSuite] if
+    /// `hashfn` isn't one of [VerifierContext::suites].
+    pub fn security_bits(&self, hashfn: &str) -> Result<f64, VerificationError> {
+        let suite = self
+            .suites
+            .get(hashfn)
+            .ok_or(VerificationError::InvalidHashSuite)?;
+        let queries = u32::try_from(self.params.unwrap_or_default().queries).unwrap_or(u32::MAX);
+        let fri_bits = queries.saturating_mul(risc0_zkp::INV_RATE.ilog2());
+        Ok(fri_bits.min(suite.hashfn.collision_bits()).into())
+    }
+}
+
+impl Default for VerifierContext {
     fn default() -> Self {
         Self {
             suites: BTreeMap::from([
@@ -438,6 +2883,1548 @@ impl Default for VerifierContext {
                 ("poseidon".into(), PoseidonHashSuite::new()),
                 ("sha-256".into(), Sha256HashSuite::new()),
             ]),
+            check_journal: true,
+            commitment: Arc::new(JournalHash::default()),
+            control_root: None,
+            control_proofs: BTreeMap::new(),
+            deprecated_hashfns: BTreeSet::new(),
+            large_journal_bytes: 1 << 20,
+            dev_mode: false,
+            params: None,
+            circuit: &crate::CIRCUIT,
+            cache_id: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::{core::digest::Digest, verify::VerificationError as ProofError};
+
+    use super::{
+        compute_journal_commitment, ExitCode, FakeReceipt, IncrementalVerifier, JournalHash,
+        MetadataDiff, Receipt, ReceiptMetadata, SegmentReceipt, SessionReceipt, SystemState,
+        VerificationError, VerifierContext, VerifyWarning, LAYOUT_VERSION,
+    };
+    use crate::{
+        sha::rust_crypto::{Digest as _, Sha256},
+        testutils::{self, dev_mode_ctx, fake_image_id, fake_metadata},
+        MEM_SIZE,
+    };
+
+    #[test]
+    fn user_code() {
+        assert_eq!(ExitCode::Halted(3).user_code(), Some(3));
+        assert_eq!(ExitCode::Paused(0).user_code(), Some(0));
+        assert_eq!(ExitCode::SystemSplit.user_code(), None);
+        assert_eq!(ExitCode::SessionLimit.user_code(), None);
+    }
+
+    #[test]
+    fn exit_code_pairs() {
+        for exit_code in [
+            ExitCode::Halted(0),
+            ExitCode::Halted(7),
+            ExitCode::Paused(3),
+            ExitCode::SystemSplit,
+        ] {
+            let metadata = ReceiptMetadata {
+                pre: SystemState {
+                    pc: 0,
+                    merkle_root: Digest::default(),
+                },
+                post: SystemState {
+                    pc: 0,
+                    merkle_root: Digest::default(),
+                },
+                exit_code,
+                input: Digest::default(),
+                output: Digest::default(),
+            };
+            let (sys_exit, user_exit) = metadata.get_exit_code_pairs().unwrap();
+            assert_eq!(
+                ReceiptMetadata::make_exit_code(sys_exit, user_exit),
+                Ok(exit_code)
+            );
+        }
+
+        // SessionLimit has no circuit encoding, so can't be proven, and no
+        // encoding decodes to it.
+        let metadata = ReceiptMetadata {
+            pre: SystemState {
+                pc: 0,
+                merkle_root: Digest::default(),
+            },
+            post: SystemState {
+                pc: 0,
+                merkle_root: Digest::default(),
+            },
+            exit_code: ExitCode::SessionLimit,
+            input: Digest::default(),
+            output: Digest::default(),
+        };
+        assert_eq!(
+            metadata.get_exit_code_pairs(),
+            Err(VerificationError::ReceiptFormatError)
+        );
+        assert_eq!(
+            ReceiptMetadata::make_exit_code(3, 0),
+            Err(VerificationError::MalformedExitCode)
+        );
+    }
+
+    #[test]
+    fn adjust_post_pc() {
+        assert_eq!(ReceiptMetadata::adjust_post_pc(0x1004), Ok(0x1000));
+        assert_eq!(ReceiptMetadata::adjust_post_pc(4), Ok(0));
+        assert_eq!(
+            ReceiptMetadata::adjust_post_pc(MEM_SIZE as u32),
+            Ok(MEM_SIZE as u32 - 4)
+        );
+        for pc in [0, 3, 0x1005, 0x1006, 0x1007, MEM_SIZE as u32 + 4, u32::MAX] {
+            assert_eq!(
+                ReceiptMetadata::adjust_post_pc(pc),
+                Err(VerificationError::MalformedPc),
+                "{pc:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        use risc0_zkp::adapter::CircuitInfo;
+
+        use super::{layout::LAYOUT, BabyBearElem, CircuitImpl};
+
+        let global = LAYOUT.mux.body.global;
+        let elem = |x| BabyBearElem::new(x).as_u32_montgomery();
+        // All zeros, except for a post-state pc of 4, which decodes as 0.
+        let mut seal = vec![0; CircuitImpl::OUTPUT_SIZE];
+        seal[global.post.pc.byte[0].offset] = elem(4);
+        let metadata = |seal: &[u32]| {
+            SegmentReceipt::new(seal.to_vec(), 0, "sha-256")
+                .unwrap()
+                .get_metadata()
+        };
+        assert_eq!(metadata(&seal).unwrap().exit_code, ExitCode::Halted(0));
+
+        // Each region fails with its own error when one of its bytes doesn't
+        // fit in a byte.
+        for (offset, err) in [
+            (
+                global.pre.image_id.word[3].byte[1].offset,
+                VerificationError::MalformedSealImageId,
+            ),
+            (
+                global.post.image_id.word[0].byte[0].offset,
+                VerificationError::MalformedSealImageId,
+            ),
+            (global.pre.pc.byte[2].offset, VerificationError::MalformedPc),
+            (
+                global.post.pc.byte[3].offset,
+                VerificationError::MalformedPc,
+            ),
+            (
+                global.input.word[7].byte[3].offset,
+                VerificationError::MalformedInput,
+            ),
+            (
+                global.output.word[0].byte[0].offset,
+                VerificationError::MalformedOutput,
+            ),
+            (
+                global.sys_exit_code.offset,
+                VerificationError::MalformedExitCode,
+            ),
+        ] {
+            let mut tampered = seal.clone();
+            tampered[offset] = elem(256);
+            assert_eq!(metadata(&tampered).unwrap_err(), err, "{offset}");
+        }
+
+        // A sys exit code that fits, but isn't one of the known codes.
+        let mut tampered = seal.clone();
+        tampered[global.sys_exit_code.offset] = elem(3);
+        assert_eq!(
+            metadata(&tampered).unwrap_err(),
+            VerificationError::MalformedExitCode
+        );
+        assert_eq!(
+            SegmentReceipt::new(tampered, 0, "sha-256")
+                .unwrap()
+                .decode_exit_code()
+                .unwrap_err(),
+            VerificationError::MalformedExitCode
+        );
+
+        // Values that decode, but aren't a valid post-state pc.
+        seal[global.post.pc.byte[0].offset] = elem(6);
+        assert_eq!(metadata(&seal).unwrap_err(), VerificationError::MalformedPc);
+    }
+
+    #[test]
+    fn supported_hashfns() {
+        assert_eq!(
+            VerifierContext::default().supported_hashfns(),
+            ["blake2b", "poseidon", "sha-256"]
+        );
+    }
+
+    #[test]
+    fn security_bits() {
+        use risc0_zkp::verify::VerifierParams;
+
+        let mut ctx = VerifierContext::default();
+        for hashfn in ctx.supported_hashfns() {
+            assert_eq!(ctx.security_bits(hashfn).unwrap(), 100.0);
+        }
+        assert_eq!(
+            ctx.security_bits("sha256").unwrap_err(),
+            VerificationError::InvalidHashSuite
+        );
+
+        // Fewer queries give less security, and more queries are limited by the
+        // collision resistance of the hash function.
+        ctx.params = Some(VerifierParams {
+            queries: 40,
+            ..Default::default()
+        });
+        assert_eq!(ctx.security_bits("sha-256").unwrap(), 80.0);
+        ctx.params = Some(VerifierParams {
+            queries: 100,
+            ..Default::default()
+        });
+        assert_eq!(ctx.security_bits("sha-256").unwrap(), 128.0);
+        assert_eq!(ctx.security_bits("poseidon").unwrap(), 124.0);
+    }
+
+    #[test]
+    fn shareable_receipt() {
+        fn assert_shareable<T: Send + Sync>() {}
+        assert_shareable::<Box<dyn Receipt>>();
+        assert_shareable::<SessionReceipt>();
+    }
+
+    #[test]
+    fn segment_receipt_new() {
+        let receipt = SegmentReceipt::new(vec![1, 2, 3], 4, "sha-256").unwrap();
+        assert_eq!(
+            receipt,
+            SegmentReceipt {
+                seal: vec![1, 2, 3].into(),
+                index: 4,
+                hashfn: "sha-256".into(),
+                layout_version: LAYOUT_VERSION,
+            }
+        );
+        assert_eq!(
+            SegmentReceipt::new(Vec::new(), 0, "sha256").unwrap_err(),
+            VerificationError::InvalidHashSuite
+        );
+    }
+
+    #[test]
+    fn oversized_seal() {
+        use super::{MAX_SEAL_BYTES, WORD_SIZE};
+        use crate::serde::{from_slice, to_vec, Error};
+
+        let max_words = MAX_SEAL_BYTES / WORD_SIZE;
+        SegmentReceipt::new(vec![0; max_words], 0, "sha-256").unwrap();
+        assert_eq!(
+            SegmentReceipt::new(vec![0; max_words + 1], 0, "sha-256").unwrap_err(),
+            VerificationError::ReceiptTooLarge
+        );
+
+        // A receipt built directly with an oversized seal is rejected when its
+        // seal is used.
+        let mut receipt = SegmentReceipt::new(vec![1, 2, 3], 0, "sha-256").unwrap();
+        receipt.seal.to_mut().resize(max_words + 1, 0);
+        assert_eq!(receipt.get_seal(), Err(VerificationError::ReceiptTooLarge));
+        assert_eq!(receipt.verify(), Err(VerificationError::ReceiptTooLarge));
+
+        // A deliberately oversized declared length is rejected as soon as it
+        // is read, rather than at the end of the input.
+        let receipt = SegmentReceipt::new(vec![1, 2, 3], 0, "sha-256").unwrap();
+        let mut encoded = to_vec(&receipt).unwrap();
+        assert_eq!(encoded[0], 3);
+        encoded[0] = max_words as u32;
+        assert_eq!(
+            from_slice::<SegmentReceipt, _>(&encoded).unwrap_err(),
+            Error::DeserializeUnexpectedEnd
+        );
+        encoded[0] = max_words as u32 + 1;
+        assert_eq!(
+            from_slice::<SegmentReceipt, _>(&encoded).unwrap_err(),
+            Error::NotSupported
+        );
+        let mut json = serde_json::to_value(&receipt).unwrap();
+        json["seal"] = vec![0; max_words + 1].into();
+        let err = serde_json::from_value::<SegmentReceipt>(json).unwrap_err();
+        assert!(err.to_string().contains("receipt too large"), "{err}");
+    }
+
+    #[test]
+    fn fake_receipt() {
+        let journal = b"journal".to_vec();
+        let pre = SystemState {
+            pc: 0x1000,
+            merkle_root: Digest::from([1; 8]),
+        };
+        let image_id = pre.compute_image_id();
+        let metadata = ReceiptMetadata {
+            pre,
+            post: SystemState {
+                pc: 0x2000,
+                merkle_root: Digest::from([2; 8]),
+            },
+            exit_code: ExitCode::Halted(0),
+            input: Digest::default(),
+            output: JournalHash::Sha256.digest(&journal),
+        };
+        let receipt = SessionReceipt::new(vec![Box::new(FakeReceipt::new(metadata))], journal);
+
+        assert_eq!(
+            receipt.verify(image_id).unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+        let ctx = dev_mode_ctx();
+        receipt.verify_with_context(&ctx, image_id).unwrap();
+        // There is no circuit, and so no control ID.
+        assert_eq!(
+            receipt.segments[0].verify_control_id(&ctx).unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        // The claimed metadata is still checked.
+        assert_eq!(
+            receipt
+                .verify_with_context(&ctx, Digest::default())
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+
+        #[cfg(feature = "std")]
+        {
+            let timing = receipt.verify_with_timing(&ctx, image_id).unwrap();
+            assert_eq!(timing.segments.len(), 1);
+            assert_eq!(timing.segments[0].hashfn, "none");
+            assert_eq!(timing.segments[0].po2, None);
+            assert_eq!(
+                receipt
+                    .verify_with_timing(&VerifierContext::default(), image_id)
+                    .unwrap_err(),
+                VerificationError::DevModeDisabled
+            );
+        }
+    }
+
+    #[test]
+    fn is_complete() {
+        let metadata = |exit_code| ReceiptMetadata {
+            pre: SystemState {
+                pc: 0x1000,
+                merkle_root: Digest::default(),
+            },
+            post: SystemState {
+                pc: 0x2000,
+                merkle_root: Digest::default(),
+            },
+            exit_code,
+            input: Digest::default(),
+            output: Digest::default(),
+        };
+        let receipt = |exit_code| {
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(metadata(exit_code)))],
+                Vec::new(),
+            )
+        };
+        assert!(receipt(ExitCode::Halted(0)).is_complete().unwrap());
+        assert!(receipt(ExitCode::Paused(1)).is_complete().unwrap());
+        assert!(!receipt(ExitCode::SystemSplit).is_complete().unwrap());
+        assert_eq!(
+            SessionReceipt::new(Vec::new(), Vec::new())
+                .is_complete()
+                .unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+
+        assert_eq!(receipt(ExitCode::Paused(7)).pause_code().unwrap(), Some(7));
+        assert_eq!(receipt(ExitCode::Halted(7)).pause_code().unwrap(), None);
+        assert_eq!(
+            receipt(ExitCode::SystemSplit).pause_code().unwrap_err(),
+            VerificationError::UnexpectedExitCode
+        );
+    }
+
+    // The conversions between digest words and bytes must match external
+    // tooling, which works with the standard SHA-256 byte order.
+    #[test]
+    fn digest_byte_order() {
+        // SHA-256("abc"), as printed by e.g. `sha256sum`.
+        let abc: [u8; 32] = hex::FromHex::from_hex(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        )
+        .unwrap();
+        let digest = JournalHash::Sha256.digest(b"abc");
+        assert_eq!(digest.as_bytes(), abc);
+        assert_eq!(digest, Digest::from(abc));
+        assert_eq!(
+            digest.as_words()[0],
+            u32::from_le_bytes([0xba, 0x78, 0x16, 0xbf])
+        );
+
+        // The journal check compares against a digest given as standard bytes.
+        let metadata = |output| ReceiptMetadata {
+            pre: SystemState {
+                pc: 0,
+                merkle_root: Digest::default(),
+            },
+            post: SystemState {
+                pc: 0,
+                merkle_root: Digest::default(),
+            },
+            exit_code: ExitCode::Halted(0),
+            input: Digest::default(),
+            output,
+        };
+        let receipt = |output| {
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(metadata(output)))],
+                b"abc".to_vec(),
+            )
+        };
+        let ctx = dev_mode_ctx();
+        let image_id = super::compute_image_id(&Digest::default(), 0);
+        receipt(Digest::from(abc))
+            .verify_with_context(&ctx, image_id)
+            .unwrap();
+        let mut swapped = abc;
+        swapped.chunks_exact_mut(4).for_each(|word| word.reverse());
+        assert_eq!(
+            receipt(Digest::from(swapped))
+                .verify_with_context(&ctx, image_id)
+                .unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+
+        // An image ID hashes the Merkle root's bytes followed by the pc in
+        // little-endian order.
+        let merkle_root = Digest::from(core::array::from_fn::<u8, 32, _>(|i| i as u8));
+        assert_eq!(
+            hex::encode(super::compute_image_id(&merkle_root, 0x0001_0000)),
+            "dda895d32aed4127e52273667421082c7b36371f7a4b51fd8b467b16f768224e"
+        );
+    }
+
+    #[test]
+    fn to_onchain_seal() {
+        let seal = vec![0x01020304, 0xdeadbeef, 7];
+        let receipt = SegmentReceipt::new(seal.clone(), 0, "sha-256").unwrap();
+        let encoded = receipt.to_onchain_seal().unwrap();
+        assert_eq!(hex::encode(&encoded), "01020304deadbeef00000007");
+        // Reading each word as `uint32(bytes4(seal[4 * i:4 * i + 4]))` does,
+        // i.e. as the big-endian integer of its 4 bytes, recovers the seal.
+        let decoded: Vec<u32> = encoded
+            .chunks_exact(4)
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .fold(0u32, |word, byte| word << 8 | *byte as u32)
+            })
+            .collect();
+        assert_eq!(decoded, seal);
+
+        let receipt = SegmentReceipt::new(seal, 0, "poseidon").unwrap();
+        assert_eq!(
+            receipt.to_onchain_seal().unwrap_err(),
+            VerificationError::DisallowedHashFn("poseidon".into())
+        );
+    }
+
+    #[cfg(target_endian = "little")]
+    #[test]
+    fn seal_byte_order() {
+        let receipt = SegmentReceipt::new(vec![0x04030201, 0x08070605], 0, "sha-256").unwrap();
+        assert_eq!(receipt.get_seal_bytes().unwrap(), [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn seal_security_bits() {
+        use risc0_zkp::adapter::CircuitInfo;
+
+        let segment = |po2| {
+            let mut seal = vec![0; super::CircuitImpl::OUTPUT_SIZE];
+            seal.push(po2);
+            SegmentReceipt::new(seal, 0, "sha-256").unwrap()
+        };
+        // Small segments are limited by the FRI query phase.
+        assert_eq!(segment(16).security_bits().unwrap(), 100.0);
+        // Large segments are limited by the size of the extension field:
+        // 4 * log2(15 * 2^27 + 1) - (24 + 2).
+        let bits = segment(24).security_bits().unwrap();
+        assert!((bits - 97.63).abs() < 0.01, "{bits}");
+
+        let receipt = SessionReceipt::new(
+            vec![Box::new(segment(16)), Box::new(segment(24))],
+            Vec::new(),
+        );
+        assert_eq!(receipt.security_bits().unwrap(), bits);
+        assert_eq!(
+            SessionReceipt::new(Vec::new(), Vec::new())
+                .security_bits()
+                .unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+
+    #[test]
+    fn padded_cycles() {
+        use risc0_zkp::adapter::CircuitInfo;
+
+        let segment = |po2| {
+            let mut seal = vec![0; super::CircuitImpl::OUTPUT_SIZE];
+            seal.push(po2);
+            Box::new(SegmentReceipt::new(seal, 0, "sha-256").unwrap()) as Box<dyn Receipt>
+        };
+        let receipt = SessionReceipt::new(vec![segment(16), segment(20), segment(16)], Vec::new());
+        assert_eq!(receipt.padded_cycles().unwrap(), (1 << 20) + (2 << 16));
+        assert_eq!(
+            SessionReceipt::new(Vec::new(), Vec::new())
+                .padded_cycles()
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            SessionReceipt::new(vec![segment(64)], Vec::new())
+                .padded_cycles()
+                .unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+
+    #[test]
+    fn same_program() {
+        let receipt = |pc, journal: &[u8]| {
+            let state = SystemState {
+                pc,
+                merkle_root: Digest::default(),
+            };
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(ReceiptMetadata {
+                    pre: state.clone(),
+                    post: state,
+                    ..fake_metadata(journal)
+                }))],
+                journal.to_vec(),
+            )
+        };
+        let ctx = dev_mode_ctx();
+
+        let a = receipt(0x1000, b"a");
+        assert!(a
+            .same_program_with_context(&ctx, &receipt(0x1000, b"b"))
+            .unwrap());
+        assert!(!a
+            .same_program_with_context(&ctx, &receipt(0x2000, b"a"))
+            .unwrap());
+
+        let mut invalid = receipt(0x1000, b"a");
+        invalid.journal = b"b".to_vec();
+        assert_eq!(
+            a.same_program_with_context(&ctx, &invalid).unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+        assert_eq!(
+            a.same_program(&receipt(0x1000, b"a")).unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+    }
+
+    #[test]
+    fn verify_with_warnings() {
+        let receipt = |exit_code, journal: &[u8]| {
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(ReceiptMetadata {
+                    exit_code,
+                    ..fake_metadata(journal)
+                }))],
+                journal.to_vec(),
+            )
+        };
+        let image_id = fake_image_id();
+        let mut ctx = VerifierContext {
+            large_journal_bytes: 4,
+            ..dev_mode_ctx()
+        };
+
+        let warnings = receipt(ExitCode::Halted(0), b"abcd")
+            .verify_with_warnings(&ctx, image_id)
+            .unwrap();
+        assert!(warnings.is_empty());
+
+        ctx.deprecated_hashfns.insert("none".into());
+        let warnings = receipt(ExitCode::Paused(3), b"abcde")
+            .verify_with_warnings(&ctx, image_id)
+            .unwrap();
+        assert_eq!(
+            warnings,
+            [
+                VerifyWarning::DeprecatedHashFn {
+                    segment: 0,
+                    hashfn: "none".into()
+                },
+                VerifyWarning::LargeJournal(5),
+                VerifyWarning::NonzeroExit(3),
+            ]
+        );
+
+        // Hard failures still fail.
+        assert_eq!(
+            receipt(ExitCode::SystemSplit, b"")
+                .verify_with_warnings(&ctx, image_id)
+                .unwrap_err(),
+            VerificationError::UnexpectedExitCode
+        );
+    }
+
+    #[test]
+    fn incremental_verifier() {
+        let state = |pc| SystemState {
+            pc,
+            merkle_root: Digest::default(),
+        };
+        let segment = |pre, post, exit_code| -> Box<dyn Receipt> {
+            Box::new(FakeReceipt::new(ReceiptMetadata {
+                pre: state(pre),
+                post: state(post),
+                exit_code,
+                input: Digest::default(),
+                output: JournalHash::Sha256.digest(b"journal"),
+            }))
+        };
+        let image_id = fake_image_id();
+        let ctx = dev_mode_ctx();
+
+        let mut verifier = IncrementalVerifier::with_context(ctx.clone(), image_id);
+        verifier
+            .push(segment(0x1000, 0x2000, ExitCode::SystemSplit))
+            .unwrap();
+        // A segment that doesn't continue from the last is caught immediately,
+        // and the right one can still follow.
+        assert_eq!(
+            verifier
+                .push(segment(0x3000, 0x4000, ExitCode::Halted(0)))
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(verifier.cursor().next_index, 1);
+        verifier
+            .push(segment(0x2000, 0x3000, ExitCode::Halted(0)))
+            .unwrap();
+        assert_eq!(verifier.cursor().next_index, 2);
+        verifier.clone().finish(b"journal").unwrap();
+        assert_eq!(
+            verifier.clone().finish(b"other").unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+        assert_eq!(
+            verifier
+                .push(segment(0x3000, 0x4000, ExitCode::Halted(0)))
+                .unwrap_err(),
+            VerificationError::UnexpectedExitCode
+        );
+
+        let mut verifier = IncrementalVerifier::with_context(ctx.clone(), image_id);
+        verifier
+            .push(segment(0x1000, 0x2000, ExitCode::SystemSplit))
+            .unwrap();
+        assert_eq!(
+            verifier.finish(b"journal").unwrap_err(),
+            VerificationError::UnexpectedExitCode
+        );
+        assert_eq!(
+            IncrementalVerifier::with_context(ctx, image_id)
+                .finish(b"journal")
+                .unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(
+            IncrementalVerifier::new(image_id)
+                .push(segment(0x1000, 0x2000, ExitCode::Halted(0)))
+                .unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+    }
+
+    #[test]
+    fn canonicalize() {
+        let segment = |index: u32| -> Box<dyn Receipt> {
+            Box::new(SegmentReceipt::new(vec![index; 4], index, "sha-256").unwrap())
+        };
+        let receipt =
+            |segments: Vec<Box<dyn Receipt>>| SessionReceipt::new(segments, b"journal".to_vec());
+
+        let mut a = receipt(vec![segment(2), segment(0), segment(1)]);
+        let mut b = receipt(vec![segment(1), segment(2), segment(0)]);
+        assert_ne!(a, b);
+        a.canonicalize();
+        b.canonicalize();
+        assert_eq!(a, b);
+        assert_eq!(a, receipt(vec![segment(0), segment(1), segment(2)]));
+
+        // Segments without an index keep their order, ahead of the rest.
+        let fake = |pc| -> Box<dyn Receipt> {
+            let state = SystemState {
+                pc,
+                merkle_root: Digest::default(),
+            };
+            Box::new(FakeReceipt::new(ReceiptMetadata {
+                pre: state.clone(),
+                post: state,
+                exit_code: ExitCode::Halted(0),
+                input: Digest::default(),
+                output: Digest::default(),
+            }))
+        };
+        let mut mixed = receipt(vec![segment(1), fake(2), segment(0), fake(1)]);
+        mixed.canonicalize();
+        assert_eq!(
+            mixed,
+            receipt(vec![fake(2), fake(1), segment(0), segment(1)])
+        );
+
+        #[cfg(feature = "zstd")]
+        {
+            use super::CompressedSegmentReceipt;
+
+            let compressed = |index: u32| -> Box<dyn Receipt> {
+                let segment = SegmentReceipt::new(vec![index; 4], index, "sha-256").unwrap();
+                Box::new(CompressedSegmentReceipt::compress(&segment, 0).unwrap())
+            };
+            let mut c = receipt(vec![compressed(1), segment(2), compressed(0)]);
+            assert_ne!(c, a);
+            c.canonicalize();
+            assert_eq!(c, a);
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_seal_too_large() {
+        use super::{CompressedSegmentReceipt, MAX_SEAL_BYTES};
+
+        let segment = SegmentReceipt::new(vec![0; 4], 0, "sha-256").unwrap();
+        let compressed = CompressedSegmentReceipt::compress(&segment, 0).unwrap();
+        assert_eq!(compressed.get_seal().unwrap(), &segment.seal[..]);
+        assert_eq!(compressed.verify_cost_estimate(), 4);
+
+        let mut bomb = CompressedSegmentReceipt::compress(&segment, 0).unwrap();
+        bomb.compressed_seal = zstd::encode_all(&vec![0; MAX_SEAL_BYTES + 4][..], 0).unwrap();
+        assert!(bomb.compressed_seal.len() < 4096);
+        assert_eq!(bomb.get_seal(), Err(VerificationError::ReceiptTooLarge));
+        assert_eq!(bomb.get_metadata(), Err(VerificationError::ReceiptTooLarge));
+        assert_eq!(bomb.verify_cost_estimate(), u64::MAX);
+
+        let mut malformed = CompressedSegmentReceipt::compress(&segment, 0).unwrap();
+        malformed.compressed_seal = b"not zstd".to_vec();
+        assert_eq!(
+            malformed.get_seal(),
+            Err(VerificationError::ReceiptFormatError)
+        );
+        assert!(SessionReceipt::new(vec![Box::new(malformed)], vec![])
+            .summary_string()
+            .contains("seal: <unknown>"));
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn scratch_is_wiped() {
+        use super::{compress_image_id, compute_image_id, take_digest};
+
+        let mut bytes = [0xa5; 32];
+        assert_eq!(take_digest(&mut bytes), Digest::from([0xa5; 32]));
+        assert_eq!(bytes, [0; 32]);
+
+        let merkle_root = Digest::from([1, 2, 3, 4, 5, 6, 7, 8]);
+        let mut block2 = Digest::from([0x1000, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            compress_image_id(&merkle_root, &mut block2),
+            compute_image_id(&merkle_root, 0x1000)
+        );
+        assert_eq!(block2, Digest::default());
+    }
+
+    #[test]
+    fn verify_cached() {
+        use super::VerificationCache;
+
+        let receipt = testutils::fake_receipt(b"journal");
+        let image_id = fake_image_id();
+        let dev_ctx = dev_mode_ctx();
+        let mut cache = VerificationCache::new(4);
+        receipt
+            .verify_cached(&dev_ctx, image_id, &mut cache)
+            .unwrap();
+        receipt
+            .verify_cached(&dev_ctx.clone(), image_id, &mut cache)
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+
+        // A stricter context doesn't reuse the result of a laxer one.
+        let ctx = VerifierContext::default();
+        for _ in 0..2 {
+            assert_eq!(
+                receipt
+                    .verify_cached(&ctx, image_id, &mut cache)
+                    .unwrap_err(),
+                VerificationError::DevModeDisabled
+            );
+        }
+        assert_eq!(cache.len(), 1);
+        let ctx = VerifierContext {
+            check_journal: false,
+            ..dev_ctx.clone()
+        };
+        receipt.verify_cached(&ctx, image_id, &mut cache).unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // Contexts are compared by value, so a separately built context with
+        // the same settings reuses the result, unless it has another id.
+        receipt
+            .verify_cached(&dev_mode_ctx(), image_id, &mut cache)
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+        let ctx = VerifierContext {
+            cache_id: 1,
+            ..dev_ctx.clone()
+        };
+        receipt.verify_cached(&ctx, image_id, &mut cache).unwrap();
+        assert_eq!(cache.len(), 3);
+
+        // The least recently used result is evicted once the cache is full.
+        let mut cache = VerificationCache::new(2);
+        receipt
+            .verify_cached(&dev_ctx, image_id, &mut cache)
+            .unwrap();
+        let unchecked = VerifierContext {
+            check_journal: false,
+            ..dev_ctx.clone()
+        };
+        receipt
+            .verify_cached(&unchecked, image_id, &mut cache)
+            .unwrap();
+        receipt
+            .verify_cached(&dev_ctx, image_id, &mut cache)
+            .unwrap();
+        let ctx = VerifierContext {
+            cache_id: 1,
+            ..dev_ctx.clone()
+        };
+        receipt.verify_cached(&ctx, image_id, &mut cache).unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(&(
+            receipt.fingerprint().unwrap(),
+            image_id,
+            dev_ctx.cache_digest()
+        )));
+        assert!(!cache.contains(&(
+            receipt.fingerprint().unwrap(),
+            image_id,
+            unchecked.cache_digest()
+        )));
+
+        let mut cache = VerificationCache::new(0);
+        receipt
+            .verify_cached(&dev_ctx, image_id, &mut cache)
+            .unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn initial_merkle_root() {
+        let state = |pc, merkle_root| SystemState { pc, merkle_root };
+        let segment = |pre, post| -> Box<dyn Receipt> {
+            Box::new(FakeReceipt::new(ReceiptMetadata {
+                pre,
+                post,
+                exit_code: ExitCode::SystemSplit,
+                input: Digest::default(),
+                output: Digest::default(),
+            }))
+        };
+        let root = Digest::from([1; 8]);
+        let receipt = SessionReceipt::new(
+            vec![
+                segment(state(0x1000, root), state(0x2000, Digest::from([2; 8]))),
+                segment(state(0x2000, Digest::from([2; 8])), state(0x3000, root)),
+            ],
+            Vec::new(),
+        );
+        assert_eq!(receipt.initial_merkle_root().unwrap(), root);
+        assert_eq!(
+            receipt.image_id_chain().unwrap()[0],
+            super::compute_image_id(&root, 0x1000)
+        );
+        assert_eq!(
+            SessionReceipt::new(Vec::new(), Vec::new())
+                .initial_merkle_root()
+                .unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        let receipt = |indices: &[u32]| {
+            let segments = indices
+                .iter()
+                .map(|index| {
+                    Box::new(SegmentReceipt::new(Vec::new(), *index, "sha-256").unwrap())
+                        as Box<dyn Receipt>
+                })
+                .collect();
+            SessionReceipt::new(segments, Vec::new())
+        };
+        let indices = |receipt: &SessionReceipt| -> Vec<u32> {
+            receipt
+                .segments
+                .iter()
+                .map(|segment| super::segment_index(segment.as_ref()).unwrap())
+                .collect()
+        };
+
+        let mut unordered = receipt(&[2, 0, 3, 1]);
+        unordered.normalize().unwrap();
+        assert_eq!(indices(&unordered), [0, 1, 2, 3]);
+
+        let mut gap = receipt(&[3, 0, 1]);
+        assert_eq!(
+            gap.normalize().unwrap_err(),
+            VerificationError::MissingSegment(2)
+        );
+        assert_eq!(indices(&gap), [0, 1, 3]);
+
+        assert_eq!(
+            receipt(&[1, 2]).normalize().unwrap_err(),
+            VerificationError::MissingSegment(0)
+        );
+        assert_eq!(
+            receipt(&[1, 0, 1, 2]).normalize().unwrap_err(),
+            VerificationError::DuplicateSegment(1)
+        );
+
+        let mut fake = receipt(&[1, 0]);
+        fake.segments
+            .push(Box::new(FakeReceipt::new(ReceiptMetadata {
+                pre: SystemState {
+                    pc: 0,
+                    merkle_root: Digest::default(),
+                },
+                post: SystemState {
+                    pc: 0,
+                    merkle_root: Digest::default(),
+                },
+                exit_code: ExitCode::Halted(0),
+                input: Digest::default(),
+                output: Digest::default(),
+            })));
+        assert_eq!(
+            fake.normalize().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(fake.segments.len(), 3);
+    }
+
+    #[test]
+    fn is_unconditional() {
+        let receipt = SessionReceipt::new(
+            vec![Box::new(
+                SegmentReceipt::new(Vec::new(), 0, "sha-256").unwrap(),
+            )],
+            Vec::new(),
+        );
+        assert!(receipt.is_unconditional().unwrap());
+    }
+
+    #[test]
+    fn verify_cost_estimate() {
+        let segment = |len| -> Box<dyn Receipt> {
+            Box::new(SegmentReceipt::new(vec![0; len], 0, "sha-256").unwrap())
+        };
+        assert!(segment(100).verify_cost_estimate() < segment(200).verify_cost_estimate());
+        let receipt = SessionReceipt::new(vec![segment(100), segment(200)], Vec::new());
+        assert_eq!(
+            receipt.verify_cost_estimate(),
+            segment(100).verify_cost_estimate() + segment(200).verify_cost_estimate()
+        );
+    }
+
+    #[test]
+    fn verify_chain() {
+        let receipt = |pc, input, journal: &[u8]| {
+            let pre = SystemState {
+                pc,
+                merkle_root: Digest::default(),
+            };
+            let image_id = pre.compute_image_id();
+            let metadata = ReceiptMetadata {
+                pre,
+                post: SystemState {
+                    pc: pc + 0x1000,
+                    merkle_root: Digest::default(),
+                },
+                exit_code: ExitCode::Halted(0),
+                input,
+                output: JournalHash::Sha256.digest(journal),
+            };
+            let segments: Vec<Box<dyn Receipt>> = vec![Box::new(FakeReceipt::new(metadata))];
+            (SessionReceipt::new(segments, journal.to_vec()), image_id)
+        };
+        let ctx = dev_mode_ctx();
+        let (a, image_id_a) = receipt(0x1000, Digest::default(), b"a");
+        let (b, image_id_b) = receipt(0x2000, JournalHash::Sha256.digest(b"a"), b"b");
+        super::verify_chain_with_context(&ctx, &a, &b, image_id_a, image_id_b).unwrap();
+        assert_eq!(
+            super::verify_chain_with_context(&ctx, &b, &a, image_id_b, image_id_a).unwrap_err(),
+            VerificationError::ChainMismatch
+        );
+        assert_eq!(
+            super::verify_chain_with_context(&ctx, &a, &b, image_id_b, image_id_b).unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(
+            super::verify_chain(&a, &b, image_id_a, image_id_b).unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+    }
+
+    #[test]
+    fn receipt_hashfn() {
+        let receipt: Box<dyn Receipt> = Box::new(SegmentReceipt {
+            seal: Default::default(),
+            index: 0,
+            hashfn: "sha-256".into(),
+            layout_version: LAYOUT_VERSION,
+        });
+        assert_eq!(receipt.hashfn(), "sha-256");
+    }
+
+    #[test]
+    fn layout_version() {
+        // A receipt serialized before the layout version was recorded.
+        let receipt: SegmentReceipt =
+            serde_json::from_str(r#"{"seal":[1,2,3],"index":4,"hashfn":"sha-256"}"#).unwrap();
+        assert_eq!(receipt.layout_version, LAYOUT_VERSION);
+
+        // The same receipt as a segment of a session receipt, which is a map of
+        // its type and fields in the word-oriented format.
+        const V0_SEGMENT: [u32; 24] = [
+            0x00000004, 0x00000004, 0x65707974, 0x0000000e, 0x6d676553, 0x52746e65, 0x69656365,
+            0x00007470, 0x00000004, 0x6c616573, 0x00000003, 0x00000001, 0x00000002, 0x00000003,
+            0x00000005, 0x65646e69, 0x00000078, 0x00000004, 0x00000006, 0x68736168, 0x00006e66,
+            0x00000007, 0x2d616873, 0x00363532,
+        ];
+        let segment: Box<dyn Receipt> = crate::serde::from_slice(&V0_SEGMENT).unwrap();
+        assert_eq!(
+            segment.as_any().downcast_ref::<SegmentReceipt>(),
+            Some(&receipt)
+        );
+
+        let receipt = SegmentReceipt {
+            layout_version: LAYOUT_VERSION + 1,
+            ..receipt
+        };
+        assert_eq!(
+            receipt.get_metadata().unwrap_err(),
+            VerificationError::UnknownLayoutVersion(LAYOUT_VERSION + 1)
+        );
+        assert_eq!(
+            receipt.decode_exit_code().unwrap_err(),
+            VerificationError::UnknownLayoutVersion(LAYOUT_VERSION + 1)
+        );
+    }
+
+    #[test]
+    fn session_receipt_v0() {
+        // A session receipt serialized by a release without layout versions or
+        // detached journals: one segment, as in `layout_version`, then the
+        // journal `b"ok"`.
+        const V0_SESSION: [u32; 28] = [
+            0x00000001, 0x00000004, 0x00000004, 0x65707974, 0x0000000e, 0x6d676553, 0x52746e65,
+            0x69656365, 0x00007470, 0x00000004, 0x6c616573, 0x00000003, 0x00000001, 0x00000002,
+            0x00000003, 0x00000005, 0x65646e69, 0x00000078, 0x00000004, 0x00000006, 0x68736168,
+            0x00006e66, 0x00000007, 0x2d616873, 0x00363532, 0x00000002, 0x0000006f, 0x0000006b,
+        ];
+        let expected = SessionReceipt::new(
+            vec![Box::new(SegmentReceipt {
+                seal: vec![1, 2, 3].into(),
+                index: 4,
+                hashfn: "sha-256".into(),
+                layout_version: LAYOUT_VERSION,
+            })],
+            b"ok".to_vec(),
+        );
+        let receipt: SessionReceipt = crate::serde::from_slice(&V0_SESSION).unwrap();
+        assert_eq!(receipt, expected);
+
+        #[cfg(feature = "std")]
+        {
+            let bytes: Vec<u8> = V0_SESSION.iter().flat_map(|w| w.to_le_bytes()).collect();
+            let receipt = SessionReceipt::from_reader(bytes.as_slice()).unwrap();
+            assert_eq!(receipt, expected);
+        }
+
+        #[cfg(feature = "mmap")]
+        {
+            let path = std::env::temp_dir().join(format!("receipt-v0-{}.bin", std::process::id()));
+            std::fs::write(&path, bytemuck::cast_slice::<u32, u8>(&V0_SESSION)).unwrap();
+            // SAFETY: nothing else writes to the temporary file.
+            let receipt = unsafe { SessionReceipt::from_mmap(&path) }.unwrap();
+            assert_eq!(receipt.segments.len(), 1);
+            assert_eq!(receipt.journal, b"ok");
+            assert_eq!(
+                receipt.segments[0].get_seal_bytes().unwrap(),
+                expected.segments[0].get_seal_bytes().unwrap()
+            );
+            drop(receipt);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn shareable_context() {
+        fn assert_shareable<T: Clone + Send + Sync>() {}
+        assert_shareable::<VerifierContext>();
+
+        let ctx = VerifierContext::default();
+        assert_eq!(ctx.clone().supported_hashfns(), ctx.supported_hashfns());
+    }
+
+    #[test]
+    fn deny_unknown_fields() {
+        let segment = r#"{"type":"SegmentReceipt","seal":[1,2,3],"index":0,"hashfn":"sha-256""#;
+        let receipt: Box<dyn Receipt> = serde_json::from_str(&format!("{segment}}}")).unwrap();
+        assert_eq!(
+            receipt
+                .as_any()
+                .downcast_ref::<SegmentReceipt>()
+                .unwrap()
+                .seal[..],
+            [1, 2, 3]
+        );
+        assert!(
+            serde_json::from_str::<Box<dyn Receipt>>(&format!("{segment},\"extra\":0}}")).is_err()
+        );
+
+        let session = format!(r#"{{"segments":[{segment}}}],"journal":[]"#);
+        serde_json::from_str::<SessionReceipt>(&format!("{session}}}")).unwrap();
+        assert!(
+            serde_json::from_str::<SessionReceipt>(&format!("{session},\"extra\":0}}")).is_err()
+        );
+    }
+
+    // The journal digest must agree with the standard SHA-256 that guests and
+    // external tools compute over the journal bytes.
+    #[test]
+    fn journal_hash_sha256() {
+        for len in [0, 1, 55, 56, 64, 65, 1000] {
+            let journal: Vec<u8> = (0..len).map(|x| x as u8).collect();
+            assert_eq!(
+                JournalHash::Sha256.digest(&journal),
+                Digest::try_from(Sha256::digest(&journal).as_slice()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn journal_hash_poseidon() {
+        use alloc::sync::Arc;
+
+        let journal = b"journal";
+        let receipt = SessionReceipt::new(
+            vec![Box::new(FakeReceipt::new(ReceiptMetadata {
+                output: JournalHash::Poseidon.digest(journal),
+                ..fake_metadata(journal)
+            }))],
+            journal.to_vec(),
+        );
+        let image_id = fake_image_id();
+
+        let ctx = VerifierContext {
+            commitment: Arc::new(JournalHash::Poseidon),
+            ..dev_mode_ctx()
+        };
+        receipt.verify_with_context(&ctx, image_id).unwrap();
+
+        assert_eq!(
+            receipt
+                .verify_with_context(&dev_mode_ctx(), image_id)
+                .unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+    }
+
+    #[test]
+    fn with_context() {
+        // A fake receipt only verifies in dev mode, so each method verifies
+        // with the context it is given rather than the default one.
+        let journal = b"journal";
+        let metadata = fake_metadata(journal);
+        let receipt = testutils::fake_receipt(journal);
+        let image_id = fake_image_id();
+        let ctx = dev_mode_ctx();
+
+        assert_eq!(
+            receipt.verify_hex(&hex::encode(image_id)).unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+        receipt
+            .verify_hex_with_context(&ctx, &hex::encode(image_id))
+            .unwrap();
+        receipt
+            .verify_with_allowed_hashfns_and_context(&ctx, image_id, &["none"])
+            .unwrap();
+        receipt
+            .verify_with_expected_segments_and_context(&ctx, image_id, 1)
+            .unwrap();
+        receipt
+            .verify_with_journal_and_context(&ctx, image_id, journal)
+            .unwrap();
+        receipt
+            .verify_against_commitment_with_context(
+                &ctx,
+                image_id,
+                compute_journal_commitment(&image_id, journal),
+            )
+            .unwrap();
+        receipt
+            .verify_with_expected_post_pc_and_context(&ctx, image_id, 0x1000)
+            .unwrap();
+        receipt
+            .verify_matches_metadata_with_context(&ctx, image_id, &metadata)
+            .unwrap();
+    }
+
+    #[test]
+    fn journal_digest() {
+        let journal = b"journal";
+        let digest = super::journal_digest(journal);
+        assert_eq!(
+            digest,
+            Digest::try_from(Sha256::digest(journal).as_slice()).unwrap()
+        );
+        assert_eq!(JournalHash::default().digest(journal), digest);
+
+        // It is the value that verification checks the journal against.
+        let receipt = |output| {
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(ReceiptMetadata {
+                    output,
+                    ..fake_metadata(journal)
+                }))],
+                journal.to_vec(),
+            )
+        };
+        let image_id = fake_image_id();
+        let ctx = dev_mode_ctx();
+        receipt(digest).verify_with_context(&ctx, image_id).unwrap();
+        assert_eq!(
+            receipt(super::journal_digest(b"other"))
+                .verify_with_context(&ctx, image_id)
+                .unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+    }
+
+    #[test]
+    fn commitment_verifier() {
+        use alloc::sync::Arc;
+
+        use super::CommitmentVerifier;
+
+        // A guest that commits to its journal by leaving its digest as the
+        // final Merkle root of its memory.
+        struct MerkleRootCommitment;
+
+        impl CommitmentVerifier for MerkleRootCommitment {
+            fn verify_commitment(
+                &self,
+                metadata: &ReceiptMetadata,
+                journal: &[u8],
+            ) -> Result<(), VerificationError> {
+                if metadata.post.merkle_root != super::journal_digest(journal) {
+                    return Err(VerificationError::JournalDigestMismatch);
+                }
+                Ok(())
+            }
+        }
+
+        let journal = b"journal";
+        let receipt = |post_root, output| {
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(ReceiptMetadata {
+                    pre: SystemState {
+                        pc: 0x1000,
+                        merkle_root: Digest::default(),
+                    },
+                    post: SystemState {
+                        pc: 0x2000,
+                        merkle_root: post_root,
+                    },
+                    exit_code: ExitCode::Halted(0),
+                    input: Digest::default(),
+                    output,
+                }))],
+                journal.to_vec(),
+            )
+        };
+        let image_id = fake_image_id();
+        let standard = dev_mode_ctx();
+        let custom = VerifierContext {
+            commitment: Arc::new(MerkleRootCommitment),
+            ..standard.clone()
+        };
+
+        let committed = receipt(super::journal_digest(journal), Digest::default());
+        committed.verify_with_context(&custom, image_id).unwrap();
+        assert_eq!(
+            committed
+                .verify_with_context(&standard, image_id)
+                .unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+
+        let standard_receipt = receipt(Digest::default(), super::journal_digest(journal));
+        standard_receipt
+            .verify_with_context(&standard, image_id)
+            .unwrap();
+        assert_eq!(
+            standard_receipt
+                .verify_with_context(&custom, image_id)
+                .unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+
+        // The commitment isn't checked when the journal isn't.
+        let unchecked = VerifierContext {
+            check_journal: false,
+            ..custom
+        };
+        standard_receipt
+            .verify_with_context(&unchecked, image_id)
+            .unwrap();
+    }
+
+    // The commitment preimage is documented byte for byte, so check it against
+    // an independent SHA-256.
+    #[test]
+    fn journal_commitment() {
+        let image_id = Digest::from([1, 2, 3, 4, 5, 6, 7, 8]);
+        let journal = b"journal";
+        let mut preimage = image_id.as_bytes().to_vec();
+        preimage.extend_from_slice(&Sha256::digest(journal));
+        assert_eq!(
+            compute_journal_commitment(&image_id, journal),
+            Digest::try_from(Sha256::digest(&preimage).as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn journal_output_commitment() {
+        let hash = JournalHash::Sha256;
+        let journal = b"journal".as_slice();
+        let nonzero = hash.digest(b"something else");
+
+        assert_eq!(hash.check(&[], &Digest::default()), Ok(()));
+        assert_eq!(
+            hash.check(&[], &nonzero),
+            Err(VerificationError::JournalDigestMismatch)
+        );
+        assert_eq!(hash.check(&[], &hash.digest(&[])), Ok(()));
+        assert_eq!(hash.check(journal, &hash.digest(journal)), Ok(()));
+        assert_eq!(
+            hash.check(journal, &nonzero),
+            Err(VerificationError::JournalDigestMismatch)
+        );
+        assert_eq!(
+            hash.check(journal, &Digest::default()),
+            Err(VerificationError::JournalDigestMismatch)
+        );
+    }
+
+    #[test]
+    fn metadata_diff() {
+        let metadata = ReceiptMetadata {
+            pre: SystemState {
+                pc: 0x1000,
+                merkle_root: Digest::from([1; 8]),
+            },
+            post: SystemState {
+                pc: 0x2000,
+                merkle_root: Digest::from([2; 8]),
+            },
+            exit_code: ExitCode::Halted(0),
+            input: Digest::default(),
+            output: Digest::from([3; 8]),
+        };
+        assert!(metadata.diff(&metadata).is_empty());
+
+        let mut other = metadata.clone();
+        other.post.pc += 4;
+        let diffs = metadata.diff(&other);
+        assert_eq!(diffs, [MetadataDiff::PostPc(0x2000, 0x2004)]);
+        assert_eq!(diffs[0].to_string(), "post.pc: 0x00002000 != 0x00002004");
+    }
+
+    #[test]
+    fn metadata_hash() {
+        use std::collections::HashSet;
+
+        let metadata = ReceiptMetadata {
+            pre: SystemState {
+                pc: 0x1000,
+                merkle_root: Digest::from([1; 8]),
+            },
+            post: SystemState {
+                pc: 0x2000,
+                merkle_root: Digest::from([2; 8]),
+            },
+            exit_code: ExitCode::Halted(0),
+            input: Digest::default(),
+            output: Digest::from([3; 8]),
+        };
+        let mut paused = metadata.clone();
+        paused.exit_code = ExitCode::Paused(0);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(metadata.clone()));
+        assert!(set.insert(paused.clone()));
+        assert!(!set.insert(metadata.clone()));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&paused));
+
+        let exit_codes: HashSet<ExitCode> = [metadata.exit_code, paused.exit_code].into();
+        assert_eq!(exit_codes.len(), 2);
+        let states: HashSet<SystemState> =
+            [metadata.pre, metadata.post.clone(), metadata.post].into();
+        assert_eq!(states.len(), 2);
+    }
+
+    #[test]
+    fn clone_box() {
+        let segment: Box<dyn Receipt> =
+            Box::new(SegmentReceipt::new(vec![1, 2, 3], 4, "sha-256").unwrap());
+        let fake: Box<dyn Receipt> = Box::new(FakeReceipt::new(fake_metadata(b"journal")));
+        assert!(segment.clone() == segment);
+        assert!(fake.clone() == fake);
+
+        let receipt = SessionReceipt::new(vec![fake], b"journal".to_vec());
+        let copy = receipt.clone();
+        assert_eq!(copy, receipt);
+        let ctx = dev_mode_ctx();
+        let image_id = fake_image_id();
+        copy.verify_with_context(&ctx, image_id).unwrap();
+        drop(receipt);
+        copy.verify_with_context(&ctx, image_id).unwrap();
+    }
+
+    #[test]
+    fn verify_with_expected_segments() {
+        let receipt = SessionReceipt::new(
+            vec![
+                Box::new(SegmentReceipt::new(Vec::new(), 0, "sha-256").unwrap()),
+                Box::new(SegmentReceipt::new(Vec::new(), 1, "sha-256").unwrap()),
+            ],
+            Vec::new(),
+        );
+        assert_eq!(
+            receipt
+                .verify_with_expected_segments(Digest::default(), 3)
+                .unwrap_err(),
+            VerificationError::UnexpectedSegmentCount {
+                expected: 3,
+                actual: 2
+            }
+        );
+        // With the right count, the (empty) seals are verified.
+        assert_eq!(
+            receipt
+                .verify_with_expected_segments(Digest::default(), 2)
+                .unwrap_err(),
+            VerificationError::Proof(ProofError::ReceiptFormatError)
+        );
+    }
+
+    #[test]
+    fn tampered_entry_pc() {
+        let merkle_root = Digest::from([1; 8]);
+        let receipt = |pc| {
+            let state = SystemState { pc, merkle_root };
+            SessionReceipt::new(
+                vec![Box::new(FakeReceipt::new(ReceiptMetadata {
+                    pre: state.clone(),
+                    post: state,
+                    exit_code: ExitCode::Halted(0),
+                    input: Digest::default(),
+                    output: Digest::default(),
+                }))],
+                Vec::new(),
+            )
+        };
+        let ctx = dev_mode_ctx();
+        let image_id = super::compute_image_id(&merkle_root, 0x1000);
+
+        receipt(0x1000).verify_with_context(&ctx, image_id).unwrap();
+        // The same memory image, entered somewhere other than the entry point.
+        assert_eq!(
+            receipt(0x1004)
+                .verify_with_context(&ctx, image_id)
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+    }
+
+    #[test]
+    fn verifier() {
+        use super::{LocalVerifier, Verifier};
+
+        // Rejects every receipt, standing in for a verifier in tests.
+        struct RejectAll;
+
+        impl Verifier for RejectAll {
+            fn verify(
+                &self,
+                _receipt: &SessionReceipt,
+                _image_id: Digest,
+            ) -> Result<(), VerificationError> {
+                Err(ProofError::InvalidProof.into())
+            }
         }
+
+        fn check(
+            verifier: &impl Verifier,
+            receipt: &SessionReceipt,
+            image_id: Digest,
+        ) -> Result<(), VerificationError> {
+            verifier.verify(receipt, image_id)
+        }
+
+        let receipt = testutils::fake_receipt(&[]);
+        let image_id = fake_image_id();
+        let local = LocalVerifier::new(dev_mode_ctx());
+
+        check(&local, &receipt, image_id).unwrap();
+        assert_eq!(
+            check(&local, &receipt, Digest::default()).unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(
+            check(&LocalVerifier::default(), &receipt, image_id).unwrap_err(),
+            receipt.verify(image_id).unwrap_err()
+        );
+        assert_eq!(
+            check(&RejectAll, &receipt, image_id).unwrap_err(),
+            VerificationError::Proof(ProofError::InvalidProof)
+        );
     }
 }
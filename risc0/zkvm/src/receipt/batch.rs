@@ -0,0 +1,213 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of many receipts with the seal checks deferred.
+
+use alloc::vec::Vec;
+
+use risc0_zkp::core::digest::Digest;
+
+use super::{SessionReceipt, VerificationError, VerifierContext};
+
+/// Verifies many [SessionReceipt]s, deferring the expensive checks of their
+/// seals until the whole batch has been collected.
+///
+/// Each receipt passed to [BatchVerifier::add] is checked structurally right
+/// away, as by [SessionReceipt::verify_sampled] with no segments sampled: its
+/// segments must stitch together from the image ID, and its journal must
+/// match. Receipts that fail these cheap checks are rejected without ever
+/// verifying a seal. [BatchVerifier::verify_all] then verifies the seals of
+/// the receipts that were accepted. Together, the two steps perform the same
+/// checks as [SessionReceipt::verify_with_context].
+///
+/// The proof system has no batched form of [risc0_zkp::verify::verify], so
+/// each seal is still verified on its own. With the `std` feature, the
+/// deferred seals are spread over one thread per available core, so that a
+/// batch is verified in about the time it takes a single core to verify its
+/// share; without it, they are verified one after another.
+#[derive(Clone)]
+pub struct BatchVerifier {
+    ctx: VerifierContext,
+    receipts: Vec<SessionReceipt>,
+}
+
+impl BatchVerifier {
+    /// Construct an empty batch, to be verified with `ctx`.
+    pub fn new(ctx: VerifierContext) -> Self {
+        Self {
+            ctx,
+            receipts: Vec::new(),
+        }
+    }
+
+    /// Returns the number of receipts in the batch.
+    pub fn len(&self) -> usize {
+        self.receipts.len()
+    }
+
+    /// Returns true if the batch holds no receipts.
+    pub fn is_empty(&self) -> bool {
+        self.receipts.is_empty()
+    }
+
+    /// Checks the structure of `receipt` against `image_id`, and adds it to
+    /// the batch if it passes.
+    ///
+    /// Returns the error, leaving the batch unchanged, if the receipt fails
+    /// the structural checks; the seals are verified by
+    /// [BatchVerifier::verify_all].
+    pub fn add(
+        &mut self,
+        receipt: SessionReceipt,
+        image_id: impl Into<Digest>,
+    ) -> Result<(), VerificationError> {
+        receipt.verify_sampled(&self.ctx, image_id, &[])?;
+        self.receipts.push(receipt);
+        Ok(())
+    }
+
+    /// Verifies the seals of every receipt in the batch, returning the result
+    /// for each receipt in the order they were added.
+    ///
+    /// A receipt whose result is `Ok` has been fully verified. A failure of one
+    /// receipt doesn't prevent the remaining receipts from being verified. A
+    /// panic while verifying a seal is propagated to the caller.
+    #[cfg(feature = "std")]
+    pub fn verify_all(&self) -> Vec<Result<(), VerificationError>> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let threads = std::thread::available_parallelism()
+            .map_or(1, usize::from)
+            .min(self.receipts.len());
+        // Each thread takes the next receipt as it finishes the last, so that
+        // receipts with many segments don't hold up the rest of the batch.
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<(usize, Result<(), VerificationError>)> =
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = (0..threads)
+                    .map(|_| {
+                        scope.spawn(|| {
+                            let mut results = Vec::new();
+                            loop {
+                                let index = next.fetch_add(1, Ordering::Relaxed);
+                                match self.receipts.get(index) {
+                                    Some(receipt) => {
+                                        results.push((index, self.verify_seals(receipt)))
+                                    }
+                                    None => break results,
+                                }
+                            }
+                        })
+                    })
+                    .collect();
+                workers
+                    .into_iter()
+                    .flat_map(|worker| {
+                        worker
+                            .join()
+                            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                    })
+                    .collect()
+            });
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Verifies the seals of every receipt in the batch, returning the result
+    /// for each receipt in the order they were added.
+    ///
+    /// A receipt whose result is `Ok` has been fully verified. A failure of one
+    /// receipt doesn't prevent the remaining receipts from being verified.
+    #[cfg(not(feature = "std"))]
+    pub fn verify_all(&self) -> Vec<Result<(), VerificationError>> {
+        self.receipts
+            .iter()
+            .map(|receipt| self.verify_seals(receipt))
+            .collect()
+    }
+
+    fn verify_seals(&self, receipt: &SessionReceipt) -> Result<(), VerificationError> {
+        receipt
+            .segments
+            .iter()
+            .try_for_each(|segment| segment.verify_with_context(&self.ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::{core::digest::Digest, verify::VerificationError as ProofError};
+
+    use super::BatchVerifier;
+    use crate::{
+        receipt::{SegmentReceipt, SessionReceipt, VerificationError, VerifierContext},
+        testutils::{dev_mode_ctx, fake_image_id, fake_receipt},
+    };
+
+    #[test]
+    fn batch_verifier() {
+        let image_id = fake_image_id();
+
+        // Structural failures are caught as receipts are added.
+        let mut batch = BatchVerifier::new(dev_mode_ctx());
+        batch.add(fake_receipt(b"journal"), image_id).unwrap();
+        let mut other = fake_receipt(b"journal");
+        other.journal = b"other".to_vec();
+        assert_eq!(
+            batch.add(other, image_id).unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+        assert_eq!(
+            batch
+                .add(fake_receipt(b"journal"), Digest::default())
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        batch.add(fake_receipt(b"journal"), image_id).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.verify_all(), [Ok(()), Ok(())]);
+
+        // Failures of the seals themselves are only caught by verify_all; here,
+        // that the fake receipt has no seal at all.
+        let mut batch = BatchVerifier::new(VerifierContext::default());
+        assert!(batch.is_empty());
+        batch.add(fake_receipt(b"journal"), image_id).unwrap();
+        assert_eq!(
+            batch.verify_all(),
+            [Err(VerificationError::DevModeDisabled)]
+        );
+
+        // The results are in the order the receipts were added, however the
+        // seals are spread across threads.
+        let mut batch = BatchVerifier::new(dev_mode_ctx());
+        let mut expected = Vec::new();
+        for i in 0..16 {
+            if i % 3 == 0 {
+                // Added directly, since a segment without a seal fails the
+                // structural checks too.
+                let segment = SegmentReceipt::new(Vec::new(), 0, "sha-256").unwrap();
+                batch
+                    .receipts
+                    .push(SessionReceipt::new(vec![Box::new(segment)], Vec::new()));
+                expected.push(Err(VerificationError::Proof(
+                    ProofError::ReceiptFormatError,
+                )));
+            } else {
+                batch.add(fake_receipt(b"journal"), image_id).unwrap();
+                expected.push(Ok(()));
+            }
+        }
+        assert_eq!(batch.verify_all(), expected);
+    }
+}
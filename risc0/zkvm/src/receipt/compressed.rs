@@ -0,0 +1,155 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Read;
+
+use dyn_partial_eq::DynPartialEq;
+use once_cell::sync::OnceCell;
+use risc0_zkp::core::digest::Digest;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Receipt, ReceiptMetadata, SegmentReceipt, VerificationError, VerifierContext, MAX_SEAL_BYTES,
+};
+
+/// A [SegmentReceipt] whose seal is stored zstd-compressed.
+///
+/// Seals compress well, so this is useful for receipts that are stored or
+/// transmitted in bulk. The seal is only decompressed when it is first needed
+/// by [Receipt::verify_with_context], [Receipt::get_metadata] or
+/// [Receipt::get_seal], after which the decompressed seal is kept alongside
+/// the compressed one. Only the compressed form is serialized.
+///
+/// A seal that decompresses to more than [MAX_SEAL_BYTES] is rejected with
+/// [VerificationError::ReceiptTooLarge], without decompressing the rest of it.
+#[derive(Debug, Deserialize, Serialize, DynPartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CompressedSegmentReceipt {
+    /// The zstd-compressed little-endian bytes of [SegmentReceipt::seal].
+    pub compressed_seal: Vec<u8>,
+
+    /// Segment index within the [crate::SessionReceipt]
+    pub index: u32,
+
+    /// Name of the hash function used to create this receipt.
+    pub hashfn: String,
+
+    /// The version of the circuit's output layout, see
+    /// [SegmentReceipt::layout_version].
+    #[serde(default = "super::default_layout_version")]
+    pub layout_version: u32,
+
+    #[serde(skip)]
+    seal: OnceCell<Vec<u32>>,
+}
+
+impl CompressedSegmentReceipt {
+    /// Compress the seal of `receipt` with the given zstd compression `level`.
+    ///
+    /// A `level` of 0 selects zstd's default.
+    pub fn compress(receipt: &SegmentReceipt, level: i32) -> std::io::Result<Self> {
+        let bytes: &[u8] = bytemuck::cast_slice(&receipt.seal[..]);
+        Ok(Self {
+            compressed_seal: zstd::encode_all(bytes, level)?,
+            index: receipt.index,
+            hashfn: receipt.hashfn.clone(),
+            layout_version: receipt.layout_version,
+            seal: OnceCell::new(),
+        })
+    }
+
+    /// Decompress this receipt into a [SegmentReceipt].
+    pub fn decompress(&self) -> Result<SegmentReceipt, VerificationError> {
+        Ok(SegmentReceipt {
+            seal: self.seal()?.to_vec().into(),
+            index: self.index,
+            hashfn: self.hashfn.clone(),
+            layout_version: self.layout_version,
+        })
+    }
+
+    fn seal(&self) -> Result<&[u32], VerificationError> {
+        self.seal
+            .get_or_try_init(|| {
+                let mut bytes = Vec::new();
+                zstd::Decoder::new(self.compressed_seal.as_slice())
+                    .and_then(|decoder| {
+                        decoder
+                            .take(MAX_SEAL_BYTES as u64 + 1)
+                            .read_to_end(&mut bytes)
+                    })
+                    .or(Err(VerificationError::ReceiptFormatError))?;
+                if bytes.len() > MAX_SEAL_BYTES {
+                    return Err(VerificationError::ReceiptTooLarge);
+                }
+                if bytes.len() % 4 != 0 {
+                    return Err(VerificationError::ReceiptFormatError);
+                }
+                Ok(bytes
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                    .collect())
+            })
+            .map(Vec::as_slice)
+    }
+}
+
+impl Clone for CompressedSegmentReceipt {
+    fn clone(&self) -> Self {
+        Self {
+            compressed_seal: self.compressed_seal.clone(),
+            index: self.index,
+            hashfn: self.hashfn.clone(),
+            layout_version: self.layout_version,
+            seal: OnceCell::new(),
+        }
+    }
+}
+
+// Whether the seal has been decompressed yet doesn't affect equality.
+impl PartialEq for CompressedSegmentReceipt {
+    fn eq(&self, other: &Self) -> bool {
+        self.compressed_seal == other.compressed_seal
+            && self.index == other.index
+            && self.hashfn == other.hashfn
+            && self.layout_version == other.layout_version
+    }
+}
+
+#[typetag::serde]
+impl Receipt for CompressedSegmentReceipt {
+    fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
+        self.verify_control_id(ctx).map(|_| ())
+    }
+
+    fn verify_control_id(&self, ctx: &VerifierContext) -> Result<Digest, VerificationError> {
+        super::verify_segment_seal(ctx, &self.hashfn, self.seal()?, self.layout_version)
+    }
+
+    fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
+        super::decode_segment_metadata(self.seal()?, self.layout_version)
+    }
+
+    fn get_seal(&self) -> Result<&[u32], VerificationError> {
+        self.seal()
+    }
+
+    fn hashfn(&self) -> &str {
+        &self.hashfn
+    }
+
+    fn clone_box(&self) -> Box<dyn Receipt> {
+        Box::new(self.clone())
+    }
+}
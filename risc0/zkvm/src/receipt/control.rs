@@ -0,0 +1,179 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle commitments to sets of control IDs.
+//!
+//! Rather than embedding the full list of control IDs it accepts, a verifier
+//! can be configured with the root of a Merkle tree over them, see
+//! [super::VerifierContext::control_root]. The tree is built with SHA-256
+//! over the control IDs in order, see [compute_control_root].
+
+use alloc::vec::Vec;
+
+use risc0_zkp::core::digest::Digest;
+use serde::{Deserialize, Serialize};
+
+use crate::sha::{self, Sha256};
+
+// The bytes prepended to a control ID to hash it into a leaf, and to a pair of
+// children to hash them into a node.
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+/// Compute the root of the Merkle tree over `control_ids`.
+///
+/// Each leaf is the SHA-256 hash of the byte `0x00` followed by a control ID,
+/// and each node above is the SHA-256 hash of the byte `0x01` followed by its
+/// two children. The distinct prefixes keep an interior node from being
+/// passed off as a control ID. The leaves are padded with zero digests up to
+/// the next power of two. An empty set of control IDs has the zero digest as
+/// its root.
+pub fn compute_control_root(control_ids: &[Digest]) -> Digest {
+    let mut level = padded_leaves(control_ids);
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied().unwrap_or_default()
+}
+
+/// A proof that a control ID is included in the Merkle tree committed to by a
+/// control root.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct ControlInclusionProof {
+    /// The position of the control ID among the leaves of the tree.
+    pub index: u32,
+
+    /// The sibling of each node on the path from the control ID to the root,
+    /// starting with the sibling of the leaf.
+    pub digests: Vec<Digest>,
+}
+
+impl ControlInclusionProof {
+    /// Construct the proof that `control_ids[index]` is included in the tree
+    /// over `control_ids`.
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    pub fn new(control_ids: &[Digest], index: usize) -> Option<Self> {
+        if index >= control_ids.len() {
+            return None;
+        }
+        let mut level = padded_leaves(control_ids);
+        let mut digests = Vec::new();
+        let mut pos = index;
+        while level.len() > 1 {
+            digests.push(level[pos ^ 1]);
+            level = next_level(&level);
+            pos /= 2;
+        }
+        Some(Self {
+            index: index as u32,
+            digests,
+        })
+    }
+
+    /// Compute the root of the tree that this proof places `control_id` in.
+    ///
+    /// The proof is valid for a given control root exactly when this returns
+    /// that root.
+    pub fn root(&self, control_id: &Digest) -> Digest {
+        let mut node = hash_leaf(control_id);
+        let mut pos = self.index;
+        for sibling in self.digests.iter() {
+            node = if pos % 2 == 0 {
+                hash_node(&node, sibling)
+            } else {
+                hash_node(sibling, &node)
+            };
+            pos /= 2;
+        }
+        node
+    }
+}
+
+fn hash_leaf(control_id: &Digest) -> Digest {
+    let mut bytes = Vec::with_capacity(1 + control_id.as_bytes().len());
+    bytes.push(LEAF_TAG);
+    bytes.extend_from_slice(control_id.as_bytes());
+    *sha::Impl::hash_bytes(&bytes)
+}
+
+fn hash_node(left: &Digest, right: &Digest) -> Digest {
+    let mut bytes = Vec::with_capacity(1 + 2 * left.as_bytes().len());
+    bytes.push(NODE_TAG);
+    bytes.extend_from_slice(left.as_bytes());
+    bytes.extend_from_slice(right.as_bytes());
+    *sha::Impl::hash_bytes(&bytes)
+}
+
+fn padded_leaves(control_ids: &[Digest]) -> Vec<Digest> {
+    let mut leaves: Vec<Digest> = control_ids.iter().map(hash_leaf).collect();
+    leaves.resize(control_ids.len().next_power_of_two(), Digest::default());
+    leaves
+}
+
+fn next_level(level: &[Digest]) -> Vec<Digest> {
+    level
+        .chunks_exact(2)
+        .map(|pair| hash_node(&pair[0], &pair[1]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::core::digest::Digest;
+
+    use super::{compute_control_root, hash_leaf, hash_node, ControlInclusionProof};
+
+    #[test]
+    fn inclusion_proofs() {
+        for len in 1..10u32 {
+            let ids: Vec<Digest> = (0..len).map(|x| Digest::from([x + 1; 8])).collect();
+            let root = compute_control_root(&ids);
+            for (idx, id) in ids.iter().enumerate() {
+                let proof = ControlInclusionProof::new(&ids, idx).unwrap();
+                assert_eq!(proof.root(id), root);
+                assert_ne!(proof.root(&Digest::default()), root);
+            }
+            assert!(ControlInclusionProof::new(&ids, ids.len()).is_none());
+        }
+    }
+
+    #[test]
+    fn interior_node_is_not_a_leaf() {
+        let ids: Vec<Digest> = (0..4u32).map(|x| Digest::from([x + 1; 8])).collect();
+        let root = compute_control_root(&ids);
+        let left = hash_node(&hash_leaf(&ids[0]), &hash_leaf(&ids[1]));
+        let right = hash_node(&hash_leaf(&ids[2]), &hash_leaf(&ids[3]));
+        assert_eq!(hash_node(&left, &right), root);
+
+        // Claiming the left subtree as a control ID one level up must fail.
+        let proof = ControlInclusionProof {
+            index: 0,
+            digests: vec![right],
+        };
+        assert_ne!(proof.root(&left), root);
+
+        // The zero digest padding a leaf level isn't a provable control ID.
+        let ids = &ids[..3];
+        let root = compute_control_root(ids);
+        let proof = ControlInclusionProof {
+            index: 3,
+            digests: vec![
+                hash_leaf(&ids[2]),
+                hash_node(&hash_leaf(&ids[0]), &hash_leaf(&ids[1])),
+            ],
+        };
+        assert_ne!(proof.root(&Digest::default()), root);
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts whose journal is stored separately.
+
+use alloc::{boxed::Box, vec::Vec};
+
+use risc0_zkp::core::digest::Digest;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    JournalHash, Receipt, SessionReceipt, VerificationError, VerifierContext, VerifyCursor,
+};
+
+/// A [SessionReceipt] whose journal has been replaced by its SHA-256 digest.
+///
+/// This lets the receipt be stored or sent without its journal, e.g. keyed by
+/// the digest, and is made by [SessionReceipt::detach_journal]. It is a type
+/// of its own, with a serialized form of its own, so that the serialized form
+/// of a [SessionReceipt] is unchanged.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DetachedSessionReceipt {
+    /// The constituent [Receipt]s, as in [SessionReceipt::segments].
+    pub segments: Vec<Box<dyn Receipt>>,
+
+    /// The SHA-256 digest of the detached journal.
+    pub journal_digest: Digest,
+}
+
+impl DetachedSessionReceipt {
+    /// Attaches `journal`, returning the complete [SessionReceipt].
+    ///
+    /// Returns [VerificationError::JournalDigestMismatch] if the digest of
+    /// `journal` doesn't match [DetachedSessionReceipt::journal_digest].
+    pub fn attach_journal(self, journal: Vec<u8>) -> Result<SessionReceipt, VerificationError> {
+        if JournalHash::Sha256.digest(&journal) != self.journal_digest {
+            return Err(VerificationError::JournalDigestMismatch);
+        }
+        Ok(SessionReceipt::new(self.segments, journal))
+    }
+
+    /// Verifies the integrity of this receipt without its journal.
+    ///
+    /// This is only possible if [VerifierContext::check_journal] isn't set,
+    /// since the journal can't be checked without it; otherwise
+    /// [VerificationError::JournalDetached] is returned. To check the journal,
+    /// use [DetachedSessionReceipt::verify_with_journal].
+    pub fn verify_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<(), VerificationError> {
+        if ctx.check_journal {
+            return Err(VerificationError::JournalDetached);
+        }
+        self.verify_with_journal_and_context(ctx, image_id, &[])
+    }
+
+    /// Verifies the integrity of this receipt against `journal`, as by
+    /// [SessionReceipt::verify_with_journal], without attaching it.
+    ///
+    /// The journal is checked against the output committed to by the final
+    /// segment, not against [DetachedSessionReceipt::journal_digest].
+    pub fn verify_with_journal(
+        &self,
+        image_id: impl Into<Digest>,
+        journal: &[u8],
+    ) -> Result<(), VerificationError> {
+        self.verify_with_journal_and_context(&VerifierContext::default(), image_id, journal)
+    }
+
+    /// Verifies the integrity of this receipt with the given context against
+    /// `journal`; see [DetachedSessionReceipt::verify_with_journal].
+    pub fn verify_with_journal_and_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        journal: &[u8],
+    ) -> Result<(), VerificationError> {
+        let mut cursor = VerifyCursor::new(image_id);
+        cursor.verify_continue(ctx, &self.segments)?;
+        cursor.verify_finish(ctx, journal)
+    }
+}
+
+impl SessionReceipt {
+    /// Detaches the journal from this receipt, returning the receipt without
+    /// it and the journal.
+    ///
+    /// The journal is replaced by its SHA-256 digest, so that the receipt can
+    /// be serialized without it and the journal stored elsewhere. The journal
+    /// can be re-attached with [DetachedSessionReceipt::attach_journal].
+    pub fn detach_journal(self) -> (DetachedSessionReceipt, Vec<u8>) {
+        let detached = DetachedSessionReceipt {
+            segments: self.segments,
+            journal_digest: JournalHash::Sha256.digest(&self.journal),
+        };
+        (detached, self.journal)
+    }
+}
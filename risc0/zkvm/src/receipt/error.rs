@@ -0,0 +1,208 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Errors from verifying receipts.
+
+use alloc::string::String;
+use core::fmt;
+
+use risc0_zkp::verify::VerificationError as ProofError;
+
+/// An error from verifying a receipt.
+///
+/// Failures of the proof itself are reported by the proof system, and wrapped
+/// in [VerificationError::Proof]; the other variants are failures of the
+/// checks the zkVM makes on what a receipt attests to.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum VerificationError {
+    /// The seal failed to verify.
+    Proof(ProofError),
+    /// The receipt is malformed, e.g. it has no segments.
+    ReceiptFormatError,
+    /// The receipt was not proven for the given image ID.
+    ImageVerificationError,
+    /// The journal doesn't match the digest committed to by the receipt.
+    JournalDigestMismatch,
+    /// A segment ended with an exit code that isn't allowed where it is.
+    UnexpectedExitCode,
+    /// The journal was detached and is needed to verify the receipt.
+    JournalDetached,
+    /// A segment doesn't start from the state the previous one ended in.
+    PreStateMismatch,
+    /// A segment doesn't end in the expected state.
+    PostStateMismatch,
+    /// The deadline passed before verification finished.
+    DeadlineExceeded,
+    /// An image ID couldn't be parsed.
+    MalformedImageId,
+    /// The receipt doesn't provide the required security level.
+    InsufficientSecurity,
+    /// The metadata of the receipt doesn't match the expected metadata.
+    MetadataMismatch {
+        /// The fields that differ.
+        diff: String,
+    },
+    /// A hash function isn't one of the [crate::VerifierContext::suites].
+    InvalidHashSuite,
+    /// A segment was proven with a hash function that isn't allowed.
+    DisallowedHashFn(String),
+    /// A fake receipt was given to a verifier that isn't in dev mode.
+    DevModeDisabled,
+    /// A segment has a circuit layout version this crate can't decode.
+    UnknownLayoutVersion(u32),
+    /// The image ID and journal don't match the given commitment.
+    CommitmentMismatch,
+    /// A receipt's input doesn't match the output of the one before it.
+    ChainMismatch,
+    /// The segment with this index is missing.
+    MissingSegment(u32),
+    /// The segment with this index was given more than once.
+    DuplicateSegment(u32),
+    /// The segment with this index doesn't match its fingerprint.
+    SegmentFingerprintMismatch(u32),
+    /// The journal doesn't decode to the expected value.
+    JournalValueMismatch,
+    /// The prover's signature over the receipt is invalid.
+    InvalidSignature,
+    /// The receipt doesn't have the expected number of segments.
+    UnexpectedSegmentCount {
+        /// The number of segments expected.
+        expected: usize,
+        /// The number of segments in the receipt.
+        actual: usize,
+    },
+    /// A remote verifier rejected the receipt, for the given reason.
+    RemoteRejected(String),
+    /// A remote verifier couldn't be reached, for the given reason.
+    RemoteUnavailable(String),
+    /// A seal is larger than [crate::MAX_SEAL_BYTES].
+    ReceiptTooLarge,
+    /// The journal isn't in the set of journals committed to.
+    JournalNotInSet,
+    /// The image ID recorded in a seal is malformed.
+    MalformedSealImageId,
+    /// A program counter recorded in a seal is malformed.
+    MalformedPc,
+    /// The input digest recorded in a seal is malformed.
+    MalformedInput,
+    /// The output digest recorded in a seal is malformed.
+    MalformedOutput,
+    /// The exit code recorded in a seal is malformed.
+    MalformedExitCode,
+    /// A segment's layout version, given here, doesn't match the circuit it
+    /// is verified with.
+    LayoutVersionMismatch(u32),
+    /// The journal wasn't checked, and so can't be trusted.
+    JournalNotChecked,
+    /// Verification was cancelled before it finished.
+    Cancelled,
+}
+
+impl From<ProofError> for VerificationError {
+    fn from(err: ProofError) -> Self {
+        Self::Proof(err)
+    }
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerificationError::Proof(err) => write!(f, "{err}"),
+            VerificationError::ReceiptFormatError => write!(f, "invalid receipt format"),
+            VerificationError::ImageVerificationError => write!(f, "image_id mismatch"),
+            VerificationError::JournalDigestMismatch => {
+                write!(f, "Journal digest mismatch detected")
+            }
+            VerificationError::UnexpectedExitCode => write!(f, "Unexpected exit_code"),
+            VerificationError::JournalDetached => {
+                write!(
+                    f,
+                    "Journal is detached and must be attached before verifying"
+                )
+            }
+            VerificationError::PreStateMismatch => write!(f, "pre-state mismatch"),
+            VerificationError::PostStateMismatch => write!(f, "post-state mismatch"),
+            VerificationError::DeadlineExceeded => write!(f, "verification deadline exceeded"),
+            VerificationError::MalformedImageId => write!(f, "malformed image_id"),
+            VerificationError::InsufficientSecurity => write!(f, "insufficient security level"),
+            VerificationError::InvalidHashSuite => write!(f, "Invalid hash suite"),
+            VerificationError::MetadataMismatch { diff } => write!(f, "metadata mismatch: {diff}"),
+            VerificationError::DisallowedHashFn(hashfn) => {
+                write!(f, "hash function {hashfn} is not allowed")
+            }
+            VerificationError::DevModeDisabled => {
+                write!(f, "fake receipts are only accepted in dev mode")
+            }
+            VerificationError::UnknownLayoutVersion(version) => {
+                write!(f, "unknown circuit layout version {version}")
+            }
+            VerificationError::CommitmentMismatch => {
+                write!(f, "image ID and journal do not match the commitment")
+            }
+            VerificationError::ChainMismatch => {
+                write!(
+                    f,
+                    "receipt input does not match the previous receipt's output"
+                )
+            }
+            VerificationError::MissingSegment(index) => write!(f, "segment {index} is missing"),
+            VerificationError::DuplicateSegment(index) => {
+                write!(f, "segment {index} appears more than once")
+            }
+            VerificationError::SegmentFingerprintMismatch(index) => {
+                write!(f, "segment {index} does not match its fingerprint")
+            }
+            VerificationError::JournalValueMismatch => {
+                write!(f, "journal does not decode to the expected value")
+            }
+            VerificationError::InvalidSignature => write!(f, "invalid prover signature"),
+            VerificationError::UnexpectedSegmentCount { expected, actual } => {
+                write!(f, "expected {expected} segments, found {actual}")
+            }
+            VerificationError::RemoteRejected(reason) => {
+                write!(f, "rejected by remote verifier: {reason}")
+            }
+            VerificationError::RemoteUnavailable(reason) => {
+                write!(f, "remote verifier unavailable: {reason}")
+            }
+            VerificationError::ReceiptTooLarge => write!(f, "receipt too large"),
+            VerificationError::JournalNotInSet => {
+                write!(f, "journal is not in the committed set")
+            }
+            VerificationError::MalformedSealImageId => write!(f, "malformed image_id in seal"),
+            VerificationError::MalformedPc => write!(f, "malformed pc"),
+            VerificationError::MalformedInput => write!(f, "malformed input digest"),
+            VerificationError::MalformedOutput => write!(f, "malformed output digest"),
+            VerificationError::MalformedExitCode => write!(f, "malformed exit code"),
+            VerificationError::LayoutVersionMismatch(version) => {
+                write!(f, "layout version {version} does not match the circuit")
+            }
+            VerificationError::JournalNotChecked => {
+                write!(f, "the journal must be checked to be trusted")
+            }
+            VerificationError::Cancelled => write!(f, "verification was cancelled"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerificationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerificationError::Proof(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use alloc::boxed::Box;
+
+use dyn_partial_eq::DynPartialEq;
+use serde::{Deserialize, Serialize};
+
+use super::{Receipt, ReceiptMetadata, VerificationError, VerifierContext};
+
+/// A placeholder receipt for use in dev mode, which carries no proof.
+///
+/// **A FakeReceipt proves nothing.** It exists so that development builds
+/// which skip proving can still produce receipts of the right shape, and so
+/// that verification rejects them explicitly rather than with an obscure
+/// error. Verifying one fails with [VerificationError::DevModeDisabled] unless
+/// [VerifierContext::dev_mode] is set, in which case it passes regardless of
+/// its contents.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, DynPartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FakeReceipt {
+    /// The metadata that this receipt claims, without any proof.
+    pub metadata: ReceiptMetadata,
+}
+
+impl FakeReceipt {
+    /// Construct a [FakeReceipt] claiming the given `metadata`.
+    pub fn new(metadata: ReceiptMetadata) -> Self {
+        Self { metadata }
+    }
+}
+
+#[typetag::serde]
+impl Receipt for FakeReceipt {
+    fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
+        if !ctx.dev_mode {
+            return Err(VerificationError::DevModeDisabled);
+        }
+        log::warn!("accepting a FakeReceipt without verification, since dev mode is enabled");
+        Ok(())
+    }
+
+    fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
+        Ok(self.metadata.clone())
+    }
+
+    /// A FakeReceipt has an empty seal.
+    fn get_seal(&self) -> Result<&[u32], VerificationError> {
+        Ok(&[])
+    }
+
+    /// A FakeReceipt isn't proven with any hash function, so this returns
+    /// `"none"`.
+    fn hashfn(&self) -> &str {
+        "none"
+    }
+
+    fn clone_box(&self) -> Box<dyn Receipt> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle commitments to sets of acceptable journals.
+
+use alloc::vec::Vec;
+
+use risc0_zkp::core::digest::Digest;
+
+use super::{journal_digest, SessionReceipt, VerificationError, VerifierContext};
+use crate::sha::{self, Sha256};
+
+/// Compute the root of the Merkle tree over `journals`.
+///
+/// A verifier that accepts any one of a set of results can hold this root,
+/// and check a receipt's journal against it with
+/// [SessionReceipt::verify_journal_membership]. The tree is built as follows,
+/// so that producers can build matching roots and proofs without this crate:
+///
+/// - Each leaf is the SHA-256 digest of a journal, as computed by
+///   [journal_digest]. The leaves are padded with zero digests up to the next
+///   power of two.
+/// - Each node is [crate::sha::Sha256::hash_pair] over its two children, with
+///   the child whose [Digest::as_bytes] compare lesser first. Sorting the
+///   children means a proof needs no index: it is just the sibling of each node
+///   on the path from the leaf to the root, starting with the sibling of the
+///   leaf, as built by [journal_inclusion_proof].
+///
+/// A set of one journal has that journal's digest as its root, and an empty
+/// proof. An empty set of journals has the zero digest as its root.
+pub fn compute_journal_root<J: AsRef<[u8]>>(journals: &[J]) -> Digest {
+    let mut level = padded_leaves(journals);
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level.first().copied().unwrap_or_default()
+}
+
+/// Construct the proof that `journals[index]` is included in the tree over
+/// `journals`.
+///
+/// Returns `None` if `index` is out of bounds.
+pub fn journal_inclusion_proof<J: AsRef<[u8]>>(
+    journals: &[J],
+    index: usize,
+) -> Option<Vec<Digest>> {
+    if index >= journals.len() {
+        return None;
+    }
+    let mut level = padded_leaves(journals);
+    let mut proof = Vec::new();
+    let mut pos = index;
+    while level.len() > 1 {
+        proof.push(level[pos ^ 1]);
+        level = next_level(&level);
+        pos /= 2;
+    }
+    Some(proof)
+}
+
+impl SessionReceipt {
+    /// Verifies this receipt with [SessionReceipt::verify], then checks that
+    /// `proof` includes its journal in the set of journals committed to by
+    /// `merkle_root`; see [compute_journal_root] for how the tree is built.
+    ///
+    /// Returns [VerificationError::JournalNotInSet] if the proof doesn't lead
+    /// from the journal to `merkle_root`.
+    pub fn verify_journal_membership(
+        &self,
+        image_id: impl Into<Digest>,
+        merkle_root: Digest,
+        proof: &[Digest],
+    ) -> Result<(), VerificationError> {
+        self.verify_journal_membership_with_context(
+            &VerifierContext::default(),
+            image_id,
+            merkle_root,
+            proof,
+        )
+    }
+
+    /// Verifies this receipt with [SessionReceipt::verify_with_context], then
+    /// checks its journal against `merkle_root`; see
+    /// [SessionReceipt::verify_journal_membership].
+    ///
+    /// Returns [VerificationError::JournalNotChecked] if
+    /// [VerifierContext::check_journal] isn't set, since verification then
+    /// says nothing about the journal, and so nor would its membership.
+    pub fn verify_journal_membership_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        merkle_root: Digest,
+        proof: &[Digest],
+    ) -> Result<(), VerificationError> {
+        if !ctx.check_journal {
+            return Err(VerificationError::JournalNotChecked);
+        }
+        self.verify_with_context(ctx, image_id)?;
+        let root = proof
+            .iter()
+            .fold(journal_digest(&self.journal), |node, sibling| {
+                hash_children(&node, sibling)
+            });
+        if root != merkle_root {
+            return Err(VerificationError::JournalNotInSet);
+        }
+        Ok(())
+    }
+}
+
+fn hash_children(a: &Digest, b: &Digest) -> Digest {
+    if a.as_bytes() <= b.as_bytes() {
+        *sha::Impl::hash_pair(a, b)
+    } else {
+        *sha::Impl::hash_pair(b, a)
+    }
+}
+
+fn padded_leaves<J: AsRef<[u8]>>(journals: &[J]) -> Vec<Digest> {
+    let mut leaves: Vec<Digest> = journals
+        .iter()
+        .map(|journal| journal_digest(journal.as_ref()))
+        .collect();
+    leaves.resize(journals.len().next_power_of_two(), Digest::default());
+    leaves
+}
+
+fn next_level(level: &[Digest]) -> Vec<Digest> {
+    level
+        .chunks_exact(2)
+        .map(|pair| hash_children(&pair[0], &pair[1]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::core::digest::Digest;
+
+    use super::{compute_journal_root, journal_inclusion_proof};
+    use crate::{
+        receipt::{JournalHash, VerificationError, VerifierContext},
+        testutils::{dev_mode_ctx, fake_image_id, fake_receipt},
+    };
+
+    #[test]
+    fn journal_membership() {
+        let image_id = fake_image_id();
+        let ctx = dev_mode_ctx();
+
+        for len in 1..6u8 {
+            let journals: Vec<Vec<u8>> = (0..len).map(|x| vec![x; x as usize + 1]).collect();
+            let root = compute_journal_root(&journals);
+            for (idx, journal) in journals.iter().enumerate() {
+                let proof = journal_inclusion_proof(&journals, idx).unwrap();
+                fake_receipt(journal)
+                    .verify_journal_membership_with_context(&ctx, image_id, root, &proof)
+                    .unwrap();
+                assert_eq!(
+                    fake_receipt(b"other")
+                        .verify_journal_membership_with_context(&ctx, image_id, root, &proof)
+                        .unwrap_err(),
+                    VerificationError::JournalNotInSet
+                );
+            }
+            assert!(journal_inclusion_proof(&journals, journals.len()).is_none());
+        }
+        assert_eq!(
+            compute_journal_root(&[b"abc"]),
+            JournalHash::Sha256.digest(b"abc")
+        );
+        assert_eq!(compute_journal_root::<&[u8]>(&[]), Digest::default());
+
+        // The receipt itself must verify first.
+        let root = compute_journal_root(&[b"abc"]);
+        assert_eq!(
+            fake_receipt(b"abc")
+                .verify_journal_membership_with_context(&ctx, Digest::default(), root, &[])
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(
+            fake_receipt(b"abc")
+                .verify_journal_membership(image_id, root, &[])
+                .unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+
+        // A journal that verification doesn't check can't be trusted to be in
+        // the set, even if it is.
+        let mut journal = fake_receipt(b"abc");
+        journal.journal = b"other".to_vec();
+        let unchecked = VerifierContext {
+            check_journal: false,
+            ..ctx
+        };
+        assert_eq!(
+            journal
+                .verify_journal_membership_with_context(
+                    &unchecked,
+                    image_id,
+                    compute_journal_root(&[b"other"]),
+                    &[]
+                )
+                .unwrap_err(),
+            VerificationError::JournalNotChecked
+        );
+    }
+}
@@ -0,0 +1,440 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts whose segments are stored separately, by fingerprint.
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+
+use risc0_zkp::core::digest::Digest;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    serialized_digest, Receipt, ReceiptMetadata, SessionReceipt, VerificationError,
+    VerifierContext, VerifyCursor,
+};
+
+/// Returns a SHA-256 digest of the serialized form of a segment receipt.
+///
+/// This is the fingerprint by which a [LazySessionReceipt] refers to its
+/// segments. Like [SessionReceipt::fingerprint], it covers the type of the
+/// receipt as well as its contents, so the same segment stored as, e.g., a
+/// [super::SegmentReceipt] and a compressed receipt has two fingerprints.
+///
+/// Returns [VerificationError::ReceiptTooLarge] if the receipt is too large
+/// to serialize.
+pub fn segment_fingerprint(receipt: &dyn Receipt) -> Result<Digest, VerificationError> {
+    serialized_digest(receipt)
+}
+
+/// A [SessionReceipt] that refers to its segments by fingerprint rather than
+/// holding them.
+///
+/// This suits content-addressed storage, where segments shared by several
+/// receipts are stored only once. The segments are fetched as they are
+/// verified by [LazySessionReceipt::verify_with_loader].
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct LazySessionReceipt {
+    /// The [segment_fingerprint] of each segment, in order.
+    pub segments: Vec<Digest>,
+
+    /// The journal committed to by the guest.
+    pub journal: Vec<u8>,
+}
+
+impl LazySessionReceipt {
+    /// Construct a [LazySessionReceipt] from the fingerprints of its segments
+    /// and its journal.
+    pub fn new(segments: Vec<Digest>, journal: Vec<u8>) -> Self {
+        Self { segments, journal }
+    }
+
+    /// Verifies the integrity of this receipt, fetching each segment with
+    /// `loader`.
+    ///
+    /// This performs the same checks as [SessionReceipt::verify]. `loader` is
+    /// called with the fingerprint of each segment in turn, just before that
+    /// segment is verified, so at most one segment is held at a time. Nothing
+    /// about the loaded segment is trusted until its [segment_fingerprint] has
+    /// been checked against the one in this receipt; a mismatch returns
+    /// [VerificationError::SegmentFingerprintMismatch] with the index of the
+    /// segment.
+    ///
+    /// An error returned by `loader` is passed through unchanged, and any
+    /// [VerificationError] is converted into the loader's error type, so that
+    /// e.g. an [anyhow::Error] can be used for both.
+    pub fn verify_with_loader<E: From<VerificationError>>(
+        &self,
+        image_id: impl Into<Digest>,
+        loader: impl FnMut(Digest) -> Result<Box<dyn Receipt>, E>,
+    ) -> Result<(), E> {
+        self.verify_with_loader_and_context(&VerifierContext::default(), image_id, loader)
+    }
+
+    /// Verifies the integrity of this receipt with the given context, fetching
+    /// each segment with `loader`; see
+    /// [LazySessionReceipt::verify_with_loader].
+    pub fn verify_with_loader_and_context<E: From<VerificationError>>(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        mut loader: impl FnMut(Digest) -> Result<Box<dyn Receipt>, E>,
+    ) -> Result<(), E> {
+        let mut cursor = VerifyCursor::new(image_id);
+        for (index, fingerprint) in self.segments.iter().enumerate() {
+            let receipt = loader(*fingerprint)?;
+            if segment_fingerprint(receipt.as_ref())? != *fingerprint {
+                return Err(VerificationError::SegmentFingerprintMismatch(index as u32).into());
+            }
+            cursor.verify_segment(ctx, receipt.as_ref())?;
+        }
+        cursor.verify_finish(ctx, &self.journal).map_err(E::from)
+    }
+
+    /// Verifies the integrity of this receipt like
+    /// [LazySessionReceipt::verify_with_loader_and_context], skipping the
+    /// segments that `cache` has already verified.
+    ///
+    /// Segments found in `cache` are neither loaded nor verified again; their
+    /// recorded metadata is checked against the rest of the session as usual.
+    /// Each other segment is loaded and verified, and recorded in `cache` once
+    /// its seal has verified, even if the session as a whole later fails. This
+    /// lets sessions that share a common prefix of segments verify the shared
+    /// segments only once.
+    pub fn verify_with_loader_cached<E: From<VerificationError>>(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        mut loader: impl FnMut(Digest) -> Result<Box<dyn Receipt>, E>,
+        cache: &mut SegmentCache,
+    ) -> Result<(), E> {
+        let ctx_digest = ctx.cache_digest();
+        let mut cursor = VerifyCursor::new(image_id);
+        for (index, fingerprint) in self.segments.iter().enumerate() {
+            let metadata = match cache.entries.get(&(*fingerprint, ctx_digest)) {
+                Some(metadata) => metadata.clone(),
+                None => {
+                    let receipt = loader(*fingerprint)?;
+                    if segment_fingerprint(receipt.as_ref())? != *fingerprint {
+                        return Err(
+                            VerificationError::SegmentFingerprintMismatch(index as u32).into()
+                        );
+                    }
+                    let metadata = receipt.verify_metadata(ctx)?;
+                    cache
+                        .entries
+                        .insert((*fingerprint, ctx_digest), metadata.clone());
+                    metadata
+                }
+            };
+            cursor.stitch_metadata(metadata)?;
+        }
+        cursor.verify_finish(ctx, &self.journal).map_err(E::from)
+    }
+}
+
+/// The segments verified by [LazySessionReceipt::verify_with_loader_cached],
+/// keyed by [segment_fingerprint] and the [VerifierContext] each segment was
+/// verified with.
+///
+/// The cache holds the metadata of each verified segment, so that it can be
+/// checked against later sessions without the segment itself. Contexts are
+/// compared as by a [super::VerificationCache], i.e. by value and by
+/// [VerifierContext::cache_id], so a segment is only skipped when verified
+/// with a context with the same settings. The cache must be
+/// protected by the same trust boundary as the receipts: anyone able to
+/// insert entries can make arbitrary segments appear valid. It
+/// is never evicted from, so it suits a batch of related sessions rather than
+/// a long-lived service.
+#[derive(Clone, Default)]
+pub struct SegmentCache {
+    entries: BTreeMap<(Digest, Digest), ReceiptMetadata>,
+}
+
+impl SegmentCache {
+    /// Construct an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of segments in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the cache holds no segments.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns true if the segment with the given fingerprint has been
+    /// verified with `ctx`.
+    pub fn contains(&self, ctx: &VerifierContext, fingerprint: &Digest) -> bool {
+        self.entries
+            .contains_key(&(*fingerprint, ctx.cache_digest()))
+    }
+}
+
+impl SessionReceipt {
+    /// Split this receipt into a [LazySessionReceipt] and its segments.
+    ///
+    /// Each segment should be stored under the fingerprint at the same
+    /// position in [LazySessionReceipt::segments].
+    ///
+    /// Returns any error from [segment_fingerprint].
+    pub fn into_lazy(
+        self,
+    ) -> Result<(LazySessionReceipt, Vec<Box<dyn Receipt>>), VerificationError> {
+        let fingerprints = self
+            .segments
+            .iter()
+            .map(|receipt| segment_fingerprint(receipt.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok((
+            LazySessionReceipt::new(fingerprints, self.journal),
+            self.segments,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap};
+
+    use risc0_zkp::core::digest::Digest;
+
+    use super::{
+        super::{
+            ExitCode, FakeReceipt, JournalHash, Receipt, ReceiptMetadata, SessionReceipt,
+            SystemState, VerificationError, VerifierContext,
+        },
+        segment_fingerprint, LazySessionReceipt, SegmentCache,
+    };
+    use crate::testutils::{dev_mode_ctx, fake_image_id};
+
+    fn state(pc: u32) -> SystemState {
+        SystemState {
+            pc,
+            merkle_root: Digest::default(),
+        }
+    }
+
+    #[test]
+    fn verify_with_loader() {
+        let journal = b"journal".to_vec();
+        let metadata = |pc, exit_code| ReceiptMetadata {
+            pre: state(pc),
+            post: state(pc + 0x1000),
+            exit_code,
+            input: Digest::default(),
+            output: JournalHash::Sha256.digest(&journal),
+        };
+        let segments: Vec<Box<dyn Receipt>> = vec![
+            Box::new(FakeReceipt::new(metadata(0x1000, ExitCode::SystemSplit))),
+            Box::new(FakeReceipt::new(metadata(0x2000, ExitCode::Halted(0)))),
+        ];
+        let image_id = fake_image_id();
+        let (lazy, segments) = SessionReceipt::new(segments, journal.clone())
+            .into_lazy()
+            .unwrap();
+
+        // An in-memory store, keyed by fingerprint.
+        let store: HashMap<Digest, FakeReceipt> = lazy
+            .segments
+            .iter()
+            .copied()
+            .zip(segments)
+            .map(|(fingerprint, receipt)| {
+                let metadata = receipt.get_metadata().unwrap();
+                (fingerprint, FakeReceipt::new(metadata))
+            })
+            .collect();
+        let loader = |fingerprint| -> Result<Box<dyn Receipt>, VerificationError> {
+            let receipt = store
+                .get(&fingerprint)
+                .ok_or(VerificationError::ReceiptFormatError)?;
+            Ok(Box::new(receipt.clone()))
+        };
+
+        let ctx = dev_mode_ctx();
+        lazy.verify_with_loader_and_context(&ctx, image_id, loader)
+            .unwrap();
+        assert_eq!(
+            lazy.verify_with_loader(image_id, loader).unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+
+        // A loader that returns a different segment than the one asked for.
+        let mut swapped = lazy.clone();
+        swapped.segments.swap(0, 1);
+        let wrong_loader = |fingerprint| {
+            let other = if fingerprint == lazy.segments[0] {
+                lazy.segments[1]
+            } else {
+                lazy.segments[0]
+            };
+            loader(other)
+        };
+        assert_eq!(
+            lazy.verify_with_loader_and_context(&ctx, image_id, wrong_loader)
+                .unwrap_err(),
+            VerificationError::SegmentFingerprintMismatch(0)
+        );
+
+        // Errors from the loader are passed through.
+        let missing = |_| -> anyhow::Result<Box<dyn Receipt>> { anyhow::bail!("not found") };
+        let err = lazy
+            .verify_with_loader_and_context(&ctx, image_id, missing)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "not found");
+        assert_eq!(
+            swapped
+                .verify_with_loader_and_context(&ctx, image_id, loader)
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+    }
+
+    #[test]
+    fn verify_with_loader_cached() {
+        let metadata = |pc, exit_code, journal: &[u8]| ReceiptMetadata {
+            pre: state(pc),
+            post: state(pc + 0x1000),
+            exit_code,
+            input: Digest::default(),
+            output: JournalHash::Sha256.digest(journal),
+        };
+        // Two sessions that share their first segment, and finish differently.
+        let shared = FakeReceipt::new(metadata(0x1000, ExitCode::SystemSplit, b""));
+        let finals = [
+            FakeReceipt::new(metadata(0x2000, ExitCode::Halted(0), b"first")),
+            FakeReceipt::new(metadata(0x2000, ExitCode::Halted(1), b"second")),
+        ];
+        let store: HashMap<Digest, FakeReceipt> = [&shared, &finals[0], &finals[1]]
+            .into_iter()
+            .map(|receipt| (segment_fingerprint(receipt).unwrap(), receipt.clone()))
+            .collect();
+        let sessions = [
+            LazySessionReceipt::new(
+                vec![
+                    segment_fingerprint(&shared).unwrap(),
+                    segment_fingerprint(&finals[0]).unwrap(),
+                ],
+                b"first".to_vec(),
+            ),
+            LazySessionReceipt::new(
+                vec![
+                    segment_fingerprint(&shared).unwrap(),
+                    segment_fingerprint(&finals[1]).unwrap(),
+                ],
+                b"second".to_vec(),
+            ),
+        ];
+        let image_id = fake_image_id();
+
+        let loaded = RefCell::new(Vec::new());
+        let loader = |fingerprint| -> Result<Box<dyn Receipt>, VerificationError> {
+            loaded.borrow_mut().push(fingerprint);
+            Ok(Box::new(store[&fingerprint].clone()))
+        };
+
+        // Segments that fail to verify aren't recorded.
+        let mut cache = SegmentCache::new();
+        assert_eq!(
+            sessions[0]
+                .verify_with_loader_cached(
+                    &VerifierContext::default(),
+                    image_id,
+                    loader,
+                    &mut cache
+                )
+                .unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+        assert!(cache.is_empty());
+        loaded.borrow_mut().clear();
+
+        // The shared segment is loaded and verified only by the first session.
+        let ctx = dev_mode_ctx();
+        for session in sessions.iter() {
+            session
+                .verify_with_loader_cached(&ctx, image_id, loader, &mut cache)
+                .unwrap();
+        }
+        assert_eq!(
+            *loaded.borrow(),
+            [
+                segment_fingerprint(&shared).unwrap(),
+                segment_fingerprint(&finals[0]).unwrap(),
+                segment_fingerprint(&finals[1]).unwrap(),
+            ]
+        );
+        assert_eq!(cache.len(), 3);
+        assert!(cache.contains(&ctx, &segment_fingerprint(&shared).unwrap()));
+        assert!(!cache.contains(
+            &VerifierContext::default(),
+            &segment_fingerprint(&shared).unwrap()
+        ));
+        // Contexts are compared by value and by id.
+        assert!(cache.contains(&dev_mode_ctx(), &segment_fingerprint(&shared).unwrap()));
+        assert!(!cache.contains(
+            &VerifierContext {
+                cache_id: 1,
+                ..ctx.clone()
+            },
+            &segment_fingerprint(&shared).unwrap()
+        ));
+
+        // Segments verified with another context aren't reused.
+        assert_eq!(
+            sessions[0]
+                .verify_with_loader_cached(
+                    &VerifierContext::default(),
+                    image_id,
+                    loader,
+                    &mut cache
+                )
+                .unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+
+        // Cached segments are still checked against the rest of the session.
+        loaded.borrow_mut().clear();
+        let swapped = LazySessionReceipt::new(
+            vec![
+                segment_fingerprint(&finals[0]).unwrap(),
+                segment_fingerprint(&shared).unwrap(),
+            ],
+            b"first".to_vec(),
+        );
+        assert_eq!(
+            swapped
+                .verify_with_loader_cached(&ctx, image_id, loader, &mut cache)
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(
+            sessions[0]
+                .verify_with_loader_cached(&ctx, Digest::default(), loader, &mut cache)
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(
+            LazySessionReceipt::new(sessions[0].segments.clone(), b"second".to_vec())
+                .verify_with_loader_cached(&ctx, image_id, loader, &mut cache)
+                .unwrap_err(),
+            VerificationError::JournalDigestMismatch
+        );
+        assert!(loaded.borrow().is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Loading of [SessionReceipt]s from memory-mapped files.
+
+use alloc::sync::Arc;
+use std::{fs::File, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+use memmap2::Mmap;
+use serde::Deserialize;
+
+use super::{Receipt, Seal, SegmentReceipt, SessionReceipt};
+use crate::serde::Deserializer as WordDeserializer;
+
+impl SessionReceipt {
+    /// Load a receipt by memory-mapping the file at `path`.
+    ///
+    /// The file must hold the receipt in the zkVM's word-oriented
+    /// [serde](crate::serde) format, as produced by
+    /// [crate::serde::to_vec] and written by `r0vm --receipt`, with each word
+    /// in the host's byte order. The [Seal] of each [SegmentReceipt] in the
+    /// file is left in the mapping, so that only the segments being verified
+    /// need to be resident in memory; any other kind of receipt is read into
+    /// memory as usual. The receipt is otherwise the same as one read with
+    /// [crate::serde::from_slice], and serializes and compares the same.
+    ///
+    /// # Safety
+    ///
+    /// The mapping is shared by the returned receipt and any clones of its
+    /// segments, and stays open until the last of them is dropped. The caller
+    /// must ensure that the file is not modified or truncated for as long as
+    /// it is mapped: the seals are read from the file lazily, so a
+    /// modification changes data that safe code assumes is immutable, and
+    /// truncation causes a bus error on access. Nothing in the platform
+    /// prevents another process from writing to the file.
+    pub unsafe fn from_mmap(path: impl AsRef<Path>) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees that the file isn't modified while it
+        // is mapped.
+        let map = Arc::new(unsafe { Mmap::map(&file)? });
+        let words: &[u32] = bytemuck::try_cast_slice(&map[..])
+            .map_err(|_| anyhow!("receipt file is not a whole number of words"))?;
+
+        let mut rest = words;
+        let num_segments: u32 = Deserialize::deserialize(&mut WordDeserializer::new(&mut rest))?;
+        let mut segments: Vec<Box<dyn Receipt>> = Vec::new();
+        for _ in 0..num_segments {
+            // A segment is serialized as a map whose first entry is its type.
+            // Receipts written before the layout version was recorded lack
+            // that last entry.
+            let mut header = rest;
+            let (entries, tag_key, tag): (u32, String, String) =
+                Deserialize::deserialize(&mut WordDeserializer::new(&mut header))?;
+            if !(entries == 4 || entries == 5) || tag_key != "type" || tag != "SegmentReceipt" {
+                segments.push(Deserialize::deserialize(&mut WordDeserializer::new(
+                    &mut rest,
+                ))?);
+                continue;
+            }
+            rest = header;
+
+            let (seal_key, seal_len): (String, u32) =
+                Deserialize::deserialize(&mut WordDeserializer::new(&mut rest))?;
+            if seal_key != "seal" || seal_len as usize > rest.len() {
+                bail!("malformed segment receipt");
+            }
+            let start = words.len() - rest.len();
+            let seal = start..start + seal_len as usize;
+            rest = &rest[seal_len as usize..];
+
+            let (index_key, index, hashfn_key, hashfn): (String, u32, String, String) =
+                Deserialize::deserialize(&mut WordDeserializer::new(&mut rest))?;
+            if index_key != "index" || hashfn_key != "hashfn" {
+                bail!("malformed segment receipt");
+            }
+            let layout_version = if entries == 5 {
+                let (layout_version_key, layout_version): (String, u32) =
+                    Deserialize::deserialize(&mut WordDeserializer::new(&mut rest))?;
+                if layout_version_key != "layout_version" {
+                    bail!("malformed segment receipt");
+                }
+                layout_version
+            } else {
+                super::LAYOUT_VERSION
+            };
+            segments.push(Box::new(SegmentReceipt {
+                seal: Seal::mapped(map.clone(), seal),
+                index,
+                hashfn,
+                layout_version,
+            }));
+        }
+
+        let journal: Vec<u8> = Deserialize::deserialize(&mut WordDeserializer::new(&mut rest))?;
+        if !rest.is_empty() {
+            bail!("trailing data after receipt");
+        }
+        Ok(Self { segments, journal })
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serialization of [SessionReceipt]s with MessagePack.
+
+use alloc::vec::Vec;
+
+use super::SessionReceipt;
+
+impl SessionReceipt {
+    /// Serialize this receipt with MessagePack.
+    ///
+    /// Structs are written as maps keyed by field name, and each segment as a
+    /// map whose `"type"` entry names its kind of receipt (e.g.
+    /// `"SegmentReceipt"`), so the result can be read without a schema, e.g.
+    /// from dynamic languages. Unlike the zkVM's [serde](crate::serde) format,
+    /// this is self-describing, at the cost of being larger.
+    pub fn to_msgpack(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// Deserialize a receipt written by [SessionReceipt::to_msgpack].
+    pub fn from_msgpack(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::core::digest::Digest;
+
+    use super::super::{
+        ExitCode, FakeReceipt, Receipt, ReceiptMetadata, SegmentReceipt, SessionReceipt,
+    };
+    use crate::testutils::fake_metadata;
+
+    #[test]
+    fn round_trip() {
+        for exit_code in [
+            ExitCode::SystemSplit,
+            ExitCode::Paused(1),
+            ExitCode::Halted(u32::MAX),
+        ] {
+            let mut metadata = ReceiptMetadata {
+                exit_code,
+                ..fake_metadata(b"journal")
+            };
+            metadata.pre.merkle_root = Digest::from([1, 2, 3, 4, 5, 6, 7, u32::MAX]);
+            let segments: Vec<Box<dyn Receipt>> = vec![
+                Box::new(SegmentReceipt::new(vec![1, 2, u32::MAX], 0, "sha-256").unwrap()),
+                Box::new(FakeReceipt::new(metadata)),
+            ];
+            let receipt = SessionReceipt::new(segments, b"journal".to_vec());
+            let decoded = SessionReceipt::from_msgpack(&receipt.to_msgpack().unwrap()).unwrap();
+            assert_eq!(decoded, receipt);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Delegation of receipt verification to a remote service.
+
+use reqwest::blocking::Client;
+use risc0_zkp::core::digest::Digest;
+use serde::{Deserialize, Serialize};
+
+use super::{SessionReceipt, VerificationError, Verifier};
+
+/// A [Verifier] implementation that delegates verification to a remote
+/// service.
+///
+/// The verifier sends a [RemoteVerifyRequest] to the service as the JSON body
+/// of an HTTP `POST` to its endpoint, and the service replies with a
+/// [RemoteVerifyResponse] as JSON:
+///
+/// ```text
+/// POST <endpoint>
+/// {"image_id": "<hex>", "receipt": "<hex>"}
+///
+/// 200 OK
+/// {"verified": true}
+/// {"verified": false, "error": "image_id mismatch"}
+/// ```
+///
+/// A service can decode requests with [RemoteVerifyRequest::decode], and
+/// build its responses with [RemoteVerifyResponse::from_result].
+///
+/// The result is only as trustworthy as the service, and the connection to
+/// it: the receipt isn't verified locally at all. This suits clients that
+/// can't run the verifier themselves, and already trust the operator of the
+/// service.
+#[derive(Clone, Debug)]
+pub struct RemoteVerifier {
+    endpoint: String,
+    client: Client,
+}
+
+impl RemoteVerifier {
+    /// Construct a [RemoteVerifier] for the service at the URL `endpoint`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self::with_client(endpoint, Client::new())
+    }
+
+    /// Construct a [RemoteVerifier] for the service at the URL `endpoint`,
+    /// making requests with `client`, e.g. to set a timeout.
+    pub fn with_client(endpoint: impl Into<String>, client: Client) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            client,
+        }
+    }
+
+    /// Returns the URL of the service.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+impl Verifier for RemoteVerifier {
+    /// Sends `receipt` to the service to be verified against `image_id`.
+    ///
+    /// Returns [VerificationError::RemoteRejected] with the reason given by
+    /// the service if it found the receipt invalid, and
+    /// [VerificationError::RemoteUnavailable] if no verdict was received,
+    /// e.g. because the service couldn't be reached, responded with an HTTP
+    /// error, or sent a malformed response. Returns
+    /// [VerificationError::ReceiptFormatError], without contacting the
+    /// service, if the receipt can't be serialized.
+    fn verify(&self, receipt: &SessionReceipt, image_id: Digest) -> Result<(), VerificationError> {
+        let unavailable =
+            |err: reqwest::Error| VerificationError::RemoteUnavailable(err.to_string());
+        let response: RemoteVerifyResponse = self
+            .client
+            .post(&self.endpoint)
+            .json(&RemoteVerifyRequest::new(receipt, image_id)?)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(unavailable)?
+            .json()
+            .map_err(unavailable)?;
+        if !response.verified {
+            return Err(VerificationError::RemoteRejected(
+                response.error.unwrap_or_default(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The body of a request to a remote verification service.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RemoteVerifyRequest {
+    /// The image ID to verify against, represented in JSON as the hex
+    /// encoding of its bytes, i.e. `hex::encode(image_id)`, with an optional
+    /// `0x` prefix.
+    #[serde(with = "risc0_zkp::core::digest::digest_hex")]
+    pub image_id: Digest,
+
+    /// The receipt to verify, as the hex encoding of the bytes written by
+    /// [SessionReceipt::to_writer].
+    pub receipt: String,
+}
+
+impl RemoteVerifyRequest {
+    /// Construct the request to verify `receipt` against `image_id`.
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if the receipt can't be
+    /// serialized.
+    pub fn new(receipt: &SessionReceipt, image_id: Digest) -> Result<Self, VerificationError> {
+        let mut bytes = Vec::new();
+        receipt
+            .to_writer(&mut bytes)
+            .or(Err(VerificationError::ReceiptFormatError))?;
+        Ok(Self {
+            image_id,
+            receipt: hex::encode(bytes),
+        })
+    }
+
+    /// Decode the receipt and image ID of this request, for a service to
+    /// verify.
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if the receipt can't be
+    /// decoded.
+    pub fn decode(&self) -> Result<(SessionReceipt, Digest), VerificationError> {
+        use hex::FromHex;
+        let bytes = Vec::from_hex(&self.receipt).or(Err(VerificationError::ReceiptFormatError))?;
+        let receipt = SessionReceipt::from_reader(bytes.as_slice())
+            .or(Err(VerificationError::ReceiptFormatError))?;
+        Ok((receipt, self.image_id))
+    }
+}
+
+/// The body of a response from a remote verification service.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RemoteVerifyResponse {
+    /// Whether the receipt verified successfully.
+    pub verified: bool,
+
+    /// Why the receipt failed to verify, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RemoteVerifyResponse {
+    /// Construct the response reporting `result`, the result of verifying the
+    /// receipt of a request.
+    pub fn from_result(result: &Result<(), VerificationError>) -> Self {
+        match result {
+            Ok(()) => Self {
+                verified: true,
+                error: None,
+            },
+            Err(err) => Self {
+                verified: false,
+                error: Some(err.to_string()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use risc0_zkp::core::digest::Digest;
+
+    use super::{RemoteVerifier, RemoteVerifyRequest, RemoteVerifyResponse};
+    use crate::receipt::{SegmentReceipt, SessionReceipt, VerificationError, Verifier};
+
+    fn receipt() -> SessionReceipt {
+        SessionReceipt::new(
+            vec![Box::new(
+                SegmentReceipt::new(vec![1, 2, 3], 0, "sha-256").unwrap(),
+            )],
+            b"journal".to_vec(),
+        )
+    }
+
+    #[test]
+    fn request_round_trip() {
+        let image_id = Digest::from([4; 8]);
+        let request = RemoteVerifyRequest::new(&receipt(), image_id).unwrap();
+        assert_eq!(request.decode().unwrap(), (receipt(), image_id));
+
+        // The image ID is hex in JSON, with or without a `0x` prefix.
+        let mut json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["image_id"], hex::encode(image_id));
+        json["image_id"] = format!("0x{}", hex::encode(image_id)).into();
+        assert_eq!(
+            serde_json::from_value::<RemoteVerifyRequest>(json.clone()).unwrap(),
+            request
+        );
+        json["image_id"] = "0x04".into();
+        assert!(serde_json::from_value::<RemoteVerifyRequest>(json).is_err());
+
+        let mut malformed = request;
+        malformed.receipt.truncate(8);
+        assert_eq!(
+            malformed.decode().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+    }
+
+    // Serves a single HTTP request, replying with `status` and the JSON
+    // `response`. Returns the URL to send the request to, and a handle that
+    // yields the JSON body of the request received.
+    fn serve_once(status: u16, response: String) -> (String, JoinHandle<serde_json::Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/verify", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut reader = BufReader::new(listener.accept().unwrap().0);
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            assert!(line.starts_with("POST /verify "), "{line}");
+            let mut content_length = 0;
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(':') {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        content_length = value.trim().parse().unwrap()
+                    }
+                    Some(_) => {}
+                    None => break,
+                }
+            }
+            let mut request = vec![0; content_length];
+            reader.read_exact(&mut request).unwrap();
+            write!(
+                reader.into_inner(),
+                "HTTP/1.1 {status} \r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                response.len()
+            )
+            .unwrap();
+            serde_json::from_slice(&request).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn remote_verifier() {
+        let image_id = Digest::from([4; 8]);
+        let request =
+            serde_json::to_value(RemoteVerifyRequest::new(&receipt(), image_id).unwrap()).unwrap();
+        let response =
+            |result| serde_json::to_string(&RemoteVerifyResponse::from_result(&result)).unwrap();
+
+        let (url, server) = serve_once(200, response(Ok(())));
+        RemoteVerifier::new(url)
+            .verify(&receipt(), image_id)
+            .unwrap();
+        assert_eq!(server.join().unwrap(), request);
+
+        let (url, server) = serve_once(
+            200,
+            response(Err(VerificationError::ImageVerificationError)),
+        );
+        assert_eq!(
+            RemoteVerifier::new(url)
+                .verify(&receipt(), image_id)
+                .unwrap_err(),
+            VerificationError::RemoteRejected("image_id mismatch".into())
+        );
+        server.join().unwrap();
+
+        let (url, server) = serve_once(500, String::new());
+        assert!(matches!(
+            RemoteVerifier::new(url)
+                .verify(&receipt(), image_id)
+                .unwrap_err(),
+            VerificationError::RemoteUnavailable(_)
+        ));
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage for the seal of a [crate::SegmentReceipt].
+
+#[cfg(feature = "mmap")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "mmap")]
+use core::ops::Range;
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The seal of a [crate::SegmentReceipt], which dereferences to its words.
+///
+/// A seal is usually held in memory, but one loaded by
+/// [crate::SessionReceipt::from_mmap] is read from the mapped file as it is
+/// accessed. Where a seal is stored doesn't affect how it is serialized or
+/// compared. Mutating a mapped seal copies it into memory first.
+#[derive(Clone)]
+pub struct Seal(Storage);
+
+#[derive(Clone)]
+enum Storage {
+    Owned(Vec<u32>),
+    /// A range of words within a mapped file.
+    #[cfg(feature = "mmap")]
+    Mapped(Arc<Mmap>, Range<usize>),
+}
+
+impl Seal {
+    /// Returns a seal of the words in `range` of `map`.
+    ///
+    /// `map` must be a whole number of words long.
+    #[cfg(feature = "mmap")]
+    pub(crate) fn mapped(map: Arc<Mmap>, range: Range<usize>) -> Self {
+        Self(Storage::Mapped(map, range))
+    }
+
+    /// Whether this seal is read from a memory-mapped file.
+    pub fn is_mapped(&self) -> bool {
+        !matches!(self.0, Storage::Owned(_))
+    }
+
+    /// Returns the seal in memory, copying it there first if it is mapped.
+    pub fn to_mut(&mut self) -> &mut Vec<u32> {
+        if self.is_mapped() {
+            *self = Self::from(self.to_vec());
+        }
+        match &mut self.0 {
+            Storage::Owned(words) => words,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(..) => unreachable!(),
+        }
+    }
+
+    /// Returns the words of this seal, copying them if it is mapped.
+    pub fn into_vec(self) -> Vec<u32> {
+        match self.0 {
+            Storage::Owned(words) => words,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(..) => self.to_vec(),
+        }
+    }
+}
+
+impl Default for Seal {
+    fn default() -> Self {
+        Self::from(Vec::new())
+    }
+}
+
+impl From<Vec<u32>> for Seal {
+    fn from(words: Vec<u32>) -> Self {
+        Self(Storage::Owned(words))
+    }
+}
+
+impl From<Seal> for Vec<u32> {
+    fn from(seal: Seal) -> Self {
+        seal.into_vec()
+    }
+}
+
+impl Deref for Seal {
+    type Target = [u32];
+
+    fn deref(&self) -> &[u32] {
+        match &self.0 {
+            Storage::Owned(words) => words,
+            // The mapping is page-aligned and was checked to be a whole number
+            // of words when it was opened.
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(map, range) => &bytemuck::cast_slice(&map[..])[range.clone()],
+        }
+    }
+}
+
+impl DerefMut for Seal {
+    fn deref_mut(&mut self) -> &mut [u32] {
+        self.to_mut()
+    }
+}
+
+impl fmt::Debug for Seal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for Seal {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Seal {}
+
+// Serialized in the same form as a `Vec<u32>`, wherever the seal is stored.
+impl Serialize for Seal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Seal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        super::deserialize_seal(deserializer).map(Self::from)
+    }
+}
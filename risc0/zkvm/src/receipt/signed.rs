@@ -0,0 +1,130 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ed25519 signatures by the prover over [SessionReceipt]s.
+//!
+//! A prover can sign the [SessionReceipt::fingerprint] of a receipt to attest
+//! that it produced the receipt. This identifies the prover for
+//! accountability, but has no bearing on the soundness of the proof: a
+//! signed receipt is verified exactly as an unsigned one, and the signature
+//! is checked in addition. The signature is carried alongside the receipt
+//! rather than in it, since the fingerprint covers the whole serialized
+//! receipt.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use risc0_zkp::core::digest::Digest;
+
+use super::{SessionReceipt, VerificationError, VerifierContext};
+
+impl SessionReceipt {
+    /// Signs the [SessionReceipt::fingerprint] of this receipt with the
+    /// Ed25519 `secret_key` of the prover.
+    ///
+    /// Returns an error if the fingerprint can't be computed; see
+    /// [SessionReceipt::fingerprint].
+    pub fn sign(&self, secret_key: &[u8; 32]) -> Result<[u8; 64], VerificationError> {
+        let signing_key = SigningKey::from_bytes(secret_key);
+        Ok(signing_key.sign(self.fingerprint()?.as_bytes()).to_bytes())
+    }
+
+    /// Verifies the integrity of this receipt, and then checks that
+    /// `signature` is a signature over its [SessionReceipt::fingerprint] by
+    /// the prover with the Ed25519 public key `prover_pubkey`.
+    ///
+    /// Returns [VerificationError::InvalidSignature] if the receipt verifies
+    /// but the signature doesn't, including when `prover_pubkey` isn't a valid
+    /// public key. Signatures are checked with
+    /// [VerifyingKey::verify_strict], which rejects malleable signatures.
+    pub fn verify_signed(
+        &self,
+        image_id: impl Into<Digest>,
+        prover_pubkey: &[u8; 32],
+        signature: &[u8; 64],
+    ) -> Result<(), VerificationError> {
+        self.verify_signed_with_context(
+            &VerifierContext::default(),
+            image_id,
+            prover_pubkey,
+            signature,
+        )
+    }
+
+    /// Verifies the integrity of this receipt with the given context, and then
+    /// checks its signature; see [SessionReceipt::verify_signed].
+    pub fn verify_signed_with_context(
+        &self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+        prover_pubkey: &[u8; 32],
+        signature: &[u8; 64],
+    ) -> Result<(), VerificationError> {
+        self.verify_with_context(ctx, image_id)?;
+        let verifying_key =
+            VerifyingKey::from_bytes(prover_pubkey).or(Err(VerificationError::InvalidSignature))?;
+        verifying_key
+            .verify_strict(
+                self.fingerprint()?.as_bytes(),
+                &Signature::from_bytes(signature),
+            )
+            .or(Err(VerificationError::InvalidSignature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::SigningKey;
+
+    use crate::{
+        receipt::VerificationError,
+        testutils::{dev_mode_ctx, fake_image_id, fake_receipt},
+    };
+
+    #[test]
+    fn sign_and_verify() {
+        let secret_key = [7; 32];
+        let pubkey = SigningKey::from_bytes(&secret_key)
+            .verifying_key()
+            .to_bytes();
+        let image_id = fake_image_id();
+        let ctx = dev_mode_ctx();
+
+        let signature = fake_receipt(b"a").sign(&secret_key).unwrap();
+        fake_receipt(b"a")
+            .verify_signed_with_context(&ctx, image_id, &pubkey, &signature)
+            .unwrap();
+
+        // A signature over another receipt.
+        assert_eq!(
+            fake_receipt(b"b")
+                .verify_signed_with_context(&ctx, image_id, &pubkey, &signature)
+                .unwrap_err(),
+            VerificationError::InvalidSignature
+        );
+        // A signature by another prover.
+        let other = SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes();
+        assert_eq!(
+            fake_receipt(b"a")
+                .verify_signed_with_context(&ctx, image_id, &other, &signature)
+                .unwrap_err(),
+            VerificationError::InvalidSignature
+        );
+        // The receipt itself is verified first.
+        assert_eq!(
+            fake_receipt(b"a")
+                .verify_signed(image_id, &pubkey, &signature)
+                .unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+    }
+}
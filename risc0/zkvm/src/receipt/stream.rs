@@ -0,0 +1,235 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming serialization of [SessionReceipt]s to and from byte streams.
+
+use std::io::{self, Read, Write};
+
+use risc0_zkvm_platform::WORD_SIZE;
+use serde::{Deserialize, Serialize};
+
+use super::SessionReceipt;
+use crate::{
+    align_up,
+    serde::{Deserializer, Error, Serializer, WordRead, WordWrite},
+};
+
+impl SessionReceipt {
+    /// Serialize this receipt to `writer`.
+    ///
+    /// The receipt is written in the zkVM's word-oriented
+    /// [serde](crate::serde) format, with each word in little-endian byte
+    /// order, i.e. the bytes of [crate::serde::to_vec] on a little-endian
+    /// host. It is written one field at a time as it is serialized, so the
+    /// serialized receipt is never held in memory as a whole. Many small
+    /// writes are made, so an unbuffered `writer` should be wrapped in a
+    /// [std::io::BufWriter].
+    ///
+    /// Errors from `writer` are returned as they are. An error while
+    /// serializing the receipt is returned with [io::ErrorKind::InvalidData],
+    /// wrapping the [crate::serde::Error].
+    pub fn to_writer(&self, writer: impl Write) -> io::Result<()> {
+        let mut stream = Stream::new(writer);
+        let result = self.serialize(&mut Serializer::new(&mut stream));
+        stream.finish(result)
+    }
+
+    /// Deserialize a receipt from `reader`, as written by
+    /// [SessionReceipt::to_writer].
+    ///
+    /// The receipt is read one field at a time as it is deserialized, without
+    /// first reading the serialized receipt into memory as a whole. Reading
+    /// stops at the end of the receipt, so `reader` may carry further data.
+    /// Many small reads are made, so an unbuffered `reader` should be wrapped
+    /// in a [std::io::BufReader].
+    ///
+    /// Errors from `reader` are returned as they are; in particular, a
+    /// truncated receipt returns [io::ErrorKind::UnexpectedEof]. A malformed
+    /// receipt returns [io::ErrorKind::InvalidData], wrapping the
+    /// [crate::serde::Error].
+    pub fn from_reader(reader: impl Read) -> io::Result<Self> {
+        let mut stream = Stream::new(reader);
+        let result = Self::deserialize(&mut Deserializer::new(&mut stream));
+        stream.finish(result)
+    }
+}
+
+// Adapts a byte stream to the word streams of the zkVM serde format.
+struct Stream<T> {
+    inner: T,
+    // The first I/O error, which is reported in place of the serde error that
+    // it causes.
+    err: Option<io::Error>,
+}
+
+impl<T> Stream<T> {
+    fn new(inner: T) -> Self {
+        Self { inner, err: None }
+    }
+
+    fn fail(&mut self, err: io::Error, serde_err: Error) -> Error {
+        self.err.get_or_insert(err);
+        serde_err
+    }
+
+    fn finish<U>(self, result: Result<U, Error>) -> io::Result<U> {
+        result.map_err(|err| {
+            self.err
+                .unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+    }
+}
+
+impl<R: Read> WordRead for Stream<R> {
+    fn read_words(&mut self, words: &mut [u32]) -> crate::serde::Result<()> {
+        if let Err(err) = self.inner.read_exact(bytemuck::cast_slice_mut(words)) {
+            return Err(self.fail(err, Error::DeserializeUnexpectedEnd));
+        }
+        for word in words.iter_mut() {
+            *word = u32::from_le(*word);
+        }
+        Ok(())
+    }
+
+    fn read_padded_bytes(&mut self, bytes: &mut [u8]) -> crate::serde::Result<()> {
+        let mut padding = [0u8; WORD_SIZE];
+        let padding = &mut padding[..align_up(bytes.len(), WORD_SIZE) - bytes.len()];
+        self.inner
+            .read_exact(bytes)
+            .and_then(|_| self.inner.read_exact(padding))
+            .map_err(|err| self.fail(err, Error::DeserializeUnexpectedEnd))
+    }
+}
+
+impl<W: Write> WordWrite for Stream<W> {
+    fn write_words(&mut self, words: &[u32]) -> crate::serde::Result<()> {
+        for word in words {
+            if let Err(err) = self.inner.write_all(&word.to_le_bytes()) {
+                return Err(self.fail(err, Error::SerializeBufferFull));
+            }
+        }
+        Ok(())
+    }
+
+    fn write_padded_bytes(&mut self, bytes: &[u8]) -> crate::serde::Result<()> {
+        let padding = [0u8; WORD_SIZE];
+        let padding = &padding[..align_up(bytes.len(), WORD_SIZE) - bytes.len()];
+        self.inner
+            .write_all(bytes)
+            .and_then(|_| self.inner.write_all(padding))
+            .map_err(|err| self.fail(err, Error::SerializeBufferFull))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, BufReader, BufWriter, Read},
+        sync::mpsc,
+        thread,
+    };
+
+    use super::super::{FakeReceipt, SessionReceipt};
+    use crate::{serde::to_vec, testutils::fake_metadata};
+
+    fn receipt() -> SessionReceipt {
+        let metadata = fake_metadata(b"journal");
+        SessionReceipt::new(
+            vec![
+                Box::new(FakeReceipt::new(metadata.clone())),
+                Box::new(FakeReceipt::new(metadata)),
+            ],
+            b"journal".to_vec(),
+        )
+    }
+
+    // The reading half of a pipe whose writing half sends chunks over a
+    // channel.
+    struct PipeReader {
+        rx: mpsc::Receiver<Vec<u8>>,
+        buf: io::Cursor<Vec<u8>>,
+    }
+
+    impl Read for PipeReader {
+        fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+            loop {
+                let n = self.buf.read(out)?;
+                if n > 0 || out.is_empty() {
+                    return Ok(n);
+                }
+                match self.rx.recv() {
+                    Ok(chunk) => self.buf = io::Cursor::new(chunk),
+                    Err(_) => return Ok(0),
+                }
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct PipeWriter(mpsc::Sender<Vec<u8>>);
+
+    impl io::Write for PipeWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0
+                .send(buf.to_vec())
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let (tx, rx) = mpsc::channel();
+        let writer = thread::spawn(move || {
+            let mut writer = BufWriter::with_capacity(64, PipeWriter(tx));
+            receipt().to_writer(&mut writer).unwrap();
+            writer.into_inner().unwrap();
+        });
+        let reader = PipeReader {
+            rx,
+            buf: io::Cursor::new(Vec::new()),
+        };
+        let decoded = SessionReceipt::from_reader(BufReader::new(reader)).unwrap();
+        writer.join().unwrap();
+        assert_eq!(decoded, receipt());
+
+        // The stream holds the same bytes as the word format.
+        let mut bytes = Vec::new();
+        receipt().to_writer(&mut bytes).unwrap();
+        let words = to_vec(&receipt()).unwrap();
+        assert_eq!(bytes, bytemuck::cast_slice::<u32, u8>(&words));
+    }
+
+    #[test]
+    fn errors() {
+        let mut bytes = Vec::new();
+        receipt().to_writer(&mut bytes).unwrap();
+
+        let err = SessionReceipt::from_reader(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Corrupt the first byte of the first segment's type tag.
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 0xff;
+        let err = SessionReceipt::from_reader(&corrupt[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = receipt().to_writer(&mut [0u8; 16][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright 2023 RISC Zero, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Receipts that are known to have been verified.
+
+use risc0_zkp::core::digest::Digest;
+
+use super::{SessionReceipt, VerificationError, VerifierContext};
+
+/// A [SessionReceipt] that has been verified against an image ID.
+///
+/// A `VerifiedReceipt` can only be obtained from
+/// [SessionReceipt::into_verified], so code that takes one as a parameter
+/// knows that the receipt was verified, without verifying it again. The image
+/// ID it was verified against is recorded alongside it, and should be checked
+/// with [VerifiedReceipt::require_image_id] by code that expects a particular
+/// guest. The receipt can't be modified while it is wrapped, and this type
+/// deliberately can't be deserialized, since that would allow an unverified
+/// receipt to pass as verified.
+///
+/// ```rust
+/// use risc0_zkvm::VerifiedReceipt;
+/// # use risc0_zkvm::{default_executor_from_elf, ExecutorEnv};
+/// # use risc0_zkvm_methods::{FIB_ELF, FIB_ID};
+///
+/// // Only receipts that have been verified can be passed in here.
+/// fn handle(receipt: &VerifiedReceipt) {
+///     receipt.require_image_id(FIB_ID).unwrap();
+///     let journal = &receipt.receipt().journal;
+///     // ...
+/// }
+///
+/// # #[cfg(not(feature = "cuda"))]
+/// # {
+/// # let env = ExecutorEnv::builder().add_input(&[20]).build().unwrap();
+/// # let mut exec = default_executor_from_elf(env, FIB_ELF).unwrap();
+/// # let session = exec.run().unwrap();
+/// # let receipt = session.prove().unwrap();
+/// let verified = receipt.into_verified(FIB_ID).unwrap();
+/// handle(&verified);
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifiedReceipt {
+    receipt: SessionReceipt,
+    image_id: Digest,
+}
+
+impl VerifiedReceipt {
+    /// Returns the verified receipt.
+    pub fn receipt(&self) -> &SessionReceipt {
+        &self.receipt
+    }
+
+    /// Returns the image ID that the receipt was verified against.
+    pub fn image_id(&self) -> Digest {
+        self.image_id
+    }
+
+    /// Checks that the receipt was verified against `image_id`, returning
+    /// [VerificationError::ImageVerificationError] if not.
+    pub fn require_image_id(&self, image_id: impl Into<Digest>) -> Result<(), VerificationError> {
+        if self.image_id != image_id.into() {
+            return Err(VerificationError::ImageVerificationError);
+        }
+        Ok(())
+    }
+
+    /// Unwraps the receipt, giving up the record that it was verified.
+    pub fn into_inner(self) -> SessionReceipt {
+        self.receipt
+    }
+}
+
+impl SessionReceipt {
+    /// Verifies this receipt with [SessionReceipt::verify], and wraps it in a
+    /// [VerifiedReceipt] recording that it was verified against `image_id`.
+    pub fn into_verified(
+        self,
+        image_id: impl Into<Digest>,
+    ) -> Result<VerifiedReceipt, VerificationError> {
+        self.into_verified_with_context(&VerifierContext::default(), image_id)
+    }
+
+    /// Verifies this receipt with [SessionReceipt::verify_with_context], and
+    /// wraps it in a [VerifiedReceipt] recording that it was verified against
+    /// `image_id`.
+    pub fn into_verified_with_context(
+        self,
+        ctx: &VerifierContext,
+        image_id: impl Into<Digest>,
+    ) -> Result<VerifiedReceipt, VerificationError> {
+        let image_id = image_id.into();
+        self.verify_with_context(ctx, image_id)?;
+        Ok(VerifiedReceipt {
+            receipt: self,
+            image_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use risc0_zkp::core::digest::Digest;
+
+    use crate::{
+        receipt::VerificationError,
+        testutils::{dev_mode_ctx, fake_image_id, fake_receipt},
+    };
+
+    #[test]
+    fn into_verified() {
+        let receipt = || fake_receipt(b"journal");
+        let image_id = fake_image_id();
+        let ctx = dev_mode_ctx();
+
+        let verified = receipt()
+            .into_verified_with_context(&ctx, image_id)
+            .unwrap();
+        assert_eq!(verified.image_id(), image_id);
+        verified.require_image_id(image_id).unwrap();
+        assert_eq!(
+            verified.require_image_id(Digest::default()).unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(verified.into_inner(), receipt());
+
+        assert_eq!(
+            receipt()
+                .into_verified_with_context(&ctx, Digest::default())
+                .unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        assert_eq!(
+            receipt().into_verified(image_id).unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+    }
+}
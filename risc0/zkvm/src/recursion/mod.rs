@@ -27,7 +27,7 @@ mod receipt;
 mod taps;
 
 pub use poly_ext::DEF;
-pub use receipt::{valid_control_ids, RollupReceipt};
+pub use receipt::{valid_control_ids, JoinedReceipt, RollupReceipt, MAX_JOIN_DEPTH};
 pub use taps::TAPSET;
 
 /// This struct implements traits that are defined by code generated by the
@@ -12,21 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use alloc::{collections::VecDeque, vec::Vec};
+use alloc::{boxed::Box, collections::VecDeque, format, vec::Vec};
+use core::cell::Cell;
 
 use dyn_partial_eq::DynPartialEq;
 use risc0_core::field::baby_bear::BabyBearElem;
 use risc0_zkp::{
     adapter::CircuitInfo,
     core::{digest::Digest, hash::sha::Sha256},
-    verify::VerificationError,
+    verify::VerificationError as ProofError,
 };
 use serde::{Deserialize, Serialize};
 
 use super::CircuitImpl;
 use crate::{
     control_id::POSEIDON_CONTROL_ID,
-    receipt::{Receipt, ReceiptMetadata, SystemState, VerifierContext},
+    receipt::{
+        check_seal_len, ExitCode, Receipt, ReceiptMetadata, SystemState, VerificationError,
+        VerifierContext,
+    },
     recursion::{circuit_impl::CIRCUIT_CORE, control_id::RECURSION_CONTROL_IDS},
     sha,
 };
@@ -45,6 +49,24 @@ pub fn valid_control_ids() -> Vec<Digest> {
     all_ids
 }
 
+/// The maximum depth of a tree of [JoinedReceipt]s, counting the join at the
+/// root as depth 1.
+///
+/// A balanced tree of this depth joins up to 2^32 segments, far more than any
+/// session produces, so a deeper tree is rejected as malformed before it is
+/// verified.
+pub const MAX_JOIN_DEPTH: usize = 32;
+
+// The control IDs that a join proof may be verified against: those of the
+// recursion programs. Unlike [valid_control_ids], this excludes the rv32im
+// control IDs, so a segment proof can't stand in for a join.
+fn join_control_ids() -> impl Iterator<Item = Digest> {
+    use hex::FromHex;
+    RECURSION_CONTROL_IDS
+        .iter()
+        .map(|digest_str| Digest::from_hex(digest_str).unwrap())
+}
+
 fn tagged_struct(tag: &str, down: &[Digest], data: &[u32]) -> Digest {
     let tag_digest: Digest = *sha::Impl::hash_bytes(tag.as_bytes());
     let mut all = Vec::<u8>::new();
@@ -175,23 +197,36 @@ pub struct RollupReceipt {
 #[typetag::serde]
 impl Receipt for RollupReceipt {
     fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
+        self.verify_control_id(ctx).map(|_| ())
+    }
+
+    fn verify_control_id(&self, ctx: &VerifierContext) -> Result<Digest, VerificationError> {
         let valid_ids = valid_control_ids();
-        let check_code = |_, control_id: &Digest| -> Result<(), VerificationError> {
-            valid_ids
-                .iter()
-                .find(|x| *x == control_id)
-                .map(|_| ())
-                .ok_or(VerificationError::ControlVerificationError)
+        let verified_id = Cell::new(None);
+        let check_code = |_, control_id: &Digest| -> Result<(), ProofError> {
+            valid_ids.iter().find(|x| *x == control_id).ok_or(
+                ProofError::ControlVerificationError {
+                    control_id: *control_id,
+                },
+            )?;
+            verified_id.set(Some(*control_id));
+            Ok(())
         };
         let suite = ctx
             .suites
-            .get("poseidon")
+            .get(self.hashfn())
             .ok_or(VerificationError::InvalidHashSuite)?;
+        let seal = self.get_seal()?;
         // Verify the receipt itself is correct
-        risc0_zkp::verify::verify(&CIRCUIT_CORE, suite, &self.seal, check_code)?;
+        risc0_zkp::verify::verify_with_params(
+            &CIRCUIT_CORE,
+            suite,
+            seal,
+            check_code,
+            &ctx.params.unwrap_or_default(),
+        )?;
         // Extract the globals from the seal
-        let output_elems: &[BabyBearElem] =
-            bytemuck::cast_slice(&self.seal[..CircuitImpl::OUTPUT_SIZE]);
+        let output_elems: &[BabyBearElem] = bytemuck::cast_slice(&seal[..CircuitImpl::OUTPUT_SIZE]);
         let mut seal_meta = VecDeque::new();
         for elem in output_elems {
             seal_meta.push_back(elem.as_u32())
@@ -204,21 +239,238 @@ impl Receipt for RollupReceipt {
             return Err(VerificationError::JournalDigestMismatch);
         }
         // Everything passed
-        Ok(())
+        verified_id
+            .get()
+            .ok_or(VerificationError::Proof(ProofError::InvalidProof))
     }
 
     fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
         Ok(self.meta.clone())
     }
 
-    fn get_seal(&self) -> &[u32] {
-        &self.seal
+    fn get_seal(&self) -> Result<&[u32], VerificationError> {
+        check_seal_len(self.seal.len())?;
+        Ok(&self.seal)
+    }
+
+    /// Rollup receipts are always proven with Poseidon.
+    fn hashfn(&self) -> &str {
+        "poseidon"
+    }
+
+    fn clone_box(&self) -> Box<dyn Receipt> {
+        Box::new(self.clone())
+    }
+}
+
+/// A receipt for a computation proven in parallel, as a tree of receipts
+/// joined through recursion.
+///
+/// Each [JoinedReceipt] joins two receipts for consecutive parts of the
+/// computation, `left` followed by `right`, which may themselves be
+/// [JoinedReceipt]s; the leaves are usually [crate::SegmentReceipt]s. The
+/// `join` proof commits to the metadata of the joined computation, as reported
+/// by [Receipt::get_metadata]:
+///
+/// * `pre` and `input` are those of `left`, i.e. of the first segment.
+/// * `post`, `exit_code` and `output` are those of `right`, i.e. of the last
+///   segment.
+///
+/// A [crate::SessionReceipt] whose only segment is a [JoinedReceipt] is
+/// verified against the image ID and journal like any other, using this
+/// metadata. A tree deeper than [MAX_JOIN_DEPTH] is rejected.
+///
+/// With the `std` feature, a tree deeper than [MAX_JOIN_DEPTH] is also
+/// rejected while it is deserialized, before it is fully read, so that an
+/// untrusted receipt can't exhaust the stack while it is deserialized or
+/// dropped. Without `std`, deserialization doesn't track the depth, and the
+/// deserializer must bound the nesting of an untrusted receipt itself.
+#[derive(Clone, Debug, Serialize, PartialEq, DynPartialEq)]
+pub struct JoinedReceipt {
+    /// The recursion proof joining `left` and `right`.
+    pub join: RollupReceipt,
+
+    /// The receipt for the first part of the computation.
+    pub left: Box<dyn Receipt>,
+
+    /// The receipt for the second part of the computation, which continues
+    /// from where `left` split.
+    pub right: Box<dyn Receipt>,
+}
+
+// The serialized form of a [JoinedReceipt], which it is deserialized from
+// once the depth of the tree is checked.
+#[derive(Deserialize)]
+#[serde(rename = "JoinedReceipt")]
+struct JoinedReceiptFields {
+    join: RollupReceipt,
+    left: Box<dyn Receipt>,
+    right: Box<dyn Receipt>,
+}
+
+impl<'de> Deserialize<'de> for JoinedReceipt {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[cfg(feature = "std")]
+        let _depth = DeserializeDepth::enter().ok_or_else(|| {
+            <D::Error as serde::de::Error>::custom(format!(
+                "joined receipt deeper than {MAX_JOIN_DEPTH}"
+            ))
+        })?;
+        let JoinedReceiptFields { join, left, right } =
+            JoinedReceiptFields::deserialize(deserializer)?;
+        Ok(Self { join, left, right })
+    }
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    // The number of [JoinedReceipt]s being deserialized on this thread, each
+    // nested in the last.
+    static DESERIALIZE_DEPTH: Cell<usize> = Cell::new(0);
+}
+
+// Counts a [JoinedReceipt] being deserialized towards [DESERIALIZE_DEPTH]
+// until it is dropped, however its deserialization ends.
+#[cfg(feature = "std")]
+struct DeserializeDepth;
+
+#[cfg(feature = "std")]
+impl DeserializeDepth {
+    fn enter() -> Option<Self> {
+        DESERIALIZE_DEPTH.with(|depth| {
+            if depth.get() >= MAX_JOIN_DEPTH {
+                return None;
+            }
+            depth.set(depth.get() + 1);
+            Some(Self)
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for DeserializeDepth {
+    fn drop(&mut self) {
+        DESERIALIZE_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+#[typetag::serde]
+impl Receipt for JoinedReceipt {
+    fn verify_with_context(&self, ctx: &VerifierContext) -> Result<(), VerificationError> {
+        self.verify_control_id(ctx).map(|_| ())
+    }
+
+    /// Verifies both sides of the tree, that `right` continues from `left`,
+    /// and that the join proof commits to their combined metadata, under the
+    /// control ID of a recursion program. Returns the control ID of the join
+    /// proof.
+    fn verify_control_id(&self, ctx: &VerifierContext) -> Result<Digest, VerificationError> {
+        self.depth()?;
+        let left = self.left.verify_metadata(ctx)?;
+        let right = self.right.verify_metadata(ctx)?;
+        if left.exit_code != ExitCode::SystemSplit {
+            return Err(VerificationError::UnexpectedExitCode);
+        }
+        if left.post != right.pre {
+            return Err(VerificationError::ImageVerificationError);
+        }
+        let joined = ReceiptMetadata {
+            pre: left.pre,
+            post: right.post,
+            exit_code: right.exit_code,
+            input: left.input,
+            output: right.output,
+        };
+        if joined != self.join.meta {
+            let diff = self
+                .join
+                .meta
+                .diff(&joined)
+                .iter()
+                .map(|diff| format!("{diff}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(VerificationError::MetadataMismatch { diff });
+        }
+        let control_id = self.join.verify_control_id(ctx)?;
+        if !join_control_ids().any(|id| id == control_id) {
+            return Err(ProofError::ControlVerificationError { control_id }.into());
+        }
+        Ok(control_id)
+    }
+
+    fn get_metadata(&self) -> Result<ReceiptMetadata, VerificationError> {
+        self.join.get_metadata()
+    }
+
+    /// Returns the seal of the join proof at the root of the tree.
+    fn get_seal(&self) -> Result<&[u32], VerificationError> {
+        self.join.get_seal()
+    }
+
+    fn hashfn(&self) -> &str {
+        self.join.hashfn()
+    }
+
+    fn clone_box(&self) -> Box<dyn Receipt> {
+        Box::new(self.clone())
+    }
+
+    /// Every proof in the tree is verified. A tree deeper than
+    /// [MAX_JOIN_DEPTH] is never verified, and is estimated at `u64::MAX`.
+    fn verify_cost_estimate(&self) -> u64 {
+        if self.depth().is_err() {
+            return u64::MAX;
+        }
+        self.join
+            .verify_cost_estimate()
+            .saturating_add(self.left.verify_cost_estimate())
+            .saturating_add(self.right.verify_cost_estimate())
+    }
+}
+
+impl JoinedReceipt {
+    /// Returns the depth of this tree of joins, counting this join as depth 1.
+    ///
+    /// Returns [VerificationError::ReceiptFormatError] if the tree is deeper
+    /// than [MAX_JOIN_DEPTH]. The tree is walked without recursion, so this
+    /// can be called on a tree of any depth, e.g. one built in memory; a tree
+    /// deserialized with the `std` feature is already known to be no deeper
+    /// than [MAX_JOIN_DEPTH], see [JoinedReceipt].
+    pub fn depth(&self) -> Result<usize, VerificationError> {
+        let mut max_depth = 0;
+        let mut pending = Vec::from([(self, 1)]);
+        while let Some((joined, depth)) = pending.pop() {
+            if depth > MAX_JOIN_DEPTH {
+                return Err(VerificationError::ReceiptFormatError);
+            }
+            max_depth = max_depth.max(depth);
+            for side in [&joined.left, &joined.right] {
+                if let Some(side) = side.as_any().downcast_ref::<JoinedReceipt>() {
+                    pending.push((side, depth + 1));
+                }
+            }
+        }
+        Ok(max_depth)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::recursion::receipt::tagged_struct;
+    use alloc::vec::Vec;
+
+    use risc0_zkp::{core::digest::Digest, verify::VerificationError as ProofError};
+
+    use super::{JoinedReceipt, RollupReceipt, MAX_JOIN_DEPTH};
+    use crate::{
+        receipt::{
+            ExitCode, FakeReceipt, Receipt, ReceiptMetadata, SystemState, VerificationError,
+        },
+        recursion::receipt::tagged_struct,
+        serde::{from_slice, to_vec},
+        testutils::dev_mode_ctx,
+    };
+
     #[test]
     fn test_tagged_struct() {
         let digest1 = tagged_struct("foo", &[], &[1, 2013265920, 3]);
@@ -231,4 +483,119 @@ mod tests {
 
         println!("digest = {:?}", digest3);
     }
+
+    #[test]
+    fn joined_receipt() {
+        let state = |pc| SystemState {
+            pc,
+            merkle_root: Digest::default(),
+        };
+        let metadata = |pre, post, exit_code| ReceiptMetadata {
+            pre: state(pre),
+            post: state(post),
+            exit_code,
+            input: Digest::default(),
+            output: Digest::from([pre; 8]),
+        };
+        let join = |left: &ReceiptMetadata, right: &ReceiptMetadata| RollupReceipt {
+            seal: Vec::new(),
+            control_id: Digest::default(),
+            meta: ReceiptMetadata {
+                pre: left.pre.clone(),
+                post: right.post.clone(),
+                exit_code: right.exit_code,
+                input: left.input,
+                output: right.output,
+            },
+        };
+        let first = metadata(0x1000, 0x2000, ExitCode::SystemSplit);
+        let second = metadata(0x2000, 0x3000, ExitCode::Halted(0));
+        let receipt = JoinedReceipt {
+            join: join(&first, &second),
+            left: Box::new(FakeReceipt::new(first.clone())),
+            right: Box::new(FakeReceipt::new(second.clone())),
+        };
+        let ctx = dev_mode_ctx();
+
+        let joined = receipt.get_metadata().unwrap();
+        assert_eq!(joined.pre, first.pre);
+        assert_eq!(joined.post, second.post);
+        assert_eq!(joined.exit_code, ExitCode::Halted(0));
+        assert_eq!(joined.output, second.output);
+
+        // The metadata is consistent, so verification proceeds to the (empty)
+        // join seal.
+        assert_eq!(
+            receipt.verify_with_context(&ctx).unwrap_err(),
+            VerificationError::Proof(ProofError::ReceiptFormatError)
+        );
+
+        // The join proof must commit to the combined metadata.
+        let mut wrong = receipt.clone();
+        wrong.join.meta.post = state(0x4000);
+        assert!(matches!(
+            wrong.verify_with_context(&ctx).unwrap_err(),
+            VerificationError::MetadataMismatch { .. }
+        ));
+
+        // The right side must continue from the left.
+        let gap = metadata(0x2004, 0x3000, ExitCode::Halted(0));
+        let unlinked = JoinedReceipt {
+            join: join(&first, &gap),
+            left: Box::new(FakeReceipt::new(first.clone())),
+            right: Box::new(FakeReceipt::new(gap)),
+        };
+        assert_eq!(
+            unlinked.verify_with_context(&ctx).unwrap_err(),
+            VerificationError::ImageVerificationError
+        );
+        let swapped = JoinedReceipt {
+            join: join(&second, &first),
+            left: Box::new(FakeReceipt::new(second.clone())),
+            right: Box::new(FakeReceipt::new(first.clone())),
+        };
+        assert_eq!(
+            swapped.verify_with_context(&ctx).unwrap_err(),
+            VerificationError::UnexpectedExitCode
+        );
+
+        // Each side is verified.
+        assert_eq!(
+            receipt.verify().unwrap_err(),
+            VerificationError::DevModeDisabled
+        );
+
+        // The tree can't be nested arbitrarily deep.
+        assert_eq!(receipt.depth().unwrap(), 1);
+        let mut deep = receipt.clone();
+        for _ in 1..MAX_JOIN_DEPTH {
+            deep = JoinedReceipt {
+                join: join(&first, &second),
+                left: Box::new(FakeReceipt::new(first.clone())),
+                right: Box::new(deep),
+            };
+        }
+        assert_eq!(deep.depth().unwrap(), MAX_JOIN_DEPTH);
+        let too_deep = JoinedReceipt {
+            join: join(&first, &second),
+            left: Box::new(deep.clone()),
+            right: Box::new(FakeReceipt::new(second.clone())),
+        };
+        assert_eq!(
+            too_deep.depth().unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(
+            too_deep.verify_with_context(&ctx).unwrap_err(),
+            VerificationError::ReceiptFormatError
+        );
+        assert_eq!(too_deep.verify_cost_estimate(), u64::MAX);
+
+        // A tree that is too deep is rejected as it is deserialized.
+        let deep: Box<dyn Receipt> = Box::new(deep);
+        let decoded: Box<dyn Receipt> = from_slice(&to_vec(&deep).unwrap()).unwrap();
+        assert!(decoded == deep);
+        let too_deep: Box<dyn Receipt> = Box::new(too_deep);
+        assert!(from_slice::<Box<dyn Receipt>, _>(&to_vec(&too_deep).unwrap()).is_err());
+    }
 }
@@ -21,6 +21,10 @@ use serde::de::{DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
 use super::err::{Error, Result};
 use crate::align_up;
 
+// The most bytes of a byte string allocated ahead of reading them; must be a
+// multiple of the word size.
+const BYTES_CHUNK_SIZE: usize = 64 * 1024;
+
 /// A reader for reading streams with serialized word-based data
 pub trait WordRead {
     /// Fill the given buffer with words from input.  Returns an error if EOF
@@ -208,6 +212,27 @@ impl<'de, R: WordRead + 'de> Deserializer<'de, R> {
         let high = self.try_take_word()? as u64;
         Ok(low | high << 32)
     }
+
+    // Reads a length-prefixed byte string. The length is untrusted, so the
+    // buffer is grown as the bytes are read rather than allocated up front;
+    // otherwise a crafted length could force an allocation of up to 4 GiB, or
+    // overflow the capacity of a `Vec` on 32-bit targets.
+    fn try_take_bytes(&mut self) -> Result<Vec<u8>> {
+        let len_bytes = self.try_take_word()? as usize;
+        let mut bytes: Vec<u8> = Vec::new();
+        while bytes.len() < len_bytes {
+            // Every chunk but the last is a whole number of words, so only the
+            // last is followed by padding.
+            let start = bytes.len();
+            let end = start + (len_bytes - start).min(BYTES_CHUNK_SIZE);
+            // TODO: Can we use MaybeUninit here instead of zeroing out?
+            // The documentation for sys::io::Read implies that it's not
+            // safe; is there another way to not do double writes here?
+            bytes.resize(end, 0);
+            self.reader.read_padded_bytes(&mut bytes[start..])?;
+        }
+        Ok(bytes)
+    }
 }
 
 impl<'de, 'a, R: WordRead + 'de> serde::Deserializer<'de> for &'a mut Deserializer<'de, R> {
@@ -318,13 +343,7 @@ impl<'de, 'a, R: WordRead + 'de> serde::Deserializer<'de> for &'a mut Deserializ
     where
         V: Visitor<'de>,
     {
-        let len_bytes = self.try_take_word()? as usize;
-        let mut bytes: Vec<u8> = Vec::with_capacity(len_bytes);
-        // TODO: Can we use MaybeUninit here instead of zeroing out?
-        // The documentation for sys::io::Read implies that it's not
-        // safe; is there another way to not do double writes here?
-        bytes.resize(len_bytes, 0);
-        self.reader.read_padded_bytes(&mut bytes)?;
+        let bytes = self.try_take_bytes()?;
         visitor.visit_string(String::from_utf8(bytes).map_err(|_| Error::DeserializeBadChar)?)
     }
 
@@ -339,13 +358,7 @@ impl<'de, 'a, R: WordRead + 'de> serde::Deserializer<'de> for &'a mut Deserializ
     where
         V: Visitor<'de>,
     {
-        let len_bytes = self.try_take_word()? as usize;
-        let mut bytes: Vec<u8> = Vec::with_capacity(len_bytes);
-        // TODO: Can we use MaybeUninit here instead of zeroing out?
-        // The documentation for sys::io::Read implies that it's not
-        // safe; is there another way to not do double writes here?
-        bytes.resize(len_bytes, 0);
-        self.reader.read_padded_bytes(&mut bytes)?;
+        let bytes = self.try_take_bytes()?;
         visitor.visit_byte_buf(bytes)
     }
 
@@ -572,4 +585,23 @@ mod tests {
         };
         assert_eq!(expected, from_slice(&words).unwrap());
     }
+
+    #[test]
+    fn test_str_oversized_len() {
+        // A declared length far beyond the input fails without allocating it.
+        let words = [u32::MAX, 0x00636261];
+        assert_eq!(
+            from_slice::<String, _>(&words).unwrap_err(),
+            Error::DeserializeUnexpectedEnd
+        );
+
+        // Strings longer than a chunk are still read in full.
+        let long: String = "abcdefg".repeat(BYTES_CHUNK_SIZE / 3);
+        let encoded = crate::serde::to_vec(&long).unwrap();
+        assert_eq!(from_slice::<String, _>(&encoded).unwrap(), long);
+        assert_eq!(
+            from_slice::<String, _>(&encoded[..encoded.len() - 1]).unwrap_err(),
+            Error::DeserializeUnexpectedEnd
+        );
+    }
 }
@@ -33,6 +33,8 @@ pub enum Error {
     NotSupported,
     /// The serialize buffer is full
     SerializeBufferFull,
+    /// A length was too large to be serialized as a u32
+    SerializeTooLarge,
 }
 
 /// A Result type for `risc0_zkvm::serde` operations that can fail
@@ -49,6 +51,7 @@ impl Display for Error {
             Self::DeserializeUnexpectedEnd => "Unexpected end during deserialization",
             Self::NotSupported => "Not supported",
             Self::SerializeBufferFull => "The serialize buffer is full",
+            Self::SerializeTooLarge => "A length was too large to be serialized as a u32",
         })
     }
 }
@@ -91,6 +91,12 @@ where
     Ok(vec)
 }
 
+// Returns the u32 length prefix for a string, byte string, sequence or map of
+// `len` elements.
+fn len_prefix(len: usize) -> Result<u32> {
+    u32::try_from(len).or(Err(Error::SerializeTooLarge))
+}
+
 /// Enables serializing to a stream
 pub struct Serializer<W: WordWrite> {
     stream: W,
@@ -178,7 +184,7 @@ impl<'a, W: WordWrite> serde::ser::Serializer for &'a mut Serializer<W> {
 
     fn serialize_str(self, v: &str) -> Result<()> {
         let bytes = v.as_bytes();
-        self.serialize_u32(bytes.len() as u32)?;
+        self.serialize_u32(len_prefix(bytes.len())?)?;
         self.stream.write_padded_bytes(bytes)
     }
 
@@ -192,7 +198,7 @@ impl<'a, W: WordWrite> serde::ser::Serializer for &'a mut Serializer<W> {
     // B) Use the experimental Rust specialization
     //    features.
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        self.serialize_u32(v.len() as u32)?;
+        self.serialize_u32(len_prefix(v.len())?)?;
         self.stream.write_padded_bytes(v)
     }
 
@@ -249,7 +255,7 @@ impl<'a, W: WordWrite> serde::ser::Serializer for &'a mut Serializer<W> {
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
         match len {
             Some(val) => {
-                self.serialize_u32(val.try_into().unwrap())?;
+                self.serialize_u32(len_prefix(val)?)?;
                 Ok(self)
             }
             None => Err(Error::NotSupported),
@@ -282,7 +288,7 @@ impl<'a, W: WordWrite> serde::ser::Serializer for &'a mut Serializer<W> {
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
         match len {
             Some(val) => {
-                self.serialize_u32(val.try_into().unwrap())?;
+                self.serialize_u32(len_prefix(val)?)?;
                 Ok(self)
             }
             None => Err(Error::NotSupported),
@@ -497,4 +503,22 @@ mod tests {
         };
         assert_eq!(expected, to_vec(&input).unwrap().as_slice());
     }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_oversized_len() {
+        use serde::Serializer as _;
+
+        let mut vec: Vec<u32> = Vec::new();
+        let mut serializer = Serializer::new(&mut vec);
+        assert!(matches!(
+            serializer.serialize_seq(Some(u32::MAX as usize + 1)),
+            Err(Error::SerializeTooLarge)
+        ));
+        assert!(matches!(
+            serializer.serialize_map(Some(usize::MAX)),
+            Err(Error::SerializeTooLarge)
+        ));
+        assert!(vec.is_empty());
+    }
 }
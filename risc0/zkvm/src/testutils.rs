@@ -17,8 +17,14 @@ use core::mem;
 use crypto_bigint::{
     rand_core::CryptoRngCore, CheckedMul, Encoding, NonZero, Random, RandomMod, U256, U512,
 };
+use risc0_zkp::core::digest::Digest;
 use risc0_zkvm_platform::syscall::bigint;
 
+use crate::receipt::{
+    compute_image_id, ExitCode, FakeReceipt, JournalHash, ReceiptMetadata, SessionReceipt,
+    SystemState, VerifierContext,
+};
+
 // Convert to little-endian u32 array. Only reinterprettation on LE machines.
 fn bigint_to_arr(num: &U256) -> [u32; bigint::WIDTH_WORDS] {
     let mut arr: [u32; bigint::WIDTH_WORDS] = bytemuck::cast(num.to_le_bytes());
@@ -126,3 +132,41 @@ pub fn generate_bigint_test_cases(
     cases.extend((0..rand_count).map(|_| BigIntTestCase::sample(rng)));
     cases
 }
+
+/// The metadata of a segment that halts where it starts, at 0x1000 in an empty
+/// memory image, committing to `journal`.
+pub fn fake_metadata(journal: &[u8]) -> ReceiptMetadata {
+    let state = SystemState {
+        pc: 0x1000,
+        merkle_root: Digest::default(),
+    };
+    ReceiptMetadata {
+        pre: state.clone(),
+        post: state,
+        exit_code: ExitCode::Halted(0),
+        input: Digest::default(),
+        output: JournalHash::Sha256.digest(journal),
+    }
+}
+
+/// A receipt of a single [FakeReceipt] with [fake_metadata], carrying
+/// `journal`. It verifies against [fake_image_id] with [dev_mode_ctx].
+pub fn fake_receipt(journal: &[u8]) -> SessionReceipt {
+    SessionReceipt::new(
+        vec![Box::new(FakeReceipt::new(fake_metadata(journal)))],
+        journal.to_vec(),
+    )
+}
+
+/// The image ID that [fake_metadata] starts from.
+pub fn fake_image_id() -> Digest {
+    compute_image_id(&Digest::default(), 0x1000)
+}
+
+/// A context that accepts [FakeReceipt]s.
+pub fn dev_mode_ctx() -> VerifierContext {
+    VerifierContext {
+        dev_mode: true,
+        ..Default::default()
+    }
+}